@@ -1,4 +1,6 @@
 use criterion::{Criterion, criterion_group, criterion_main};
+#[cfg(feature = "parallel_sim")]
+use sandsim::component::sim_thread::SimThread;
 use sandsim::component::{
     grid::Grid,
     particles::{
@@ -14,21 +16,13 @@ use sandsim::component::{
 };
 
 fn fill_grid_mixed(g: &mut Grid, (x, y): (usize, usize)) {
-    for y in 0..y / 3 {
-        for x in 0..x {
-            g.spawn_particle((x, y), Particle::from(Sand::new()));
-        }
-    }
-    for y in y / 3..y {
-        for x in 0..x {
-            g.spawn_particle((x, y), Particle::from(Water::new()));
-        }
-    }
-    for y in 2 * y / 3..y {
-        for x in 0..x {
-            g.spawn_particle((x, y), Particle::from(Salt::new()));
-        }
-    }
+    let rows = |r: std::ops::Range<usize>, particle: Particle| {
+        r.flat_map(move |y| (0..x).map(move |x| (x, y)))
+            .map(move |position| (position, particle.clone()))
+    };
+    g.spawn_many(rows(0..y / 3, Particle::from(Sand::new())));
+    g.spawn_many(rows(y / 3..y, Particle::from(Water::new())));
+    g.spawn_many(rows(2 * y / 3..y, Particle::from(Salt::new())));
 }
 
 fn criterion_benchmark(c: &mut Criterion) {
@@ -213,6 +207,31 @@ fn criterion_benchmark(c: &mut Criterion) {
             g.draw_grid(&mut image);
         });
     });
+
+    c.bench_function("update and draw grid in frame", |b| {
+        let mut g = Grid::new(x, y);
+        let mut image = Grid::create_output_frame(x, y);
+        fill_grid_mixed(&mut g, (x, y));
+        b.iter(|| {
+            g.update_grid();
+            g.draw_grid(&mut image);
+        });
+    });
+
+    #[cfg(feature = "parallel_sim")]
+    c.bench_function("update and draw grid on sim thread", |b| {
+        let mut g = Grid::new(x, y);
+        let mut image = Grid::create_output_frame(x, y);
+        fill_grid_mixed(&mut g, (x, y));
+        let sim = SimThread::spawn(g, std::time::Duration::ZERO);
+        let mut mirror = Grid::new(x, y);
+        b.iter(|| {
+            while !sim.read_snapshot(&mut mirror) {
+                std::thread::yield_now();
+            }
+            mirror.draw_grid(&mut image);
+        });
+    });
 }
 
 criterion_group!(benches, criterion_benchmark);
@@ -0,0 +1,65 @@
+//! Runs a simulation without Bevy's app loop and writes the settled grid to a PNG.
+//!
+//! cargo run --example headless --features export -- [OUTPUT.png] [SCENE.png]
+
+use std::{env, process::ExitCode};
+
+use bevy::{asset::RenderAssetUsages, image::Image};
+use sandsim::component::{
+    grid::Grid,
+    particles::{
+        particle::{Particle, ParticleKind},
+        rock::Rock,
+        salt::Salt,
+        sand::Sand,
+        water::Water,
+    },
+};
+
+const MAX_CYCLES: u32 = 2000;
+
+fn default_scene() -> Grid {
+    let (width, height) = (120, 80);
+    let mut g = Grid::new(width, height);
+    g.spawn_many((0..width).map(|x| ((x, height - 1), Particle::from(Rock::new()))));
+    for x in 0..width / 3 {
+        g.spawn_brush(
+            (width / 3 + x, 10),
+            7,
+            Some(&ParticleKind::from(Sand::new())),
+        );
+    }
+    g.spawn_brush((width / 4, 20), 15, Some(&ParticleKind::from(Salt::new())));
+    g.flood_to_level(height - 12, ParticleKind::from(Water::new()));
+    g
+}
+
+fn load_scene(path: &str) -> Result<Grid, Box<dyn std::error::Error>> {
+    let image = Image::from_dynamic(image::open(path)?, true, RenderAssetUsages::default());
+    Ok(Grid::from_image(&image)?)
+}
+
+fn main() -> ExitCode {
+    let mut args = env::args().skip(1);
+    let output = args.next().unwrap_or_else(|| "headless.png".to_string());
+    let mut g = match args.next() {
+        Some(scene) => match load_scene(&scene) {
+            Ok(g) => g,
+            Err(error) => {
+                eprintln!("could not load {scene}: {error}");
+                return ExitCode::FAILURE;
+            }
+        },
+        None => default_scene(),
+    };
+
+    let cycles = g.run_until_settled(MAX_CYCLES);
+    println!("settled after {cycles} cycles");
+
+    if let Err(error) = g.export_png(&output) {
+        eprintln!("could not write {output}: {error}");
+        return ExitCode::FAILURE;
+    }
+    println!("wrote {output}");
+    ExitCode::SUCCESS
+}
@@ -2,12 +2,14 @@ use bevy::window::{Window, WindowPlugin, WindowResolution};
 use bevy::{DefaultPlugins, app::App, image::ImagePlugin, prelude::PluginGroup, utils::default};
 use bevy_embedded_assets::{EmbeddedAssetPlugin, PluginMode};
 use sandsim::component::{
+    brush_config::BrushConfigFile,
     grid_plugin::{ConfigResource, GridPlugin},
     render::RenderSimPlugin,
 };
 
 fn main() {
     App::new()
+        .insert_resource(BrushConfigFile("brush.cfg".into()))
         .add_plugins((
             EmbeddedAssetPlugin {
                 mode: PluginMode::ReplaceDefault,
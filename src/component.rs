@@ -1,5 +1,7 @@
+pub mod brush_config;
 pub mod grid;
 pub mod grid_plugin;
 pub mod macros;
 pub mod particles;
 pub mod render;
+pub mod sim_thread;
@@ -0,0 +1,156 @@
+use std::{fs, io, path::PathBuf};
+
+use bevy::ecs::resource::Resource;
+
+use crate::component::grid::PaintMode;
+
+#[derive(Resource, Clone, Debug)]
+pub struct BrushConfigFile(pub PathBuf);
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BrushConfig {
+    pub particle_kind_id: Option<u8>,
+    pub size: usize,
+    pub paint_mode: PaintMode,
+    pub erase_only: bool,
+    pub mix_ratio: f32,
+}
+
+impl Default for BrushConfig {
+    fn default() -> Self {
+        Self {
+            particle_kind_id: Some(0),
+            size: 8,
+            paint_mode: PaintMode::FillEmpty,
+            erase_only: false,
+            mix_ratio: 0.5,
+        }
+    }
+}
+
+fn paint_mode_name(paint_mode: PaintMode) -> &'static str {
+    match paint_mode {
+        PaintMode::FillEmpty => "fill_empty",
+        PaintMode::Replace => "replace",
+        PaintMode::Behind => "behind",
+    }
+}
+
+fn parse_paint_mode(name: &str) -> Option<PaintMode> {
+    match name {
+        "fill_empty" => Some(PaintMode::FillEmpty),
+        "replace" => Some(PaintMode::Replace),
+        "behind" => Some(PaintMode::Behind),
+        _ => None,
+    }
+}
+
+impl BrushConfig {
+    pub fn to_config_string(&self) -> String {
+        let particle_kind = self
+            .particle_kind_id
+            .map(|id| id.to_string())
+            .unwrap_or_default();
+        format!(
+            "particle_kind={particle_kind}\nsize={}\npaint_mode={}\nerase_only={}\nmix_ratio={}\n",
+            self.size,
+            paint_mode_name(self.paint_mode),
+            self.erase_only,
+            self.mix_ratio
+        )
+    }
+
+    pub fn parse(text: &str) -> Option<Self> {
+        let mut config = Self::default();
+        for line in text.lines().filter(|l| !l.trim().is_empty()) {
+            let (key, value) = line.split_once('=')?;
+            match (key.trim(), value.trim()) {
+                ("particle_kind", "") => config.particle_kind_id = None,
+                ("particle_kind", v) => config.particle_kind_id = Some(v.parse().ok()?),
+                ("size", v) => config.size = v.parse().ok()?,
+                ("paint_mode", v) => config.paint_mode = parse_paint_mode(v)?,
+                ("erase_only", v) => config.erase_only = v.parse().ok()?,
+                ("mix_ratio", v) => config.mix_ratio = v.parse().ok()?,
+                _ => (),
+            }
+        }
+        Some(config)
+    }
+
+    pub fn load(file: &BrushConfigFile) -> Option<Self> {
+        Self::parse(&fs::read_to_string(&file.0).ok()?)
+    }
+
+    pub fn save(&self, file: &BrushConfigFile) -> io::Result<()> {
+        fs::write(&file.0, self.to_config_string())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use super::*;
+
+    #[test]
+    fn test_brush_config_round_trips_through_its_text_form() {
+        let config = BrushConfig {
+            particle_kind_id: Some(6),
+            size: 3,
+            paint_mode: PaintMode::Replace,
+            erase_only: false,
+            mix_ratio: 0.25,
+        };
+
+        assert_eq!(
+            Some(config.clone()),
+            BrushConfig::parse(&config.to_config_string())
+        );
+        let behind = BrushConfig {
+            paint_mode: PaintMode::Behind,
+            ..BrushConfig::default()
+        };
+        assert_eq!(
+            Some(behind.clone()),
+            BrushConfig::parse(&behind.to_config_string())
+        );
+        let eraser = BrushConfig {
+            particle_kind_id: None,
+            ..BrushConfig::default()
+        };
+        assert_eq!(
+            Some(eraser.clone()),
+            BrushConfig::parse(&eraser.to_config_string())
+        );
+    }
+
+    #[test]
+    fn test_brush_config_rejects_malformed_values_and_ignores_unknown_keys() {
+        assert_eq!(None, BrushConfig::parse("size=big"));
+        assert_eq!(None, BrushConfig::parse("paint_mode=sideways"));
+        assert_eq!(
+            Some(BrushConfig {
+                size: 2,
+                ..BrushConfig::default()
+            }),
+            BrushConfig::parse("size=2\nshape=circle\n")
+        );
+    }
+
+    #[test]
+    fn test_brush_config_saves_and_loads_from_a_file() {
+        let file = BrushConfigFile(
+            std::env::temp_dir().join(format!("sandsim-brush-config-{}.cfg", std::process::id())),
+        );
+        let config = BrushConfig {
+            size: 12,
+            ..BrushConfig::default()
+        };
+
+        config.save(&file).unwrap();
+        let loaded = BrushConfig::load(&file);
+        let _ = fs::remove_file(&file.0);
+
+        assert_eq!(Some(config), loaded);
+    }
+}
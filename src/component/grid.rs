@@ -1,21 +1,195 @@
-use std::{collections::HashMap, fmt};
+use std::{
+    collections::{BTreeMap, HashMap},
+    fmt, fs, io, iter,
+    ops::{Index, IndexMut},
+    path::Path,
+};
 
 use bevy::{
     asset::RenderAssetUsages,
-    color::{Color, ColorToPacked, palettes::css},
+    color::{Color, ColorToPacked, Hsva, palettes::css},
     ecs::component::Component,
     image::Image,
+    math::{URect, UVec3},
     render::render_resource::{Extent3d, TextureDimension, TextureFormat},
 };
 
-use super::particles::particle::{Particle, ParticleKind};
+#[cfg(feature = "particle_ids")]
+use super::particles::particle::ParticleId;
+use super::particles::{
+    acid::Acid,
+    bedrock::Bedrock,
+    cryo::Cryo,
+    crystal::Crystal,
+    drain::Drain,
+    dust::Dust,
+    dye::Dye,
+    fuse::Fuse,
+    gate::Gate,
+    generator::Generator,
+    glass::Glass,
+    ice::Ice,
+    magnet::Magnet,
+    membrane::Membrane,
+    metal::Metal,
+    particle::{ORTHOGONAL_NEIGHBORS, Particle, ParticleKind},
+    registry::Custom,
+    reservoir::Reservoir,
+    rock::Rock,
+    rope::Rope,
+    salt::Salt,
+    sand::Sand,
+    slime::Slime,
+    steam::Steam,
+    sticky::Sticky,
+    tap::Tap,
+    teleporter::Teleporter,
+    virus::Virus,
+    water::Water,
+    wire::Wire,
+};
 
+#[derive(Debug, PartialEq)]
 pub enum GridError {
     OutOfBound,
+    DimensionMismatch,
+    InvalidFormat,
+    ChecksumMismatch,
+    WindowCoverage,
+    Io(io::ErrorKind),
+}
+
+impl fmt::Display for GridError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            GridError::OutOfBound => write!(f, "position is out of the grid bounds"),
+            GridError::DimensionMismatch => write!(f, "dimensions do not match the grid"),
+            GridError::InvalidFormat => write!(f, "invalid grid format"),
+            GridError::ChecksumMismatch => write!(f, "checksum does not match the grid"),
+            GridError::WindowCoverage => {
+                write!(f, "windows must cover every cell of the grid exactly once")
+            }
+            GridError::Io(kind) => write!(f, "could not access the grid file: {kind}"),
+        }
+    }
+}
+
+impl std::error::Error for GridError {}
+
+impl From<io::Error> for GridError {
+    fn from(error: io::Error) -> Self {
+        GridError::Io(error.kind())
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct Stamp {
+    pub cells: Vec<Cell>,
+    pub size: (usize, usize),
+}
+
+/// One cell's value before and after a cycle, so the change can be undone.
+#[derive(Clone, Debug, PartialEq)]
+pub struct CellDelta {
+    pub index: usize,
+    pub old: Cell,
+    pub new: Cell,
+}
+
+/// The cells a single `update_grid` call changed.
+#[derive(Clone, Debug, PartialEq)]
+pub struct GridDelta {
+    pub cycle: (u32, u32),
+    pub cells: Vec<CellDelta>,
+}
+
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct RegionStats {
+    pub counts: BTreeMap<String, usize>,
+    pub average_velocity: (f32, f32),
+    pub velocity_histogram: [usize; VELOCITY_HISTOGRAM_BUCKETS],
+}
+
+impl fmt::Display for RegionStats {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (kind, count) in &self.counts {
+            write!(f, "{kind}: {count} ")?;
+        }
+        write!(
+            f,
+            "| velocity: ({:.0}, {:.0})",
+            self.average_velocity.0, self.average_velocity.1
+        )
+    }
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub enum SimEvent {
+    Rest { position: (usize, usize) },
+    Dissolve { position: (usize, usize) },
+    Drain { position: (usize, usize) },
+}
+
+struct EventCallback(Box<dyn FnMut(SimEvent) + Send + Sync>);
+
+impl fmt::Debug for EventCallback {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str("EventCallback")
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum BrushShape {
+    Circle,
+    Square,
+}
+
+/// How a brush treats the particles already under it: `FillEmpty` leaves
+/// them alone, `Replace` overwrites them and `Behind` uses them as a mask
+/// that nothing the brush does, erasing included, can change.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum PaintMode {
+    #[default]
+    FillEmpty,
+    Replace,
+    Behind,
+}
+
+/// Where a blocked liquid tries to go next: `Diagonal` slides down a
+/// diagonal before spreading sideways, `Orthogonal` only spreads sideways.
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum SpreadMode {
+    #[default]
+    Diagonal,
+    Orthogonal,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum Edge {
+    Top,
+    Bottom,
+    Left,
+    Right,
+}
+
+#[derive(Clone, Debug, PartialEq)]
+pub struct BoundaryFlow {
+    pub edge: Edge,
+    pub kind: ParticleKind,
+    pub rate: usize,
 }
 
 pub const BACKGROUND_COLOR: bevy::prelude::Color = Color::srgb(0.82, 0.93, 1.);
 
+pub const VELOCITY_HISTOGRAM_BUCKETS: usize = 8;
+
+/// Widest row `from_rle` accepts, so a huge run length can't exhaust memory.
+const MAX_RLE_WIDTH: usize = 1 << 16;
+/// Furthest a sprayed grain lands from its brush cell, reached at the
+/// largest spray velocity.
+const SPRAY_REACH: i32 = 4;
+const BAYER_4X4: [[u8; 4]; 4] = [[0, 8, 2, 10], [12, 4, 14, 6], [3, 11, 1, 9], [15, 7, 13, 5]];
+
 #[derive(Clone, PartialEq, Debug)]
 pub enum RowUpdateDirection {
     Forward = 0,
@@ -49,13 +223,23 @@ impl Cell {
     }
 }
 
-#[derive(Debug)]
+#[derive(Clone, Debug)]
 pub struct Random {
     row_update_direction: fn(r: &mut Random) -> RowUpdateDirection,
     particle_seed: fn(r: &mut Random) -> u8,
     particle_seed_with_cycle: fn(&mut Random) -> u8,
     horizontal_velocity_probability: fn(r: &mut Random) -> i16,
     vertical_velocity_probability: fn(r: &mut Random) -> i16,
+    cohesion_probability: fn(r: &mut Random) -> f32,
+    mix_probability: fn(r: &mut Random) -> f32,
+    spray_velocity: fn(r: &mut Random) -> (i16, i16),
+    spray_direction: fn(r: &mut Random) -> bool,
+    repose_probability: fn(r: &mut Random) -> u8,
+    drift_probability: fn(r: &mut Random) -> u8,
+    jitter_probability: fn(r: &mut Random) -> f32,
+    reaction_probability: fn(r: &mut Random) -> f32,
+    rain_column: fn(r: &mut Random) -> usize,
+    update_order: fn(r: &mut Random, positions: &mut [(usize, usize)]),
     rng: fastrand::Rng,
     cycle: u32,
 }
@@ -66,6 +250,7 @@ struct Window {
     end: (usize, usize),
     cycle: u32,
     threshold: u32,
+    dimmed: bool,
 }
 
 impl Window {
@@ -75,6 +260,7 @@ impl Window {
             end,
             cycle: 0,
             threshold: 0,
+            dimmed: false,
         }
     }
 
@@ -97,6 +283,9 @@ pub struct WindowGrid {
     windows: HashMap<(usize, usize), Window>,
     window_width: usize,
     window_height: usize,
+    /// Window key of every cell, in row order; empty for a uniform tiling.
+    cell_windows: Vec<(usize, usize)>,
+    width: usize,
 }
 
 #[derive(Component, Debug)]
@@ -109,12 +298,39 @@ pub struct Grid {
     random: Random,
     initial_particle_velocity: (i16, i16),
     window_grid: WindowGrid,
+    cohesion: f32,
+    surface_jitter: f32,
+    liquid_spread: SpreadMode,
+    reaction_rate: f32,
+    seed_variation: bool,
+    deterministic_row_order: bool,
+    shuffle_updates: bool,
+    windowing: bool,
+    sleep_dim_after: Option<u32>,
+    dither: bool,
+    border: Option<(Color, usize)>,
+    emission_budget: Option<usize>,
+    emitted: usize,
+    max_particles: Option<usize>,
+    particle_count: usize,
+    boundary_flows: Vec<BoundaryFlow>,
+    events: Vec<SimEvent>,
+    capacity_hint: usize,
+    update_positions: Vec<(usize, usize)>,
+    equilibrium_cache: HashMap<u32, Vec<Cell>>,
+    event_callback: Option<EventCallback>,
+    #[cfg(feature = "particle_ids")]
+    next_particle_id: u64,
 }
 
 pub trait GridAccess {
     fn particle_seed(&mut self) -> u8;
     fn horizontal_velocity_probability(&mut self) -> i16;
     fn vertical_velocity_probability(&mut self) -> i16;
+    fn cohesion_probability(&mut self) -> f32;
+    fn repose_probability(&mut self) -> u8;
+    fn drift_probability(&mut self) -> u8;
+    fn jitter_probability(&mut self) -> f32;
     fn get_neighbor_index(
         &self,
         position: (usize, usize),
@@ -129,18 +345,49 @@ pub trait GridAccess {
     fn get_cell_mut(&mut self, index: usize) -> &mut Cell;
     fn get_cells(&self) -> &Vec<Cell>;
     fn to_index(&self, position: (usize, usize)) -> usize;
+    fn to_position(&self, index: usize) -> (usize, usize);
     fn swap_particles(&mut self, index: usize, next_location_index: usize);
     fn is_empty(&self, position: (usize, usize), offset: (i32, i32)) -> Option<usize>;
     fn is_simulated(&self, c: &Cell) -> bool;
     fn cycle(&self) -> u32;
     fn get_particle_initial_velocity(&self) -> (i16, i16);
     fn activate_window(&mut self, position: (usize, usize));
+    fn cohesion(&self) -> f32;
+    fn surface_jitter(&self) -> f32;
+    fn liquid_spread(&self) -> SpreadMode;
+    fn reacts(&mut self) -> bool;
+    fn push_event(&mut self, event: SimEvent);
+    fn try_emit(&mut self) -> bool;
+    /// Puts `particle` in the cell at `index` and keeps the live particle
+    /// count in step. Filling an empty cell is refused once the grid's
+    /// particle cap is reached; returns whether the particle was placed.
+    fn place_particle(&mut self, index: usize, particle: Particle) -> bool;
+    /// Empties the cell at `index` and keeps the live particle count in step.
+    fn remove_particle(&mut self, index: usize) -> Option<Particle>;
+
+    /// How many of the eight cells around `position` hold a particle that
+    /// matches `predicate`.
+    fn count_neighbors(
+        &self,
+        position: (usize, usize),
+        predicate: impl Fn(&Particle) -> bool,
+    ) -> usize
+    where
+        Self: Sized,
+    {
+        (-1..=1)
+            .flat_map(|yo| (-1..=1).map(move |xo| (xo, yo)))
+            .filter(|&offset| offset != (0, 0))
+            .filter_map(|offset| self.get_neighbor_index(position, offset).ok())
+            .filter(|&i| self.get_cell(i).particle.as_ref().is_some_and(&predicate))
+            .count()
+    }
 }
 
 impl fmt::Display for Cell {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         match &self.particle {
-            Some(p) => match p.kind {
+            Some(p) => match &p.kind {
                 ParticleKind::Sand(_) => write!(f, "s"),
                 ParticleKind::Water(_) => write!(f, "w"),
                 ParticleKind::Salt(_) => write!(f, "S"),
@@ -148,6 +395,35 @@ impl fmt::Display for Cell {
                 ParticleKind::Drain(_) => write!(f, "d"),
                 ParticleKind::Tap(_) => write!(f, "t"),
                 ParticleKind::Acid(_) => write!(f, "a"),
+                ParticleKind::Wire(w) if w.is_on() => write!(f, "I"),
+                ParticleKind::Wire(_) => write!(f, "i"),
+                ParticleKind::Generator(_) => write!(f, "g"),
+                ParticleKind::Slime(_) => write!(f, "l"),
+                ParticleKind::Virus(_) => write!(f, "v"),
+                ParticleKind::Reservoir(_) => write!(f, "R"),
+                ParticleKind::Teleporter(_) => write!(f, "T"),
+                ParticleKind::Crystal(_) => write!(f, "c"),
+                ParticleKind::Dust(_) => write!(f, "D"),
+                ParticleKind::Bedrock(_) => write!(f, "B"),
+                ParticleKind::Cryo(_) => write!(f, "C"),
+                ParticleKind::Ice(_) => write!(f, "e"),
+                ParticleKind::Metal(_) => write!(f, "m"),
+                ParticleKind::Magnet(_) => write!(f, "M"),
+                ParticleKind::Membrane(_) => write!(f, "n"),
+                ParticleKind::Rope(_) => write!(f, "p"),
+                ParticleKind::Dye(_) => write!(f, "y"),
+                ParticleKind::Fuse(fuse) if fuse.lit => write!(f, "F"),
+                ParticleKind::Fuse(_) => write!(f, "f"),
+                ParticleKind::Gate(gate) if gate.is_open() => write!(f, "H"),
+                ParticleKind::Gate(_) => write!(f, "h"),
+                ParticleKind::Glass(_) => write!(f, "G"),
+                ParticleKind::Sticky(sticky) if sticky.stuck => write!(f, "K"),
+                ParticleKind::Sticky(_) => write!(f, "k"),
+                ParticleKind::Steam(_) => write!(f, "W"),
+                ParticleKind::Custom(c) => match c.descriptor() {
+                    Some(d) => write!(f, "{}", d.glyph),
+                    None => write!(f, "?"),
+                },
             },
             None => write!(f, "-"),
         }
@@ -169,6 +445,37 @@ impl fmt::Display for Grid {
     }
 }
 
+impl Index<(usize, usize)> for Grid {
+    type Output = Cell;
+
+    fn index(&self, (x, y): (usize, usize)) -> &Cell {
+        assert!(
+            x < self.width && y < self.height,
+            "position ({x}, {y}) is out of the {}x{} grid",
+            self.width,
+            self.height
+        );
+        &self.cells[self.to_index((x, y))]
+    }
+}
+
+/// Writing through `grid[(x, y)]` does not wake the surrounding windows or
+/// mark the cell for redrawing; call `Grid::touch` on the position afterwards.
+/// Particles written straight into a cell this way are not counted toward
+/// `with_max_particles`; use `spawn_particle` and `despawn_particle` for that.
+impl IndexMut<(usize, usize)> for Grid {
+    fn index_mut(&mut self, (x, y): (usize, usize)) -> &mut Cell {
+        assert!(
+            x < self.width && y < self.height,
+            "position ({x}, {y}) is out of the {}x{} grid",
+            self.width,
+            self.height
+        );
+        let index = self.to_index((x, y));
+        &mut self.cells[index]
+    }
+}
+
 impl GridAccess for Grid {
     fn get_cell(&self, index: usize) -> &Cell {
         &self.cells[index]
@@ -182,6 +489,10 @@ impl GridAccess for Grid {
         y * self.width + x
     }
 
+    fn to_position(&self, index: usize) -> (usize, usize) {
+        (index % self.width, index / self.width)
+    }
+
     fn get_neighbor_position(
         &self,
         (x, y): (usize, usize),
@@ -220,6 +531,22 @@ impl GridAccess for Grid {
         (self.random.vertical_velocity_probability)(&mut self.random)
     }
 
+    fn cohesion_probability(&mut self) -> f32 {
+        (self.random.cohesion_probability)(&mut self.random)
+    }
+
+    fn repose_probability(&mut self) -> u8 {
+        (self.random.repose_probability)(&mut self.random)
+    }
+
+    fn drift_probability(&mut self) -> u8 {
+        (self.random.drift_probability)(&mut self.random)
+    }
+
+    fn jitter_probability(&mut self) -> f32 {
+        (self.random.jitter_probability)(&mut self.random)
+    }
+
     fn get_cells(&self) -> &Vec<Cell> {
         &self.cells
     }
@@ -256,6 +583,61 @@ impl GridAccess for Grid {
         self.initial_particle_velocity
     }
 
+    fn cohesion(&self) -> f32 {
+        self.cohesion
+    }
+
+    fn surface_jitter(&self) -> f32 {
+        self.surface_jitter
+    }
+
+    fn liquid_spread(&self) -> SpreadMode {
+        self.liquid_spread
+    }
+
+    fn reacts(&mut self) -> bool {
+        1. <= self.reaction_rate
+            || (self.random.reaction_probability)(&mut self.random) < self.reaction_rate
+    }
+
+    fn push_event(&mut self, event: SimEvent) {
+        if let Some(EventCallback(callback)) = &mut self.event_callback {
+            callback(event.clone());
+        }
+        self.events.push(event);
+    }
+
+    fn try_emit(&mut self) -> bool {
+        if self
+            .emission_budget
+            .is_some_and(|budget| self.emitted >= budget)
+            || !self.has_room()
+        {
+            return false;
+        }
+        self.emitted += 1;
+        true
+    }
+
+    fn place_particle(&mut self, index: usize, particle: Particle) -> bool {
+        if self.cells[index].particle.is_none() {
+            if !self.has_room() {
+                return false;
+            }
+            self.particle_count += 1;
+        }
+        self.cells[index].particle = Some(particle);
+        true
+    }
+
+    fn remove_particle(&mut self, index: usize) -> Option<Particle> {
+        let particle = self.cells[index].particle.take();
+        if particle.is_some() {
+            self.particle_count = self.particle_count.saturating_sub(1);
+        }
+        particle
+    }
+
     fn activate_window(&mut self, (x, y): (usize, usize)) {
         for yo in -1..=1 {
             for xo in -1..=1 {
@@ -269,19 +651,136 @@ impl GridAccess for Grid {
     }
 }
 
+impl Default for Random {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
 impl Random {
-    fn new() -> Self {
+    pub fn new() -> Self {
         Self {
             horizontal_velocity_probability: Random::random_horizontal_velocity_probability,
             row_update_direction: Random::random_row_update_direction,
             particle_seed: Random::random_particle_seed,
             particle_seed_with_cycle: Random::random_particle_seed_with_cycle,
             vertical_velocity_probability: Random::random_vertical_velocity_probability,
+            cohesion_probability: Random::random_cohesion_probability,
+            mix_probability: Random::random_mix_probability,
+            spray_velocity: Random::random_spray_velocity,
+            spray_direction: Random::random_spray_direction,
+            repose_probability: Random::random_repose_probability,
+            drift_probability: Random::random_drift_probability,
+            jitter_probability: Random::random_jitter_probability,
+            reaction_probability: Random::random_reaction_probability,
+            rain_column: Random::random_rain_column,
+            update_order: Random::random_update_order,
             rng: fastrand::Rng::new(),
             cycle: 0,
         }
     }
 
+    pub fn with_horizontal_velocity_probability(
+        mut self,
+        horizontal_velocity_probability: fn(&mut Random) -> i16,
+    ) -> Self {
+        self.horizontal_velocity_probability = horizontal_velocity_probability;
+        self
+    }
+
+    pub fn with_row_update_direction(
+        mut self,
+        row_update_direction: fn(&mut Random) -> RowUpdateDirection,
+    ) -> Self {
+        self.row_update_direction = row_update_direction;
+        self
+    }
+
+    pub fn with_particle_seed(mut self, particle_seed: fn(&mut Random) -> u8) -> Self {
+        self.particle_seed = particle_seed;
+        self
+    }
+
+    pub fn with_particle_seed_with_cycle(
+        mut self,
+        particle_seed_with_cycle: fn(&mut Random) -> u8,
+    ) -> Self {
+        self.particle_seed_with_cycle = particle_seed_with_cycle;
+        self
+    }
+
+    pub fn with_vertical_velocity_probability(
+        mut self,
+        vertical_velocity_probability: fn(&mut Random) -> i16,
+    ) -> Self {
+        self.vertical_velocity_probability = vertical_velocity_probability;
+        self
+    }
+
+    pub fn with_cohesion_probability(
+        mut self,
+        cohesion_probability: fn(&mut Random) -> f32,
+    ) -> Self {
+        self.cohesion_probability = cohesion_probability;
+        self
+    }
+
+    pub fn with_mix_probability(mut self, mix_probability: fn(&mut Random) -> f32) -> Self {
+        self.mix_probability = mix_probability;
+        self
+    }
+
+    pub fn with_repose_probability(mut self, repose_probability: fn(&mut Random) -> u8) -> Self {
+        self.repose_probability = repose_probability;
+        self
+    }
+
+    pub fn with_drift_probability(mut self, drift_probability: fn(&mut Random) -> u8) -> Self {
+        self.drift_probability = drift_probability;
+        self
+    }
+
+    pub fn with_jitter_probability(mut self, jitter_probability: fn(&mut Random) -> f32) -> Self {
+        self.jitter_probability = jitter_probability;
+        self
+    }
+
+    pub fn with_reaction_probability(
+        mut self,
+        reaction_probability: fn(&mut Random) -> f32,
+    ) -> Self {
+        self.reaction_probability = reaction_probability;
+        self
+    }
+
+    pub fn with_spray_velocity(mut self, spray_velocity: fn(&mut Random) -> (i16, i16)) -> Self {
+        self.spray_velocity = spray_velocity;
+        self
+    }
+
+    pub fn with_spray_direction(mut self, spray_direction: fn(&mut Random) -> bool) -> Self {
+        self.spray_direction = spray_direction;
+        self
+    }
+
+    pub fn with_rain_column(mut self, rain_column: fn(&mut Random) -> usize) -> Self {
+        self.rain_column = rain_column;
+        self
+    }
+
+    pub fn with_update_order(
+        mut self,
+        update_order: fn(&mut Random, &mut [(usize, usize)]),
+    ) -> Self {
+        self.update_order = update_order;
+        self
+    }
+
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.rng = fastrand::Rng::with_seed(seed);
+        self
+    }
+
     fn random_horizontal_velocity_probability(r: &mut Random) -> i16 {
         r.rng.i16(..)
     }
@@ -303,10 +802,55 @@ impl Random {
     fn random_vertical_velocity_probability(r: &mut Random) -> i16 {
         r.rng.i16(0..=i16::MAX)
     }
+
+    fn random_cohesion_probability(r: &mut Random) -> f32 {
+        r.rng.f32()
+    }
+
+    fn random_mix_probability(r: &mut Random) -> f32 {
+        r.rng.f32()
+    }
+
+    fn random_jitter_probability(r: &mut Random) -> f32 {
+        r.rng.f32()
+    }
+
+    fn random_reaction_probability(r: &mut Random) -> f32 {
+        r.rng.f32()
+    }
+
+    fn random_spray_velocity(r: &mut Random) -> (i16, i16) {
+        (r.rng.i16(1..=1024), r.rng.i16(0..=i16::MAX))
+    }
+
+    fn random_spray_direction(r: &mut Random) -> bool {
+        r.rng.bool()
+    }
+
+    fn random_repose_probability(r: &mut Random) -> u8 {
+        r.rng.u8(..)
+    }
+
+    fn random_drift_probability(r: &mut Random) -> u8 {
+        r.rng.u8(..)
+    }
+
+    fn random_rain_column(r: &mut Random) -> usize {
+        r.rng.usize(..)
+    }
+
+    fn random_update_order(r: &mut Random, positions: &mut [(usize, usize)]) {
+        r.rng.shuffle(positions);
+    }
 }
 
 impl WindowGrid {
+    /// Each side of `window_size` is clamped to `1..=` the matching grid side.
     pub fn new((width, height): (usize, usize), window_size: (usize, usize)) -> Self {
+        let window_size = (
+            window_size.0.clamp(1, width.max(1)),
+            window_size.1.clamp(1, height.max(1)),
+        );
         let mut windows = HashMap::new();
         for y in 0..height / window_size.1 {
             for x in 0..width / window_size.0 {
@@ -320,13 +864,66 @@ impl WindowGrid {
             windows,
             window_width: window_size.0,
             window_height: window_size.1,
+            cell_windows: vec![],
+            width,
+        }
+    }
+
+    /// Builds windows from explicit rectangles so quiet areas can share one
+    /// big window while busy ones get many small ones. Each `max` corner is
+    /// exclusive and the rectangles must tile the grid without gaps or
+    /// overlaps.
+    pub fn from_rects(
+        (width, height): (usize, usize),
+        rects: Vec<URect>,
+    ) -> Result<Self, GridError> {
+        let mut cell_windows = vec![None; width * height];
+        let mut windows = HashMap::new();
+        for rect in rects {
+            let (start, end) = (
+                (rect.min.x as usize, rect.min.y as usize),
+                (rect.max.x as usize, rect.max.y as usize),
+            );
+            if rect.is_empty() || width < end.0 || height < end.1 {
+                return Err(GridError::WindowCoverage);
+            }
+            for y in start.1..end.1 {
+                for x in start.0..end.0 {
+                    if cell_windows[y * width + x].replace(start).is_some() {
+                        return Err(GridError::WindowCoverage);
+                    }
+                }
+            }
+            windows.insert(start, Window::new(start, (end.0 - 1, end.1 - 1)));
+        }
+        let cell_windows = cell_windows
+            .into_iter()
+            .collect::<Option<Vec<_>>>()
+            .ok_or(GridError::WindowCoverage)?;
+        Ok(Self {
+            windows,
+            window_width: width.max(1),
+            window_height: height.max(1),
+            cell_windows,
+            width,
+        })
+    }
+
+    fn window_key(&self, (x, y): (usize, usize)) -> Option<(usize, usize)> {
+        match self.cell_windows.is_empty() {
+            true => Some((x / self.window_width, y / self.window_height)),
+            false if x < self.width => self.cell_windows.get(y * self.width + x).copied(),
+            false => None,
         }
     }
 
+    fn get_window(&self, position: (usize, usize)) -> Option<&Window> {
+        self.windows.get(&self.window_key(position)?)
+    }
+
     fn get_window_mut(&mut self, position: (usize, usize)) -> Option<&mut Window> {
-        let x = (position.0) / self.window_width;
-        let y = (position.1) / self.window_height;
-        self.windows.get_mut(&(x, y))
+        let key = self.window_key(position)?;
+        self.windows.get_mut(&key)
     }
 
     pub fn with_window_threshold(mut self, threshold: u32) -> WindowGrid {
@@ -348,27 +945,120 @@ impl Grid {
             random: Random::new(),
             initial_particle_velocity: (0, i16::MAX),
             window_grid: WindowGrid::new((width, height), (width, height)),
+            cohesion: 0.,
+            surface_jitter: 0.,
+            liquid_spread: SpreadMode::default(),
+            reaction_rate: 1.,
+            seed_variation: true,
+            deterministic_row_order: false,
+            shuffle_updates: false,
+            windowing: true,
+            sleep_dim_after: None,
+            dither: false,
+            border: None,
+            emission_budget: None,
+            emitted: 0,
+            max_particles: None,
+            particle_count: 0,
+            boundary_flows: vec![],
+            events: vec![],
+            capacity_hint: 0,
+            update_positions: vec![],
+            #[cfg(feature = "particle_ids")]
+            next_particle_id: 1,
+            equilibrium_cache: HashMap::new(),
+            event_callback: None,
         }
     }
 
     pub fn spawn_particle(&mut self, (x, y): (usize, usize), particle: Particle) {
         if y < self.height && x < self.width {
             let index = self.to_index((x, y));
-            if self.cells[index].particle.is_none() {
+            if self.cells[index].particle.is_none() && self.has_room() {
+                #[cfg(feature = "particle_ids")]
+                let particle = {
+                    let mut particle = particle;
+                    particle.id = ParticleId(self.next_particle_id);
+                    self.next_particle_id += 1;
+                    particle
+                };
                 self.cells[index] = Cell::new(particle).with_cycle(self.cycle);
+                self.particle_count += 1;
                 self.activate_window((x, y));
             }
         }
     }
 
+    fn has_room(&self) -> bool {
+        self.max_particles
+            .is_none_or(|max_particles| self.particle_count < max_particles)
+    }
+
+    fn recount_particles(&mut self) {
+        self.particle_count = self.cells.iter().filter(|c| c.particle.is_some()).count();
+    }
+
+    pub fn spawn_many(&mut self, items: impl IntoIterator<Item = ((usize, usize), Particle)>) {
+        for (position, particle) in items {
+            self.spawn_particle(position, particle);
+        }
+    }
+
+    fn deterministic_row_direction(
+        cycle: u32,
+        (wx, wy): (usize, usize),
+        row: usize,
+    ) -> RowUpdateDirection {
+        let hash = (cycle as u64).wrapping_mul(0x9E37_79B9_7F4A_7C15)
+            ^ (wx as u64).wrapping_mul(0xC2B2_AE3D_27D4_EB4F)
+            ^ (wy as u64).wrapping_mul(0x1656_67B1_9E37_79F9)
+            ^ (row as u64).wrapping_mul(0x27D4_EB2F_1656_67C5);
+        match (hash >> 32) & 1 {
+            0 => RowUpdateDirection::Forward,
+            _ => RowUpdateDirection::Reverse,
+        }
+    }
+
+    pub fn touch(&mut self, (x, y): (usize, usize)) {
+        if x < self.width && y < self.height {
+            let index = self.to_index((x, y));
+            self.cells[index].cycle = self.cycle;
+            self.activate_window((x, y));
+        }
+    }
+
+    pub fn kind_at(&self, (x, y): (usize, usize)) -> Option<ParticleKind> {
+        if x < self.width && y < self.height {
+            self.cells[self.to_index((x, y))]
+                .particle
+                .as_ref()
+                .map(|p| p.kind.clone())
+        } else {
+            None
+        }
+    }
+
     pub fn despawn_particle(&mut self, (x, y): (usize, usize)) {
         if y < self.height && x < self.width {
             let index = self.to_index((x, y));
-            self.cells[index] = Cell::empty().with_cycle(self.cycle);
+            self.remove_particle(index);
+            self.cells[index].cycle = self.cycle;
             self.activate_window((x, y));
         }
     }
 
+    pub fn despawn_if(&mut self, (x, y): (usize, usize), predicate: impl Fn(&Particle) -> bool) {
+        if y < self.height
+            && x < self.width
+            && self.cells[self.to_index((x, y))]
+                .particle
+                .as_ref()
+                .is_some_and(predicate)
+        {
+            self.despawn_particle((x, y));
+        }
+    }
+
     fn increment_cycle(&mut self) {
         self.cycle = self.cycle.wrapping_add(1);
         self.random.cycle = self.cycle;
@@ -377,10 +1067,45 @@ impl Grid {
     pub fn update_grid(&mut self) {
         let window_grid = self.window_grid.clone();
         self.increment_cycle();
-        for (_, w) in window_grid.windows {
-            if w.is_active(self.cycle) {
+        self.events.clear();
+        self.emitted = 0;
+        for flow in self.boundary_flows.clone() {
+            self.spawn_inflow(flow.edge, &flow.kind, flow.rate);
+        }
+        let mut windows: Vec<_> = match self.windowing {
+            true => window_grid.windows.into_iter().collect(),
+            false => vec![(
+                (0, 0),
+                Window::new(
+                    (0, 0),
+                    (self.width.saturating_sub(1), self.height.saturating_sub(1)),
+                ),
+            )],
+        };
+        if self.deterministic_row_order {
+            windows.sort_by_key(|&((x, y), _)| (y, x));
+        }
+        for (key, w) in windows {
+            if (!self.windowing || w.is_active(self.cycle)) && self.shuffle_updates {
+                let mut positions = std::mem::take(&mut self.update_positions);
+                positions.clear();
+                positions.extend(
+                    (w.start.1..=w.end.1).flat_map(|y| (w.start.0..=w.end.0).map(move |x| (x, y))),
+                );
+                (self.random.update_order)(&mut self.random, &mut positions);
+                for &position in &positions {
+                    let c = self.get_cell(self.to_index(position));
+                    if !self.is_simulated(c) && c.particle.is_some() {
+                        Particle::update(self, position);
+                    };
+                }
+                self.update_positions = positions;
+            } else if !self.windowing || w.is_active(self.cycle) {
                 for y in (w.start.1..=w.end.1).rev() {
-                    let x_direction = (self.random.row_update_direction)(&mut self.random);
+                    let x_direction = match self.deterministic_row_order {
+                        true => Self::deterministic_row_direction(self.cycle, key, y),
+                        false => (self.random.row_update_direction)(&mut self.random),
+                    };
                     for x in w.start.0..=w.end.0 {
                         let x = match x_direction {
                             RowUpdateDirection::Forward => x,
@@ -396,39 +1121,790 @@ impl Grid {
         }
     }
 
-    pub fn clear_grid(&mut self) {
-        self.cells.iter_mut().for_each(|c| {
-            c.particle = None;
-            c.cycle = self.cycle;
-        });
+    pub fn drain_events(&mut self) -> Vec<SimEvent> {
+        std::mem::replace(&mut self.events, Vec::with_capacity(self.capacity_hint))
     }
 
-    pub fn create_output_frame(width: usize, height: usize) -> Image {
-        Image::new_fill(
-            Extent3d {
-                width: width as u32,
-                height: height as u32,
-                depth_or_array_layers: 1,
-            },
-            TextureDimension::D2,
-            &(css::BLACK.to_u8_array()),
-            TextureFormat::Rgba8UnormSrgb,
-            RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
-        )
+    /// `callback` sees each event as it happens during `update_grid`; the
+    /// events are still collected for `drain_events` as well.
+    pub fn on_event(&mut self, callback: Box<dyn FnMut(SimEvent) + Send + Sync>) {
+        self.event_callback = Some(EventCallback(callback));
     }
 
-    pub fn draw_grid(&mut self, image: &mut Image) {
-        for (index, cell) in self.cells.iter().enumerate() {
-            if self.draw_cycle <= cell.cycle {
-                let x: u32 = index as u32 % self.width as u32;
-                let y: u32 = (index as u32 - x) / self.width as u32;
-                let _ = match &cell.particle {
-                    Some(p) => image.set_color_at(x, y, p.color()),
-                    _ => image.set_color_at(x, y, BACKGROUND_COLOR),
-                };
+    pub fn replace_kind(&mut self, from: &ParticleKind, to: ParticleKind) {
+        let mut replaced = vec![];
+        for (index, c) in self.cells.iter_mut().enumerate() {
+            if let Some(p) = &c.particle
+                && p.kind.id() == from.id()
+            {
+                c.particle = Some(
+                    Particle::from(to.clone())
+                        .with_seed(p.seed)
+                        .with_velocity(p.velocity),
+                );
+                c.cycle = self.cycle;
+                replaced.push(index);
             }
         }
-        self.draw_cycle = self.cycle;
+        for index in replaced {
+            self.activate_window((index % self.width, index / self.width));
+        }
+    }
+
+    pub fn for_each_in(
+        &mut self,
+        top_left: (usize, usize),
+        bottom_right: (usize, usize),
+        mut f: impl FnMut(&mut Cell, (usize, usize)),
+    ) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        let end = (
+            bottom_right.0.min(self.width.saturating_sub(1)),
+            bottom_right.1.min(self.height.saturating_sub(1)),
+        );
+        for y in top_left.1..=end.1 {
+            for x in top_left.0..=end.0 {
+                let index = self.to_index((x, y));
+                let occupied = self.cells[index].particle.is_some();
+                f(&mut self.cells[index], (x, y));
+                self.cells[index].cycle = self.cycle;
+                match (occupied, self.cells[index].particle.is_some()) {
+                    (false, true) => self.particle_count += 1,
+                    (true, false) => self.particle_count = self.particle_count.saturating_sub(1),
+                    _ => (),
+                }
+                self.activate_window((x, y));
+            }
+        }
+    }
+
+    pub fn active_window_count(&self) -> usize {
+        self.window_grid
+            .windows
+            .values()
+            .filter(|w| w.is_active(self.cycle))
+            .count()
+    }
+
+    pub fn is_settled(&self) -> bool {
+        !self
+            .window_grid
+            .windows
+            .values()
+            .any(|w| w.is_active(self.cycle.wrapping_add(1)))
+    }
+
+    pub fn run_until_settled(&mut self, max_cycles: u32) -> u32 {
+        let mut cycles = 0;
+        while cycles < max_cycles && !self.is_settled() {
+            self.update_grid();
+            cycles += 1;
+        }
+        cycles
+    }
+
+    pub fn clear_grid(&mut self) {
+        self.cells.iter_mut().for_each(|c| {
+            c.particle = None;
+            c.cycle = self.cycle;
+        });
+        self.particle_count = 0;
+    }
+
+    /// Despawns the particle at `start` and every particle orthogonally
+    /// connected to it, optionally only following particles of its kind.
+    /// Returns how many particles were removed.
+    pub fn remove_connected(&mut self, start: (usize, usize), same_kind_only: bool) -> usize {
+        let Some(kind_id) = self.kind_at(start).map(|k| k.id()) else {
+            return 0;
+        };
+        let mut stack = vec![start];
+        let mut removed = 0;
+        while let Some(position) = stack.pop() {
+            let connected = self.cells[self.to_index(position)]
+                .particle
+                .as_ref()
+                .is_some_and(|p| !same_kind_only || p.kind.id() == kind_id);
+            if !connected {
+                continue;
+            }
+            self.despawn_particle(position);
+            removed += 1;
+            stack.extend(
+                ORTHOGONAL_NEIGHBORS
+                    .iter()
+                    .filter_map(|&offset| self.get_neighbor_position(position, offset).ok()),
+            );
+        }
+        removed
+    }
+
+    /// Builds a rock hourglass whose chambers narrow one cell per row to a
+    /// single cell neck in the middle row, with the top chamber's upper half
+    /// filled with sand.
+    pub fn hourglass(width: usize, height: usize) -> Self {
+        let mut g = Grid::new(width, height);
+        let (center, middle) = (width / 2, height / 2);
+        for y in 0..height {
+            let half_width = y.abs_diff(middle) + 1;
+            for x in 0..width {
+                if half_width <= x.abs_diff(center) {
+                    g.spawn_particle((x, y), Particle::from(Rock::new()));
+                } else if y < middle / 2 {
+                    g.spawn_particle((x, y), Particle::from(Sand::new()));
+                }
+            }
+        }
+        g
+    }
+
+    pub fn flood_to_level(&mut self, y_level: usize, kind: ParticleKind) {
+        for y in y_level..self.height {
+            for x in 0..self.width {
+                self.brush_cell((x, y), Some(&kind));
+            }
+        }
+    }
+
+    pub fn restore_cells(&mut self, cells: &[Cell], cycle: u32) -> Result<(), GridError> {
+        if cells.len() != self.cells.len() {
+            return Err(GridError::DimensionMismatch);
+        }
+        self.cells.clone_from_slice(cells);
+        self.recount_particles();
+        self.set_cycle(cycle);
+        Ok(())
+    }
+
+    /// Runs `update_grid` and returns every cell it changed, old and new.
+    pub fn update_grid_recording(&mut self) -> GridDelta {
+        let before = self.cells.clone();
+        let cycle = self.cycle;
+        self.update_grid();
+        let cells = before
+            .into_iter()
+            .zip(&self.cells)
+            .enumerate()
+            .filter(|(_, (old, new))| old != *new)
+            .map(|(index, (old, new))| CellDelta {
+                index,
+                old,
+                new: new.clone(),
+            })
+            .collect();
+        GridDelta {
+            cycle: (cycle, self.cycle),
+            cells,
+        }
+    }
+
+    pub fn apply_delta(&mut self, delta: &GridDelta) -> Result<(), GridError> {
+        self.apply_cells(delta, |c| &c.new, delta.cycle.1)
+    }
+
+    /// Steps back over a recorded cycle by putting its old cells back.
+    pub fn apply_delta_inverse(&mut self, delta: &GridDelta) -> Result<(), GridError> {
+        self.apply_cells(delta, |c| &c.old, delta.cycle.0)
+    }
+
+    /// Writes the chosen side of each changed cell back. The written cells
+    /// can carry cycles older than the last drawn frame, so the draw cycle is
+    /// lowered to repaint them.
+    fn apply_cells(
+        &mut self,
+        delta: &GridDelta,
+        cell: impl Fn(&CellDelta) -> &Cell,
+        cycle: u32,
+    ) -> Result<(), GridError> {
+        if delta.cells.iter().any(|c| self.cells.len() <= c.index) {
+            return Err(GridError::OutOfBound);
+        }
+        for c in &delta.cells {
+            let cell = cell(c).clone();
+            match (
+                self.cells[c.index].particle.is_some(),
+                cell.particle.is_some(),
+            ) {
+                (false, true) => self.particle_count += 1,
+                (true, false) => self.particle_count = self.particle_count.saturating_sub(1),
+                _ => (),
+            }
+            self.draw_cycle = self.draw_cycle.min(cell.cycle);
+            self.cells[c.index] = cell;
+        }
+        self.set_cycle(cycle);
+        Ok(())
+    }
+
+    /// Keeps the settled cells under `scene`, usually the `layout_checksum`
+    /// taken before the scene started running, so scenes that only differ
+    /// in particle state share a key. Does nothing until it is settled.
+    pub fn cache_if_settled(&mut self, scene: u32) -> bool {
+        if !self.is_settled() {
+            return false;
+        }
+        self.equilibrium_cache.insert(scene, self.cells.clone());
+        true
+    }
+
+    /// Jumps straight to the settled cells cached for `scene`, if any. The
+    /// cached cells keep their old cycles, so the whole frame is redrawn.
+    pub fn try_restore_cached(&mut self, scene: u32) -> bool {
+        match self.equilibrium_cache.get(&scene) {
+            Some(cells) => {
+                let cells = cells.clone();
+                let restored = self.restore_cells(&cells, self.cycle).is_ok();
+                if restored {
+                    self.redraw_all();
+                }
+                restored
+            }
+            None => false,
+        }
+    }
+
+    /// Resumes the simulation at `cycle`, waking every window so none of them
+    /// looks asleep after the jump.
+    pub fn set_cycle(&mut self, cycle: u32) {
+        self.cycle = cycle;
+        self.random.cycle = cycle;
+        for w in self.window_grid.windows.values_mut() {
+            w.activate(cycle);
+        }
+    }
+
+    pub fn set_draw_cycle(&mut self, draw_cycle: u32) {
+        self.draw_cycle = draw_cycle;
+    }
+
+    pub fn velocity_histogram(&self) -> [usize; VELOCITY_HISTOGRAM_BUCKETS] {
+        let mut histogram = [0; VELOCITY_HISTOGRAM_BUCKETS];
+        for p in self.cells.iter().filter_map(|c| c.particle.as_ref()) {
+            histogram[Self::velocity_bucket(p)] += 1;
+        }
+        histogram
+    }
+
+    fn velocity_bucket(particle: &Particle) -> usize {
+        let speed = particle.velocity.1.unsigned_abs() as usize;
+        speed * VELOCITY_HISTOGRAM_BUCKETS / (i16::MAX as usize + 2)
+    }
+
+    /// Counts particles by kind and averages their velocity over the cells
+    /// between the two corners, inclusive and clipped to the grid.
+    pub fn region_stats(
+        &self,
+        top_left: (usize, usize),
+        bottom_right: (usize, usize),
+    ) -> RegionStats {
+        let mut stats = RegionStats::default();
+        let (mut sum_x, mut sum_y) = (0., 0.);
+        let x_end = bottom_right.0.min(self.width.saturating_sub(1));
+        let y_end = bottom_right.1.min(self.height.saturating_sub(1));
+        for y in top_left.1..=y_end {
+            for x in top_left.0..=x_end {
+                if let Some(p) = &self.cells[self.to_index((x, y))].particle {
+                    *stats.counts.entry(p.to_string()).or_default() += 1;
+                    stats.velocity_histogram[Self::velocity_bucket(p)] += 1;
+                    sum_x += p.velocity.0 as f32;
+                    sum_y += p.velocity.1 as f32;
+                }
+            }
+        }
+        let total = stats.counts.values().sum::<usize>();
+        if total > 0 {
+            stats.average_velocity = (sum_x / total as f32, sum_y / total as f32);
+        }
+        stats
+    }
+
+    pub fn occupied_bounds(&self) -> Option<((usize, usize), (usize, usize))> {
+        self.cells
+            .iter()
+            .enumerate()
+            .filter(|(_, c)| c.particle.is_some())
+            .map(|(i, _)| (i % self.width, i / self.width))
+            .fold(None, |bounds, (x, y)| match bounds {
+                None => Some(((x, y), (x, y))),
+                Some(((x0, y0), (x1, y1))) => {
+                    Some(((x0.min(x), y0.min(y)), (x1.max(x), y1.max(y))))
+                }
+            })
+    }
+
+    /// Every particle within `radius` cells of `center`, in row order.
+    pub fn particles_in_radius(
+        &self,
+        center: (usize, usize),
+        radius: usize,
+    ) -> Vec<((usize, usize), &Particle)> {
+        Self::circle_brush(center, radius * 2 + 1)
+            .filter(|&(x, y)| x < self.width && y < self.height)
+            .filter_map(|position| {
+                self.cells[self.to_index(position)]
+                    .particle
+                    .as_ref()
+                    .map(|p| (position, p))
+            })
+            .collect()
+    }
+
+    /// Flips the cells left to right.
+    pub fn mirror_horizontal(&mut self) {
+        let width = self.width;
+        self.remap_cells(|(x, y)| (width - 1 - x, y));
+    }
+
+    /// Flips the cells top to bottom.
+    pub fn mirror_vertical(&mut self) {
+        let height = self.height;
+        self.remap_cells(|(x, y)| (x, height - 1 - y));
+    }
+
+    fn remap_cells(&mut self, source: impl Fn((usize, usize)) -> (usize, usize)) {
+        let mut cells = Vec::with_capacity(self.cells.len());
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let mut cell = self.cells[self.to_index(source((x, y)))].clone();
+                cell.cycle = self.cycle;
+                cells.push(cell);
+            }
+        }
+        self.cells = cells;
+        for w in self.window_grid.windows.values_mut() {
+            w.activate(self.cycle);
+        }
+    }
+
+    pub fn rotate(&mut self, quarter_turns: u8) {
+        for _ in 0..quarter_turns % 4 {
+            let (width, height) = (self.height, self.width);
+            let mut cells = Vec::with_capacity(self.cells.len());
+            for y in 0..height {
+                for x in 0..width {
+                    let mut cell = self.cells[self.to_index((y, self.height - 1 - x))].clone();
+                    cell.cycle = self.cycle;
+                    cells.push(cell);
+                }
+            }
+            let threshold = self
+                .window_grid
+                .windows
+                .values()
+                .next()
+                .map(|w| w.threshold);
+            let window_size = (
+                self.window_grid.window_height,
+                self.window_grid.window_width,
+            );
+            self.cells = cells;
+            self.width = width;
+            self.height = height;
+            self.window_grid = WindowGrid::new((width, height), window_size)
+                .with_window_threshold(threshold.unwrap_or_default());
+            for w in self.window_grid.windows.values_mut() {
+                w.activate(self.cycle);
+            }
+        }
+    }
+
+    /// Crops the grid to its occupied cells, or `None` when nothing is left
+    /// to keep.
+    pub fn trimmed(&self) -> Option<Grid> {
+        let (start, end) = self.occupied_bounds()?;
+        let mut grid = Grid::new(end.0 - start.0 + 1, end.1 - start.1 + 1);
+        for y in start.1..=end.1 {
+            for x in start.0..=end.0 {
+                let index = grid.to_index((x - start.0, y - start.1));
+                grid.cells[index].particle = self.get_cell(self.to_index((x, y))).particle.clone();
+            }
+        }
+        grid.recount_particles();
+        Some(grid)
+    }
+
+    pub fn copy_region(&self, top_left: (usize, usize), bottom_right: (usize, usize)) -> Stamp {
+        let end = (
+            bottom_right.0.min(self.width.saturating_sub(1)),
+            bottom_right.1.min(self.height.saturating_sub(1)),
+        );
+        let size = (
+            (end.0 + 1).saturating_sub(top_left.0),
+            (end.1 + 1).saturating_sub(top_left.1),
+        );
+        let mut cells = Vec::with_capacity(size.0 * size.1);
+        for y in top_left.1..=end.1 {
+            for x in top_left.0..=end.0 {
+                let mut cell = Cell::empty();
+                cell.particle = self.get_cell(self.to_index((x, y))).particle.clone();
+                cells.push(cell);
+            }
+        }
+        Stamp { cells, size }
+    }
+
+    pub fn paste_stamp(&mut self, position: (usize, usize), stamp: &Stamp, replace: bool) {
+        for (i, cell) in stamp.cells.iter().enumerate() {
+            let (x, y) = (position.0 + i % stamp.size.0, position.1 + i / stamp.size.0);
+            if x >= self.width || y >= self.height {
+                continue;
+            }
+            match (&cell.particle, replace) {
+                (Some(p), true) => {
+                    self.despawn_particle((x, y));
+                    self.spawn_particle((x, y), p.clone());
+                }
+                (Some(p), false) => self.spawn_particle((x, y), p.clone()),
+                (None, true) => self.despawn_particle((x, y)),
+                (None, false) => (),
+            }
+        }
+    }
+
+    pub fn to_rle(&self) -> String {
+        let mut rle = String::new();
+        if self.width == 0 {
+            return rle;
+        }
+        for (y, row) in self.cells.chunks(self.width).enumerate() {
+            if y > 0 {
+                rle.push('/');
+            }
+            let mut glyphs = row.iter().map(|c| c.to_string()).peekable();
+            while let Some(glyph) = glyphs.next() {
+                let mut run = 1;
+                while glyphs.next_if_eq(&glyph).is_some() {
+                    run += 1;
+                }
+                if run > 1 {
+                    rle.push_str(&run.to_string());
+                }
+                rle.push_str(&glyph);
+            }
+        }
+        rle
+    }
+
+    /// The cell glyphs one row per line, each particle colored with a 24-bit
+    /// ANSI escape so headless runs can be looked at in a terminal.
+    pub fn render_ansi(&self) -> String {
+        let mut ansi = String::new();
+        for y in 0..self.height {
+            for x in 0..self.width {
+                let cell = self.get_cell(self.to_index((x, y)));
+                match &cell.particle {
+                    Some(p) => {
+                        let [r, g, b] = p.color_bytes();
+                        ansi.push_str(&format!("\x1b[38;2;{r};{g};{b}m{cell}\x1b[0m"));
+                    }
+                    None => ansi.push_str(&cell.to_string()),
+                }
+            }
+            ansi.push('\n');
+        }
+        ansi
+    }
+
+    pub fn from_cells(width: usize, height: usize, cells: Vec<Cell>) -> Result<Self, GridError> {
+        if cells.len() != width * height {
+            return Err(GridError::DimensionMismatch);
+        }
+        let mut grid = Grid::new(width, height);
+        grid.cells = cells;
+        grid.recount_particles();
+        Ok(grid)
+    }
+
+    pub fn from_rle(rle: &str) -> Result<Self, GridError> {
+        let mut rows = vec![];
+        for row in rle.trim().split('/') {
+            let mut particles = vec![];
+            let mut run: usize = 0;
+            for glyph in row.chars() {
+                match glyph.to_digit(10) {
+                    Some(d) => {
+                        run = run
+                            .checked_mul(10)
+                            .and_then(|run| run.checked_add(d as usize))
+                            .filter(|&run| run <= MAX_RLE_WIDTH)
+                            .ok_or(GridError::InvalidFormat)?
+                    }
+                    None => {
+                        if particles.len() + run.max(1) > MAX_RLE_WIDTH {
+                            return Err(GridError::InvalidFormat);
+                        }
+                        let particle = Self::glyph_particle(glyph)?;
+                        particles.extend(iter::repeat_n(particle, run.max(1)));
+                        run = 0;
+                    }
+                }
+            }
+            if run != 0 {
+                return Err(GridError::InvalidFormat);
+            }
+            rows.push(particles);
+        }
+
+        let width = rows[0].len();
+        if width == 0 || rows.iter().any(|r| r.len() != width) {
+            return Err(GridError::DimensionMismatch);
+        }
+        let mut grid = Grid::new(width, rows.len());
+        for (y, row) in rows.into_iter().enumerate() {
+            for (x, particle) in row.into_iter().enumerate() {
+                if let Some(p) = particle {
+                    grid.spawn_particle((x, y), p);
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    /// FNV-1a hash of `to_rle`, so it only covers which kind sits in each
+    /// cell. Health, seed, velocity and per-kind state like acidity, tint or
+    /// wire charge don't change it.
+    pub fn layout_checksum(&self) -> u32 {
+        Self::fnv1a(self.to_rle().as_bytes())
+    }
+
+    fn fnv1a(bytes: &[u8]) -> u32 {
+        bytes.iter().fold(0x811c_9dc5, |hash, &b| {
+            (hash ^ b as u32).wrapping_mul(0x0100_0193)
+        })
+    }
+
+    /// Saves the layout as its rle under a `layout_checksum` line. Only the
+    /// kinds are kept: loading it back spawns fresh particles of each kind.
+    pub fn save_layout_to_file(&self, path: impl AsRef<Path>) -> Result<(), GridError> {
+        fs::write(
+            path,
+            format!("{:08x}\n{}\n", self.layout_checksum(), self.to_rle()),
+        )?;
+        Ok(())
+    }
+
+    pub fn load_layout_from_file(path: impl AsRef<Path>) -> Result<Self, GridError> {
+        let text = fs::read_to_string(path)?;
+        let (checksum, rle) = text.split_once('\n').ok_or(GridError::InvalidFormat)?;
+        let checksum =
+            u32::from_str_radix(checksum.trim(), 16).map_err(|_| GridError::InvalidFormat)?;
+        let rle = rle.trim();
+        if Self::fnv1a(rle.as_bytes()) != checksum {
+            return Err(GridError::ChecksumMismatch);
+        }
+        Self::from_rle(rle)
+    }
+
+    pub fn to_image(&self) -> Image {
+        let (width, height) = self.frame_size();
+        let mut image = Self::create_output_frame(width, height);
+        self.draw_border(&mut image);
+        for y in 0..self.height {
+            for x in 0..self.width {
+                self.draw_cell(&mut image, (x, y));
+            }
+        }
+        image
+    }
+
+    /// Each pixel becomes the particle kind whose color is closest to it;
+    /// transparent pixels and pixels closest to the background stay empty.
+    pub fn from_image(image: &Image) -> Result<Self, GridError> {
+        let palette: Vec<([u8; 4], Option<ParticleKind>)> =
+            iter::once((BACKGROUND_COLOR.to_srgba().to_u8_array(), None))
+                .chain(
+                    (0..=u8::MAX)
+                        .filter_map(ParticleKind::with_id)
+                        .map(|k| (Particle::from(k.clone()).color_rgba(), Some(k))),
+                )
+                .collect();
+        let (width, height) = (image.width() as usize, image.height() as usize);
+        let mut grid = Grid::new(width, height);
+        for y in 0..height {
+            for x in 0..width {
+                let rgba = image
+                    .get_color_at(x as u32, y as u32)
+                    .map_err(|_| GridError::InvalidFormat)?
+                    .to_srgba()
+                    .to_u8_array();
+                if rgba[3] < u8::MAX / 2 {
+                    continue;
+                }
+                let distance = |color: &[u8; 4]| {
+                    (0..3)
+                        .map(|c| (color[c] as i32 - rgba[c] as i32).pow(2))
+                        .sum::<i32>()
+                };
+                if let Some((_, Some(kind))) = palette.iter().min_by_key(|(c, _)| distance(c)) {
+                    grid.spawn_particle((x, y), Particle::from(kind.clone()));
+                }
+            }
+        }
+        Ok(grid)
+    }
+
+    #[cfg(feature = "export")]
+    pub fn export_png(&self, path: impl AsRef<Path>) -> Result<(), GridError> {
+        self.to_image()
+            .try_into_dynamic()
+            .map_err(|_| GridError::InvalidFormat)?
+            .save_with_format(path, image::ImageFormat::Png)
+            .map_err(|error| match error {
+                image::ImageError::IoError(error) => GridError::from(error),
+                _ => GridError::InvalidFormat,
+            })
+    }
+
+    fn glyph_particle(glyph: char) -> Result<Option<Particle>, GridError> {
+        Ok(Some(match glyph {
+            '-' => return Ok(None),
+            's' => Particle::from(Sand::new()),
+            'w' => Particle::from(Water::new()),
+            'S' => Particle::from(Salt::new()),
+            'r' => Particle::from(Rock::new()),
+            'd' => Particle::from(Drain::new()),
+            't' => Particle::from(Tap::new()),
+            'a' => Particle::from(Acid::new()),
+            'I' => Particle::from(Wire::powered()),
+            'i' => Particle::from(Wire::new()),
+            'g' => Particle::from(Generator::new()),
+            'l' => Particle::from(Slime::new()),
+            'v' => Particle::from(Virus::new()),
+            'R' => Particle::from(Reservoir::new()),
+            'T' => Particle::from(Teleporter::new()),
+            'c' => Particle::from(Crystal::new()),
+            'D' => Particle::from(Dust::new()),
+            'B' => Particle::from(Bedrock::new()),
+            'C' => Particle::from(Cryo::new()),
+            'e' => Particle::from(Ice::new()),
+            'm' => Particle::from(Metal::new()),
+            'M' => Particle::from(Magnet::new()),
+            'n' => Particle::from(Membrane::new()),
+            'p' => Particle::from(Rope::new()),
+            'y' => Particle::from(Dye::new()),
+            'F' => Particle::from(Fuse::lit()),
+            'f' => Particle::from(Fuse::new()),
+            'H' => Particle::from(Gate::open()),
+            'h' => Particle::from(Gate::new()),
+            'G' => Particle::from(Glass::new()),
+            'K' => Particle::from(Sticky::stuck()),
+            'k' => Particle::from(Sticky::new()),
+            'W' => Particle::from(Steam::new()),
+            glyph => match Custom::with_glyph(glyph) {
+                Some(c) => Particle::from(c),
+                None => return Err(GridError::InvalidFormat),
+            },
+        }))
+    }
+
+    pub fn create_output_frame(width: usize, height: usize) -> Image {
+        Image::new_fill(
+            Extent3d {
+                width: width as u32,
+                height: height as u32,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            &(css::BLACK.to_u8_array()),
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::MAIN_WORLD | RenderAssetUsages::RENDER_WORLD,
+        )
+    }
+
+    pub fn redraw_all(&mut self) {
+        self.draw_cycle = 0;
+    }
+
+    /// Size of the rendered image: the grid plus the border around it.
+    pub fn frame_size(&self) -> (usize, usize) {
+        let thickness = self.border.map_or(0, |(_, thickness)| thickness);
+        (self.width + 2 * thickness, self.height + 2 * thickness)
+    }
+
+    pub fn draw_grid(&mut self, image: &mut Image) {
+        if self.draw_cycle == 0 {
+            self.draw_border(image);
+        }
+        let mut redraw = vec![];
+        if let Some(after) = self.sleep_dim_after {
+            let cycle = self.cycle.saturating_sub(after);
+            for w in self.window_grid.windows.values_mut() {
+                let sleeping = self.cycle > after && !w.is_active(cycle);
+                if sleeping != w.dimmed {
+                    w.dimmed = sleeping;
+                    redraw.push((w.start, w.end));
+                }
+            }
+        }
+        for (index, cell) in self.cells.iter().enumerate() {
+            if self.draw_cycle <= cell.cycle {
+                let x = index % self.width;
+                let y = index / self.width;
+                self.draw_cell(image, (x, y));
+            }
+        }
+        for (start, end) in redraw {
+            for y in start.1..=end.1 {
+                for x in start.0..=end.0 {
+                    self.draw_cell(image, (x, y));
+                }
+            }
+        }
+        self.draw_cycle = self.cycle;
+    }
+
+    fn draw_border(&self, image: &mut Image) {
+        let Some((color, thickness)) = self.border else {
+            return;
+        };
+        let (width, height) = self.frame_size();
+        let rgba = color.to_srgba().to_u8_array();
+        for y in 0..height {
+            for x in 0..width {
+                let inside = (thickness..self.width + thickness).contains(&x)
+                    && (thickness..self.height + thickness).contains(&y);
+                if !inside
+                    && let Some(pixel) = image.pixel_bytes_mut(UVec3::new(x as u32, y as u32, 0))
+                {
+                    pixel.copy_from_slice(&rgba);
+                }
+            }
+        }
+    }
+
+    fn draw_cell(&self, image: &mut Image, (x, y): (usize, usize)) {
+        let dimmed = self
+            .window_grid
+            .get_window((x, y))
+            .is_some_and(|w| w.dimmed);
+        let particle = &self.get_cell(self.to_index((x, y))).particle;
+        let mut rgba = match particle {
+            Some(p) if !dimmed => p.color_rgba(),
+            particle => {
+                let color = particle.as_ref().map_or(BACKGROUND_COLOR, |p| p.color());
+                let color = match dimmed {
+                    true => {
+                        let hsva: Hsva = color.into();
+                        hsva.with_value(hsva.value * 0.6).into()
+                    }
+                    false => color,
+                };
+                color.to_srgba().to_u8_array()
+            }
+        };
+        if self.dither && particle.is_some() {
+            let offset = BAYER_4X4[y % 4][x % 4] as i16 / 4 - 2;
+            for channel in &mut rgba[..3] {
+                *channel = (*channel as i16 + offset).clamp(0, u8::MAX as i16) as u8;
+            }
+        }
+        let thickness = self.border.map_or(0, |(_, thickness)| thickness);
+        let pixel = UVec3::new((x + thickness) as u32, (y + thickness) as u32, 0);
+        if let Some(pixel) = image.pixel_bytes_mut(pixel) {
+            pixel.copy_from_slice(&rgba);
+        }
     }
 
     pub fn spawn_brush(
@@ -436,29 +1912,235 @@ impl Grid {
         position: (usize, usize),
         size: usize,
         kind: Option<&ParticleKind>,
+    ) {
+        self.paint_brush(position, size, kind, PaintMode::FillEmpty);
+    }
+
+    pub fn spawn_brush_replacing(
+        &mut self,
+        position: (usize, usize),
+        size: usize,
+        kind: Option<&ParticleKind>,
+    ) {
+        self.paint_brush(position, size, kind, PaintMode::Replace);
+    }
+
+    pub fn paint_brush(
+        &mut self,
+        position: (usize, usize),
+        size: usize,
+        kind: Option<&ParticleKind>,
+        mode: PaintMode,
     ) {
         for position in Self::circle_brush(position, size) {
-            match kind {
-                Some(k) => {
-                    let seed = (self.random.particle_seed_with_cycle)(&mut self.random);
-                    self.spawn_particle(
-                        position,
-                        Particle::from(k.clone())
-                            .with_seed(seed)
-                            .with_velocity(self.initial_particle_velocity),
-                    )
-                }
-                None => self.despawn_particle(position),
+            self.paint_cell(position, kind, self.initial_particle_velocity, mode);
+        }
+    }
+
+    pub fn spawn_brush_mix(
+        &mut self,
+        position: (usize, usize),
+        size: usize,
+        kind: Option<&ParticleKind>,
+        secondary_kind: &ParticleKind,
+        ratio: f32,
+        mode: PaintMode,
+    ) {
+        for position in Self::circle_brush(position, size) {
+            let kind = match (self.random.mix_probability)(&mut self.random) < ratio {
+                true => Some(secondary_kind),
+                false => kind,
+            };
+            self.paint_cell(position, kind, self.initial_particle_velocity, mode);
+        }
+    }
+
+    pub fn spawn_brush_spray(
+        &mut self,
+        (x, y): (usize, usize),
+        size: usize,
+        kind: Option<&ParticleKind>,
+        mode: PaintMode,
+    ) {
+        for position in Self::circle_brush((x, y), size) {
+            let (velocityx, velocityy) = (self.random.spray_velocity)(&mut self.random);
+            let directionx = self.spray_direction(position.0.cmp(&x));
+            let directiony = self.spray_direction(position.1.cmp(&y));
+            let offset = (
+                directionx * (velocityx as i32 * SPRAY_REACH / 1024),
+                directiony * (velocityy as i32 * SPRAY_REACH / i16::MAX as i32),
+            );
+            let (px, py) = (position.0 as i32 + offset.0, position.1 as i32 + offset.1);
+            // sprayed past the edge, the particle leaves the grid
+            if px < 0 || py < 0 || px >= self.width as i32 || py >= self.height as i32 {
+                continue;
+            }
+            let position = (px as usize, py as usize);
+            self.paint_cell(
+                position,
+                kind,
+                (directionx as i16 * velocityx, velocityy),
+                mode,
+            );
+        }
+    }
+
+    fn spray_direction(&mut self, ordering: std::cmp::Ordering) -> i32 {
+        match ordering {
+            std::cmp::Ordering::Less => -1,
+            std::cmp::Ordering::Equal if (self.random.spray_direction)(&mut self.random) => -1,
+            _ => 1,
+        }
+    }
+
+    fn paint_cell(
+        &mut self,
+        position: (usize, usize),
+        kind: Option<&ParticleKind>,
+        velocity: (i16, i16),
+        mode: PaintMode,
+    ) {
+        match mode {
+            PaintMode::FillEmpty => (),
+            PaintMode::Replace => self.despawn_particle(position),
+            PaintMode::Behind if self.get_cell(self.to_index(position)).particle.is_some() => {
+                return;
             }
+            PaintMode::Behind => (),
         }
+        self.brush_cell_with_velocity(position, kind, velocity);
     }
 
-    fn circle_brush((x, y): (usize, usize), size: usize) -> impl Iterator<Item = (usize, usize)> {
-        let radius = size as i32 / 2;
-        ((-radius)..=(radius)).flat_map(move |j| {
-            ((-radius)..=(radius)).filter_map(move |i| {
-                if (i * i) + (j * j) <= (radius * radius) {
-                    Some(((x as i32 + i) as usize, (y as i32 + j) as usize))
+    fn brush_cell(&mut self, position: (usize, usize), kind: Option<&ParticleKind>) {
+        self.brush_cell_with_velocity(position, kind, self.initial_particle_velocity);
+    }
+
+    fn brush_cell_with_velocity(
+        &mut self,
+        position: (usize, usize),
+        kind: Option<&ParticleKind>,
+        velocity: (i16, i16),
+    ) {
+        match kind {
+            Some(k) => {
+                let seed = match self.seed_variation {
+                    true => (self.random.particle_seed_with_cycle)(&mut self.random),
+                    false => 127,
+                };
+                self.spawn_particle(
+                    position,
+                    Particle::from(k.clone())
+                        .with_seed(seed)
+                        .with_velocity(velocity),
+                )
+            }
+            None => self.despawn_particle(position),
+        }
+    }
+
+    pub fn spawn_rain(&mut self, kind: &ParticleKind, rate: usize) {
+        self.spawn_inflow(Edge::Top, kind, rate);
+    }
+
+    pub fn spawn_inflow(&mut self, edge: Edge, kind: &ParticleKind, rate: usize) {
+        if self.width == 0 || self.height == 0 {
+            return;
+        }
+        for _ in 0..rate {
+            let slot = (self.random.rain_column)(&mut self.random);
+            let position = match edge {
+                Edge::Top => (slot % self.width, 0),
+                Edge::Bottom => (slot % self.width, self.height - 1),
+                Edge::Left => (0, slot % self.height),
+                Edge::Right => (self.width - 1, slot % self.height),
+            };
+            self.brush_cell(position, Some(kind));
+        }
+    }
+
+    pub fn despawn_brush(&mut self, position: (usize, usize), size: usize, shape: BrushShape) {
+        for position in Self::shape_brush(position, size, shape) {
+            self.despawn_particle(position);
+        }
+    }
+
+    pub fn erase_brush(&mut self, position: (usize, usize), size: usize, kind: &ParticleKind) {
+        for position in Self::circle_brush(position, size) {
+            self.despawn_if(position, |p| p.kind.id() == kind.id());
+        }
+    }
+
+    pub fn draw_line(
+        &mut self,
+        start: (usize, usize),
+        end: (usize, usize),
+        size: usize,
+        kind: Option<&ParticleKind>,
+    ) {
+        for position in Self::line(
+            (start.0 as i32, start.1 as i32),
+            (end.0 as i32, end.1 as i32),
+        ) {
+            self.spawn_brush(position, size, kind);
+        }
+    }
+
+    fn line((x0, y0): (i32, i32), (x1, y1): (i32, i32)) -> impl Iterator<Item = (usize, usize)> {
+        let dx = (x1 - x0).abs();
+        let dy = -(y1 - y0).abs();
+        let sx = if x0 < x1 { 1 } else { -1 };
+        let sy = if y0 < y1 { 1 } else { -1 };
+        let mut error = dx + dy;
+        let mut position = Some((x0, y0));
+        std::iter::from_fn(move || {
+            let (x, y) = position?;
+            position = if (x, y) == (x1, y1) {
+                None
+            } else {
+                let mut next = (x, y);
+                let e2 = 2 * error;
+                if e2 >= dy {
+                    error += dy;
+                    next.0 += sx;
+                }
+                if e2 <= dx {
+                    error += dx;
+                    next.1 += sy;
+                }
+                Some(next)
+            };
+            Some((x as usize, y as usize))
+        })
+    }
+
+    fn circle_brush(position: (usize, usize), size: usize) -> impl Iterator<Item = (usize, usize)> {
+        Self::shape_brush(position, size, BrushShape::Circle)
+    }
+
+    /// `size` is the brush diameter in cells, and a size of 0 covers no cells
+    /// at all. Odd sizes are centered on `(x, y)`; even sizes have no middle
+    /// cell, so their center sits half a cell up and left of it.
+    fn shape_brush(
+        (x, y): (usize, usize),
+        size: usize,
+        shape: BrushShape,
+    ) -> impl Iterator<Item = (usize, usize)> {
+        let size = size as i32;
+        let (start, end) = (-(size / 2), size - 1 - size / 2);
+        // distances are doubled so the half-cell center of even sizes stays
+        // whole; the +2 keeps the edge cells of even sizes and can't change
+        // odd ones, whose doubled squared distances are multiples of 4
+        let reach = (size - 1) * (size - 1) + 2;
+        (start..=end).flat_map(move |j| {
+            (start..=end).filter_map(move |i| {
+                let (px, py) = (x as i32 + i, y as i32 + j);
+                let (di, dj) = (2 * i - start - end, 2 * j - start - end);
+                let inside = match shape {
+                    BrushShape::Circle => (di * di) + (dj * dj) <= reach,
+                    BrushShape::Square => true,
+                };
+                if inside && px >= 0 && py >= 0 {
+                    Some((px as usize, py as usize))
                 } else {
                     None
                 }
@@ -466,6 +2148,15 @@ impl Grid {
         })
     }
 
+    #[allow(dead_code)]
+    pub fn with_random(mut self, random: Random) -> Self {
+        self.random = Random {
+            cycle: self.cycle,
+            ..random
+        };
+        self
+    }
+
     #[allow(dead_code)]
     pub fn with_rand_horizontal_velocity_probability(
         mut self,
@@ -491,20 +2182,216 @@ impl Grid {
     }
 
     #[allow(dead_code)]
-    pub fn with_rand_seed_with_cycle(
-        mut self,
-        particle_seed_with_cycle: fn(r: &mut Random) -> u8,
-    ) -> Self {
-        self.random.particle_seed_with_cycle = particle_seed_with_cycle;
+    pub fn with_rand_seed_with_cycle(
+        mut self,
+        particle_seed_with_cycle: fn(r: &mut Random) -> u8,
+    ) -> Self {
+        self.random.particle_seed_with_cycle = particle_seed_with_cycle;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rand_vertical_velocity_probability(
+        mut self,
+        veritical_velocity_probability: fn(r: &mut Random) -> i16,
+    ) -> Self {
+        self.random.vertical_velocity_probability = veritical_velocity_probability;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rand_cohesion_probability(
+        mut self,
+        cohesion_probability: fn(r: &mut Random) -> f32,
+    ) -> Self {
+        self.random.cohesion_probability = cohesion_probability;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rand_mix_probability(mut self, mix_probability: fn(r: &mut Random) -> f32) -> Self {
+        self.random.mix_probability = mix_probability;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rand_repose_probability(
+        mut self,
+        repose_probability: fn(r: &mut Random) -> u8,
+    ) -> Self {
+        self.random.repose_probability = repose_probability;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rand_drift_probability(
+        mut self,
+        drift_probability: fn(r: &mut Random) -> u8,
+    ) -> Self {
+        self.random.drift_probability = drift_probability;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rand_jitter_probability(
+        mut self,
+        jitter_probability: fn(r: &mut Random) -> f32,
+    ) -> Self {
+        self.random.jitter_probability = jitter_probability;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rand_reaction_probability(
+        mut self,
+        reaction_probability: fn(r: &mut Random) -> f32,
+    ) -> Self {
+        self.random.reaction_probability = reaction_probability;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rand_spray_velocity(
+        mut self,
+        spray_velocity: fn(r: &mut Random) -> (i16, i16),
+    ) -> Self {
+        self.random.spray_velocity = spray_velocity;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rand_spray_direction(
+        mut self,
+        spray_direction: fn(r: &mut Random) -> bool,
+    ) -> Self {
+        self.random.spray_direction = spray_direction;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rand_rain_column(mut self, rain_column: fn(r: &mut Random) -> usize) -> Self {
+        self.random.rain_column = rain_column;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rand_update_order(
+        mut self,
+        update_order: fn(r: &mut Random, positions: &mut [(usize, usize)]),
+    ) -> Self {
+        self.random.update_order = update_order;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_cohesion(mut self, cohesion: f32) -> Self {
+        self.cohesion = cohesion;
+        self
+    }
+
+    /// Chance per cycle that a liquid cell with nothing above it swaps with
+    /// a liquid beside it, keeping still pools rippling and awake. Off at 0.
+    #[allow(dead_code)]
+    pub fn with_surface_jitter(mut self, probability: f32) -> Self {
+        self.surface_jitter = probability;
+        self
+    }
+
+    /// Scales how often dissolving, draining and corrosion happen relative
+    /// to movement: at 0.5 a reaction that is due goes ahead half the time.
+    /// Rates of 1 and above react every time, as before.
+    #[allow(dead_code)]
+    pub fn with_reaction_rate(mut self, reaction_rate: f32) -> Self {
+        self.reaction_rate = reaction_rate;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_liquid_spread(mut self, liquid_spread: SpreadMode) -> Self {
+        self.liquid_spread = liquid_spread;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_rng_seed(mut self, seed: u64) -> Self {
+        self.random.rng = fastrand::Rng::with_seed(seed);
+        self
+    }
+
+    /// Visits the cells of each window in an order drawn from `Random` every
+    /// cycle instead of bottom-up rows.
+    #[allow(dead_code)]
+    pub fn with_shuffle_updates(mut self, shuffle_updates: bool) -> Self {
+        self.shuffle_updates = shuffle_updates;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_deterministic_row_order(mut self, deterministic_row_order: bool) -> Self {
+        self.deterministic_row_order = deterministic_row_order;
+        self
+    }
+
+    /// Preallocates the event buffer, and the shuffled update order, for
+    /// about `expected_active_cells` cells so busy grids don't keep growing
+    /// them.
+    #[allow(dead_code)]
+    pub fn with_capacity_hint(mut self, expected_active_cells: usize) -> Self {
+        self.capacity_hint = expected_active_cells;
+        self.events.reserve(expected_active_cells);
+        self.update_positions.reserve(expected_active_cells);
+        self
+    }
+
+    /// Caps how many particles the grid holds; spawning and tap emission are
+    /// refused once the cap is reached.
+    #[allow(dead_code)]
+    pub fn with_max_particles(mut self, max_particles: usize) -> Self {
+        self.max_particles = Some(max_particles);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_emission_budget(mut self, budget: usize) -> Self {
+        self.emission_budget = Some(budget);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_windowing(mut self, windowing: bool) -> Self {
+        self.windowing = windowing;
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_boundary_flow(mut self, flow: BoundaryFlow) -> Self {
+        self.boundary_flows.push(flow);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_sleep_dimming(mut self, after_cycles: u32) -> Self {
+        self.sleep_dim_after = Some(after_cycles);
+        self
+    }
+
+    #[allow(dead_code)]
+    pub fn with_dither(mut self, dither: bool) -> Self {
+        self.dither = dither;
+        self
+    }
+
+    /// Paints a `thickness` cells wide frame of `color` around the rendered
+    /// grid; the image grows by `frame_size` to make room for it.
+    #[allow(dead_code)]
+    pub fn with_border(mut self, color: Color, thickness: usize) -> Self {
+        self.border = Some((color, thickness));
         self
     }
 
     #[allow(dead_code)]
-    pub fn with_rand_vertical_velocity_probability(
-        mut self,
-        veritical_velocity_probability: fn(r: &mut Random) -> i16,
-    ) -> Self {
-        self.random.vertical_velocity_probability = veritical_velocity_probability;
+    pub fn with_seed_variation(mut self, seed_variation: bool) -> Self {
+        self.seed_variation = seed_variation;
         self
     }
 
@@ -514,6 +2401,7 @@ impl Grid {
         self
     }
 
+    /// See [`WindowGrid::new`] for how out of range sizes are clamped.
     #[allow(dead_code)]
     pub fn with_window_size(mut self, window_size: (usize, usize)) -> Self {
         self.window_grid = WindowGrid::new((self.width, self.height), window_size);
@@ -531,6 +2419,7 @@ impl Grid {
 mod tests {
     use bevy::color::{Gray, Hsva};
     use pretty_assertions::assert_eq;
+    use std::sync::atomic::{AtomicUsize, Ordering};
 
     use super::*;
     use crate::component::{
@@ -541,88 +2430,614 @@ mod tests {
     };
 
     #[test]
-    fn test_create_grid() {
-        let g = Grid::new(2, 3);
-        assert_eq!(6, g.get_cells().len());
-        assert_eq!(2, g.width);
-        assert_eq!(3, g.height);
+    fn test_create_grid() {
+        let g = Grid::new(2, 3);
+        assert_eq!(6, g.get_cells().len());
+        assert_eq!(2, g.width);
+        assert_eq!(3, g.height);
+    }
+
+    #[test]
+    fn test_grid_spawn_particle_to_grid() {
+        let mut g = Grid::new(2, 3);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+
+        g.spawn_particle((1, 1), Particle::from(Water::new()));
+
+        assert_eq!(
+            vec![
+                Cell::new(Particle::from(Sand::new())),
+                Cell::empty(),
+                Cell::empty(),
+                Cell::new(Particle::from(Water::new())),
+                Cell::empty(),
+                Cell::empty(),
+            ],
+            *g.get_cells()
+        );
+    }
+
+    #[test]
+    fn test_grid_spawn_particle_to_grid_spawns_with_current_cycle() {
+        let mut g = Grid::new(1, 2);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+
+        g.update_grid();
+
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+
+        assert_eq!(
+            vec![
+                Cell::new(Particle::from(Sand::new())).with_cycle(1),
+                Cell::new(Particle::from(Sand::new())).with_cycle(1),
+            ],
+            *g.get_cells()
+        );
+    }
+
+    #[test]
+    fn test_grid_spawn_particle_to_non_empty_location_silently_fails() {
+        let mut g = Grid::new(2, 3);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+
+        g.spawn_particle((0, 0), Particle::from(Water::new()));
+
+        assert_eq!(Some(Particle::from(Sand::new())), g.get_cell(0).particle);
+    }
+
+    #[test]
+    fn test_grid_spawn_particle_out_of_grid_bound_silently_fails() {
+        let mut g = Grid::new(2, 3);
+        g.spawn_particle((0, 3), Particle::from(Sand::new()));
+        g.spawn_particle((2, 0), Particle::from(Water::new()));
+
+        assert_eq!(
+            vec![
+                Cell::empty(),
+                Cell::empty(),
+                Cell::empty(),
+                Cell::empty(),
+                Cell::empty(),
+                Cell::empty(),
+            ],
+            *g.get_cells()
+        );
+    }
+
+    #[test]
+    fn test_grid_despawn_particle_empties_the_cell_particle() {
+        let mut g = Grid::new(1, 1);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        assert_eq!(Cell::new(Particle::from(Sand::new())), *g.get_cell(0));
+
+        g.despawn_particle((0, 0));
+        assert_eq!(Cell::empty(), *g.get_cell(0));
+    }
+
+    #[test]
+    fn test_replace_kind_converts_every_particle_of_a_kind() {
+        /*
+         * s-w -> r-w
+         * sws    rwr
+         */
+        let mut g = Grid::new(3, 2);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()).with_seed(3));
+        g.spawn_particle((2, 0), Particle::from(Water::new()));
+        g.spawn_particle((0, 1), Particle::from(Sand::new()));
+        g.spawn_particle((1, 1), Particle::from(Water::new()));
+        g.spawn_particle((2, 1), Particle::from(Sand::new()));
+        g.cycle = 4;
+
+        g.replace_kind(
+            &ParticleKind::from(Sand::new()),
+            ParticleKind::from(Rock::new()),
+        );
+
+        assert_eq!(
+            vec![
+                Cell::new(Particle::from(Rock::new()).with_seed(3)).with_cycle(4),
+                Cell::empty(),
+                Cell::new(Particle::from(Water::new())),
+                Cell::new(Particle::from(Rock::new())).with_cycle(4),
+                Cell::new(Particle::from(Water::new())),
+                Cell::new(Particle::from(Rock::new())).with_cycle(4),
+            ],
+            *g.get_cells()
+        );
+    }
+
+    #[test]
+    fn test_landing_grain_produces_a_rest_event() {
+        /*
+         * s -> - -> - -> -
+         * -    s    -    -
+         * -    -    s    s
+         */
+        let mut g = Grid::new(1, 3)
+            .with_initial_particle_velocity((0, 0))
+            .with_rand_vertical_velocity_probability(|_| 0);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()).with_velocity((0, 0)));
+
+        let mut events = vec![];
+        for _ in 0..5 {
+            g.update_grid();
+            events.push(g.drain_events());
+        }
+
+        assert_eq!(
+            vec![
+                vec![],
+                vec![],
+                vec![],
+                vec![SimEvent::Rest { position: (0, 2) }],
+                vec![],
+            ],
+            events
+        );
+    }
+
+    #[test]
+    fn test_max_particles_caps_spawning_and_tap_emission() {
+        let sand = Particle::from(Sand::new());
+        let occupied = |g: &Grid| {
+            g.get_cells()
+                .iter()
+                .filter(|c| c.particle.is_some())
+                .count()
+        };
+        let mut g = Grid::new(4, 4).with_max_particles(3);
+        g.spawn_particle((0, 0), Particle::from(Tap::with_particle(&sand)));
+
+        for _ in 0..5 {
+            g.update_grid();
+            assert_eq!(3, g.particle_count);
+            assert_eq!(3, occupied(&g));
+        }
+
+        g.spawn_brush((2, 2), 4, Some(&sand.kind));
+        assert_eq!(3, occupied(&g));
+
+        g.despawn_particle((0, 0));
+        assert_eq!(2, g.particle_count);
+        g.spawn_brush((2, 2), 4, Some(&sand.kind));
+        assert_eq!(3, g.particle_count);
+        assert_eq!(3, occupied(&g));
+    }
+
+    #[test]
+    fn test_particle_count_follows_reactions_and_despawns_without_a_cap() {
+        let mut g = Grid::from_rle("-w-wSwws/rRi-t-a-/r-r-w---/----fFd-/C-c-w--T/8r")
+            .unwrap()
+            .with_random(Random::new().with_rng_seed(5));
+        let occupied = |g: &Grid| {
+            g.get_cells()
+                .iter()
+                .filter(|c| c.particle.is_some())
+                .count()
+        };
+
+        for cycle in 0..20 {
+            if cycle == 10 {
+                g.despawn_particle((2, 1));
+                g.spawn_particle((2, 1), Particle::from(Wire::powered()));
+            }
+            g.update_grid();
+            assert_eq!(occupied(&g), g.particle_count);
+        }
+
+        g.despawn_brush((4, 3), 20, BrushShape::Square);
+        assert_eq!(0, g.particle_count);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        assert_eq!(1, g.particle_count);
+    }
+
+    #[test]
+    fn test_capacity_hint_preallocates_without_changing_the_simulation() {
+        let run = |g: Grid| {
+            let mut g = g
+                .with_random(Random::new().with_rng_seed(3))
+                .with_shuffle_updates(true);
+            g.spawn_brush((4, 2), 5, Some(&ParticleKind::from(Sand::new())));
+            let mut events = vec![];
+            for _ in 0..10 {
+                g.update_grid();
+                events.extend(g.drain_events());
+            }
+            (g.to_rle(), events)
+        };
+
+        assert_eq!(
+            run(Grid::new(8, 8)),
+            run(Grid::new(8, 8).with_capacity_hint(64))
+        );
+
+        let mut g = Grid::new(8, 8)
+            .with_capacity_hint(64)
+            .with_shuffle_updates(true);
+        g.spawn_brush((4, 2), 5, Some(&ParticleKind::from(Sand::new())));
+        let scratch = g.update_positions.as_ptr();
+        for _ in 0..10 {
+            g.update_grid();
+            assert!(64 <= g.drain_events().capacity());
+            assert_eq!(scratch, g.update_positions.as_ptr());
+        }
+    }
+
+    #[test]
+    fn test_spawn_rain_spawns_rate_particles_along_the_top_row() {
+        /*
+         * ----- -> w-w-w
+         * -----    -----
+         */
+        static COLUMN: AtomicUsize = AtomicUsize::new(0);
+        let mut g = Grid::new(5, 2)
+            .with_rand_rain_column(|_| COLUMN.fetch_add(2, Ordering::Relaxed))
+            .with_seed_variation(false);
+
+        g.spawn_rain(&ParticleKind::from(Water::new()), 3);
+
+        assert_eq!("w-w-w/5-", g.to_rle());
+    }
+
+    #[test]
+    fn test_left_edge_inflow_fills_the_grid_over_cycles() {
+        /*
+         * ---- -> w--- -> ... -> 4w
+         * ----    ----           4w
+         * ----    ----           4w
+         */
+        static ROW: AtomicUsize = AtomicUsize::new(0);
+        let mut g = Grid::new(4, 3)
+            .with_rand_rain_column(|_| ROW.fetch_add(1, Ordering::Relaxed))
+            .with_boundary_flow(BoundaryFlow {
+                edge: Edge::Left,
+                kind: ParticleKind::from(Water::new()),
+                rate: 1,
+            });
+
+        g.update_grid();
+        assert_eq!(1, g.to_rle().matches('w').count());
+
+        for _ in 0..100 {
+            g.update_grid();
+        }
+        assert_eq!("4w/4w/4w", g.to_rle());
+    }
+
+    #[test]
+    fn test_trimmed_crops_the_grid_to_the_occupied_cells() {
+        /*
+         * ----
+         * ----    s-
+         * --s- -> -w
+         * ---w
+         */
+        let mut g = Grid::new(4, 4);
+        g.spawn_particle((2, 2), Particle::from(Sand::new()));
+        g.spawn_particle((3, 3), Particle::from(Water::new()));
+        g.cycle = 5;
+        g.get_cell_mut(g.to_index((2, 2))).cycle = 5;
+
+        let t = g.trimmed().unwrap();
+
+        assert_eq!(Some(((2, 2), (3, 3))), g.occupied_bounds());
+        assert_eq!((2, 2), (t.width, t.height));
+        assert_eq!(
+            vec![
+                Cell::new(Particle::from(Sand::new())),
+                Cell::empty(),
+                Cell::empty(),
+                Cell::new(Particle::from(Water::new())),
+            ],
+            *t.get_cells()
+        );
+        assert_eq!(None, Grid::new(3, 3).trimmed().map(|t| t.to_rle()));
+    }
+
+    #[test]
+    fn test_copy_region_and_paste_stamp_at_a_new_location() {
+        /*
+         * sw-- -> sw--
+         * rs--    rssw
+         * ---w    --rs
+         */
+        let mut g = Grid::new(4, 3);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        g.spawn_particle((1, 0), Particle::from(Water::new()));
+        g.spawn_particle((0, 1), Particle::from(Rock::new()));
+        g.spawn_particle((1, 1), Particle::from(Sand::new()));
+        g.spawn_particle((3, 2), Particle::from(Water::new()));
+
+        let stamp = g.copy_region((0, 0), (1, 1));
+        assert_eq!((2, 2), stamp.size);
+
+        g.paste_stamp((2, 1), &stamp, true);
+        assert_eq!("sw2-/r2sw/2-rs", g.to_rle());
+    }
+
+    #[test]
+    fn test_paste_stamp_without_replace_keeps_occupied_cells() {
+        /*
+         * s- -> ss
+         * -r    sr
+         */
+        let mut g = Grid::new(2, 2);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        g.spawn_particle((1, 1), Particle::from(Rock::new()));
+        let stamp = Stamp {
+            cells: vec![Cell::new(Particle::from(Sand::new())); 4],
+            size: (2, 2),
+        };
+
+        g.paste_stamp((0, 0), &stamp, false);
+        assert_eq!("2s/sr", g.to_rle());
+    }
+
+    #[test]
+    fn test_for_each_in_only_touches_cells_inside_the_region() {
+        /*
+         * sss    sss
+         * sss -> s##
+         * sss    s##
+         */
+        let mut g = Grid::new(3, 3).with_window_size((1, 1));
+        for y in 0..3 {
+            for x in 0..3 {
+                g.spawn_particle((x, y), Particle::from(Sand::new()));
+            }
+        }
+        g.cycle = 10;
+
+        let mut visited = vec![];
+        g.for_each_in((1, 1), (5, 5), |c, position| {
+            if let Some(p) = &mut c.particle {
+                p.health = 7;
+            }
+            visited.push(position);
+        });
+
+        assert_eq!(vec![(1, 1), (2, 1), (1, 2), (2, 2)], visited);
+        assert_eq!(
+            vec![u8::MAX, u8::MAX, u8::MAX, u8::MAX, 7, 7, u8::MAX, 7, 7],
+            g.get_cells()
+                .iter()
+                .map(|c| c.particle.as_ref().unwrap().health)
+                .collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![0, 0, 0, 0, 10, 10, 0, 10, 10],
+            g.get_cells().iter().map(|c| c.cycle).collect::<Vec<_>>()
+        );
+        assert_eq!(9, g.active_window_count());
+    }
+
+    #[test]
+    fn test_for_each_in_on_an_empty_grid_visits_nothing() {
+        let mut g = Grid::new(0, 0);
+        let mut visited = vec![];
+
+        g.for_each_in((0, 0), (5, 5), |_, position| visited.push(position));
+
+        assert!(visited.is_empty());
+    }
+
+    #[test]
+    fn test_for_each_in_keeps_an_out_of_sync_count_from_underflowing() {
+        let mut g = Grid::new(2, 1);
+        g[(0, 0)].particle = Some(Particle::from(Sand::new()));
+
+        g.for_each_in((0, 0), (1, 0), |c, _| c.particle = None);
+
+        assert_eq!(0, g.particle_count);
+    }
+
+    #[test]
+    fn test_grid_error_string_messages() {
+        assert_eq!(
+            "position is out of the grid bounds",
+            GridError::OutOfBound.to_string()
+        );
+        assert_eq!(
+            "dimensions do not match the grid",
+            GridError::DimensionMismatch.to_string()
+        );
+        assert_eq!("invalid grid format", GridError::InvalidFormat.to_string());
+        assert_eq!(
+            "checksum does not match the grid",
+            GridError::ChecksumMismatch.to_string()
+        );
+        assert_eq!(
+            "windows must cover every cell of the grid exactly once",
+            GridError::WindowCoverage.to_string()
+        );
+    }
+
+    #[test]
+    fn test_render_ansi_colors_each_particle_glyph() {
+        let g = Grid::from_rle("s-/-r").unwrap();
+        let [r, gr, b] = Particle::from(Sand::new()).color_bytes();
+
+        let ansi = g.render_ansi();
+
+        assert!(ansi.starts_with(&format!("\x1b[38;2;{r};{gr};{b}ms\x1b[0m-\n")));
+        assert_eq!(2, ansi.lines().count());
+        assert_eq!(2, ansi.matches("\x1b[0m").count());
+        assert_eq!("\n\n", Grid::new(0, 2).render_ansi());
+    }
+
+    #[test]
+    fn test_count_neighbors_at_edge_and_interior_cells() {
+        /*
+         * ws-
+         * wwa
+         * rw-
+         */
+        let g = Grid::from_rle("ws-/2wa/rw-").unwrap();
+        let liquid = |p: &Particle| p.is_liquid();
+
+        assert_eq!(2, g.count_neighbors((0, 0), liquid));
+        assert_eq!(4, g.count_neighbors((1, 1), liquid));
+        assert_eq!(3, g.count_neighbors((2, 2), liquid));
+        assert_eq!(
+            2,
+            g.count_neighbors((1, 1), |p| p.is_solid() || p.is_powder())
+        );
+    }
+
+    #[test]
+    fn test_grid_layout_checksum_changes_with_the_layout_only() {
+        let g = Grid::from_rle("s-/-w").unwrap();
+
+        assert_eq!(
+            g.layout_checksum(),
+            Grid::from_rle("s-/-w").unwrap().layout_checksum()
+        );
+        assert_ne!(
+            g.layout_checksum(),
+            Grid::from_rle("s-/w-").unwrap().layout_checksum()
+        );
+        let mut damaged = Grid::from_rle("s-/-w").unwrap();
+        damaged[(0, 0)].particle.as_mut().unwrap().health = 1;
+        assert_eq!(g.layout_checksum(), damaged.layout_checksum());
     }
 
     #[test]
-    fn test_grid_spawn_particle_to_grid() {
-        let mut g = Grid::new(2, 3);
-        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+    fn test_grid_saves_and_loads_a_layout_file_and_rejects_corrupted_ones() {
+        let path = std::env::temp_dir().join(format!("sandsim-grid-{}.txt", std::process::id()));
+        let g = Grid::from_rle("s2-/r-w").unwrap();
 
-        g.spawn_particle((1, 1), Particle::from(Water::new()));
+        g.save_layout_to_file(&path).unwrap();
+        let loaded = Grid::load_layout_from_file(&path).map(|l| l.to_rle());
 
+        let saved = fs::read_to_string(&path).unwrap();
+        fs::write(&path, saved.replace("r-w", "r-s")).unwrap();
+        let corrupted = Grid::load_layout_from_file(&path).err();
+        let _ = fs::remove_file(&path);
+
+        assert_eq!(Ok(g.to_rle()), loaded);
+        assert_eq!(Some(GridError::ChecksumMismatch), corrupted);
         assert_eq!(
-            vec![
-                Cell::new(Particle::from(Sand::new())),
-                Cell::empty(),
-                Cell::empty(),
-                Cell::new(Particle::from(Water::new())),
-                Cell::empty(),
-                Cell::empty(),
-            ],
-            *g.get_cells()
+            Some(GridError::Io(io::ErrorKind::NotFound)),
+            Grid::load_layout_from_file(&path).err()
         );
     }
 
     #[test]
-    fn test_grid_spawn_particle_to_grid_spawns_with_current_cycle() {
-        let mut g = Grid::new(1, 2);
-        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+    fn test_grid_round_trips_through_an_image() {
+        let g = Grid::from_rle("s2-/r-w/SaB").unwrap();
 
-        g.update_grid();
+        let image = g.to_image();
 
-        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        assert_eq!((3, 3), (image.width(), image.height()));
+        assert_eq!(Ok(g.to_rle()), Grid::from_image(&image).map(|g| g.to_rle()));
+    }
+
+    #[test]
+    fn test_grid_from_image_leaves_transparent_pixels_empty() {
+        let mut image = Grid::create_output_frame(2, 1);
+        image
+            .set_color_at(0, 0, Particle::from(Sand::new()).color())
+            .unwrap();
+        image.set_color_at(1, 0, Color::NONE).unwrap();
 
         assert_eq!(
-            vec![
-                Cell::new(Particle::from(Sand::new())).with_cycle(1),
-                Cell::new(Particle::from(Sand::new())).with_cycle(1),
-            ],
-            *g.get_cells()
+            Ok("s-".to_string()),
+            Grid::from_image(&image).map(|g| g.to_rle())
         );
     }
 
+    #[cfg(feature = "export")]
     #[test]
-    fn test_grid_spawn_particle_to_non_empty_location_silently_fails() {
-        let mut g = Grid::new(2, 3);
+    fn test_grid_exports_a_png_file() {
+        let path = std::env::temp_dir().join(format!("sandsim-grid-{}.png", std::process::id()));
+        let g = Grid::from_rle("s-/-w").unwrap();
+
+        g.export_png(&path).unwrap();
+        let exported = image::open(&path).map(|i| (i.width(), i.height()));
+        let _ = fs::remove_file(&path);
+
+        assert_eq!((2, 2), exported.unwrap());
+    }
+
+    #[test]
+    fn test_kind_at_returns_the_kind_of_occupied_cells_only() {
+        /*
+         * s-
+         */
+        let mut g = Grid::new(2, 1);
         g.spawn_particle((0, 0), Particle::from(Sand::new()));
 
-        g.spawn_particle((0, 0), Particle::from(Water::new()));
+        assert_eq!(Some(ParticleKind::from(Sand::new())), g.kind_at((0, 0)));
+        assert_eq!(None, g.kind_at((1, 0)));
+        assert_eq!(None, g.kind_at((2, 0)));
+        assert_eq!(None, g.kind_at((0, 1)));
+    }
 
-        assert_eq!(Some(Particle::from(Sand::new())), g.get_cell(0).particle);
+    #[test]
+    fn test_grid_cells_can_be_indexed_by_position() {
+        /*
+         * s- -> s-
+         * --    -r
+         */
+        let mut g = Grid::new(2, 2).with_window_size((1, 1));
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+
+        assert_eq!(Cell::new(Particle::from(Sand::new())), g[(0, 0)]);
+        assert_eq!(Cell::empty(), g[(1, 1)]);
+
+        g.cycle = 5;
+        g[(1, 1)].particle = Some(Particle::from(Rock::new()));
+        assert_eq!(0, g.active_window_count());
+
+        g.touch((1, 1));
+        assert_eq!("s-/-r", g.to_rle());
+        assert_eq!(5, g[(1, 1)].cycle);
+        assert_eq!(4, g.active_window_count());
     }
 
     #[test]
-    fn test_grid_spawn_particle_out_of_grid_bound_silently_fails() {
-        let mut g = Grid::new(2, 3);
-        g.spawn_particle((0, 3), Particle::from(Sand::new()));
-        g.spawn_particle((2, 0), Particle::from(Water::new()));
+    #[should_panic(expected = "position (2, 0) is out of the 2x1 grid")]
+    fn test_grid_index_panics_outside_the_grid() {
+        let g = Grid::new(2, 1);
+        let _ = &g[(2, 0)];
+    }
+
+    #[test]
+    fn test_grid_despawn_if_only_empties_matching_particles() {
+        /*
+         * sw -> s-
+         */
+        let mut g = Grid::new(2, 1);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        g.spawn_particle((1, 0), Particle::from(Water::new()));
+
+        let is_water = |p: &Particle| matches!(p.kind, ParticleKind::Water(..));
+        g.despawn_if((0, 0), is_water);
+        g.despawn_if((1, 0), is_water);
+        g.despawn_if((2, 0), is_water);
 
         assert_eq!(
-            vec![
-                Cell::empty(),
-                Cell::empty(),
-                Cell::empty(),
-                Cell::empty(),
-                Cell::empty(),
-                Cell::empty(),
-            ],
+            vec![Cell::new(Particle::from(Sand::new())), Cell::empty()],
             *g.get_cells()
         );
     }
 
     #[test]
-    fn test_grid_despawn_particle_empties_the_cell_particle() {
-        let mut g = Grid::new(1, 1);
+    fn test_erase_brush_only_erases_the_given_kind() {
+        /*
+         * ssw -> ss-
+         * www    w-w
+         */
+        let mut g = Grid::new(3, 2);
         g.spawn_particle((0, 0), Particle::from(Sand::new()));
-        assert_eq!(Cell::new(Particle::from(Sand::new())), *g.get_cell(0));
+        g.spawn_particle((1, 0), Particle::from(Sand::new()));
+        for (x, y) in [(2, 0), (0, 1), (1, 1), (2, 1)] {
+            g.spawn_particle((x, y), Particle::from(Water::new()));
+        }
 
-        g.despawn_particle((0, 0));
-        assert_eq!(Cell::empty(), *g.get_cell(0));
+        g.erase_brush((1, 0), 3, &ParticleKind::from(Water::with_capacity(0)));
+
+        assert_eq!("2s-/w-w", g.to_rle());
     }
 
     #[test]
@@ -668,6 +3083,32 @@ mod tests {
         assert_eq!(vec![Cell::empty().with_cycle(1)], *g.get_cells());
     }
 
+    #[test]
+    fn test_spawn_many_matches_individual_spawns() {
+        /*
+         * s-w
+         * rrr
+         */
+        let items = vec![
+            ((0, 0), Particle::from(Sand::new())),
+            ((2, 0), Particle::from(Water::new())),
+            ((0, 1), Particle::from(Rock::new())),
+            ((1, 1), Particle::from(Rock::new())),
+            ((2, 1), Particle::from(Rock::new())),
+            ((3, 1), Particle::from(Rock::new())),
+        ];
+
+        let mut expected = Grid::new(3, 2);
+        for (position, particle) in items.clone() {
+            expected.spawn_particle(position, particle);
+        }
+        let mut g = Grid::new(3, 2);
+        g.spawn_many(items);
+
+        assert_eq!(*expected.get_cells(), *g.get_cells());
+        assert_eq!(expected.window_grid.windows, g.window_grid.windows);
+    }
+
     #[test]
     fn test_spawn_particles_brush_sets_a_random_seed_to_particles() {
         let mut g = Grid::new(1, 1).with_rand_seed(|_| 255);
@@ -679,6 +3120,258 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_spawn_particles_brush_without_seed_variation_spawns_identical_colors() {
+        let mut g = Grid::new(3, 3)
+            .with_rand_seed(|r| r.rng.u8(..))
+            .with_seed_variation(false);
+        (0..5).for_each(|_| g.update_grid());
+        g.spawn_brush((1, 1), 3, Some(&ParticleKind::from(Sand::new())));
+
+        let color = Particle::from(Sand::new()).with_seed(127).color();
+        for p in g.get_cells().iter().filter_map(|c| c.particle.as_ref()) {
+            assert_color_srgb_eq!(color, p.color());
+        }
+    }
+
+    #[test]
+    fn test_spawn_brush_over_existing_particles_with_and_without_replacing() {
+        /*
+         * www -> www | sss
+         */
+        let sand = ParticleKind::from(Sand::new());
+        let mut g = Grid::new(3, 1);
+        for x in 0..3 {
+            g.spawn_particle((x, 0), Particle::from(Water::new()));
+        }
+
+        g.spawn_brush((1, 0), 3, Some(&sand));
+        assert_eq!("3w", g.to_rle());
+
+        g.spawn_brush_replacing((1, 0), 3, Some(&sand));
+        assert_eq!("3s", g.to_rle());
+    }
+
+    #[cfg(feature = "particle_ids")]
+    #[test]
+    fn test_particle_id_follows_the_grain_as_it_falls() {
+        /*
+         * s- -> --
+         * -s    --
+         * --    ss
+         */
+        let mut g = Grid::new(2, 3).with_rand_vertical_velocity_probability(|_| 0);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        g.spawn_particle((1, 1), Particle::from(Sand::new()));
+        let id = |g: &Grid, position| {
+            g.get_cell(g.to_index(position))
+                .particle
+                .as_ref()
+                .map(|p| p.id.0)
+        };
+        assert_eq!((Some(1), Some(2)), (id(&g, (0, 0)), id(&g, (1, 1))));
+
+        g.run_until_settled(10);
+
+        assert_eq!("2-/2-/2s", g.to_rle());
+        assert_eq!((Some(1), Some(2)), (id(&g, (0, 2)), id(&g, (1, 2))));
+    }
+
+    #[test]
+    fn test_paint_brush_modes_over_a_mixed_grid() {
+        /*
+         * -w-    -w-    -s-    -w-
+         * r--    rss    sss    rss
+         * --- -> -s-  | -s-  | -s-
+         *     FillEmpty Replace Behind
+         *
+         * erasing:
+         * -w-    ---    ---    -w-
+         * r--    ---    ---    r--
+         * --- -> ---  | ---  | ---
+         */
+        let sand = ParticleKind::from(Sand::new());
+        let paint = |mode: PaintMode, kind: Option<&ParticleKind>| {
+            let mut g = Grid::from_rle("-w-/r2-/3-").unwrap();
+            g.paint_brush((1, 1), 3, kind, mode);
+            g.to_rle()
+        };
+
+        assert_eq!("-w-/r2s/-s-", paint(PaintMode::FillEmpty, Some(&sand)));
+        assert_eq!("-s-/3s/-s-", paint(PaintMode::Replace, Some(&sand)));
+        assert_eq!("-w-/r2s/-s-", paint(PaintMode::Behind, Some(&sand)));
+        assert_eq!("3-/3-/3-", paint(PaintMode::FillEmpty, None));
+        assert_eq!("3-/3-/3-", paint(PaintMode::Replace, None));
+        assert_eq!("-w-/r2-/3-", paint(PaintMode::Behind, None));
+    }
+
+    #[test]
+    fn test_spawn_brush_mix_places_both_kinds() {
+        let mut g = Grid::new(21, 21);
+        g.spawn_brush_mix(
+            (10, 10),
+            20,
+            Some(&ParticleKind::from(Sand::new())),
+            &ParticleKind::from(Salt::new()),
+            0.5,
+            PaintMode::FillEmpty,
+        );
+
+        let rle = g.to_rle();
+        assert!(rle.contains('s'));
+        assert!(rle.contains('S'));
+    }
+
+    #[test]
+    fn test_spawn_brush_mix_picks_the_kind_per_cell_from_the_ratio() {
+        /*
+         * -s-    -S-
+         * sss -> SSS
+         * -s-    -S-
+         */
+        let mut g = Grid::new(3, 3).with_rand_mix_probability(|_| 0.5);
+        let (sand, salt) = (
+            ParticleKind::from(Sand::new()),
+            ParticleKind::from(Salt::new()),
+        );
+        g.spawn_brush_mix((1, 1), 3, Some(&sand), &salt, 0.5, PaintMode::FillEmpty);
+        assert_eq!("-s-/3s/-s-", g.to_rle());
+
+        let mut g = Grid::new(3, 3).with_rand_mix_probability(|_| 0.5);
+        g.spawn_brush_mix((1, 1), 3, Some(&sand), &salt, 0.6, PaintMode::FillEmpty);
+        assert_eq!("-S-/3S/-S-", g.to_rle());
+    }
+
+    #[test]
+    fn test_spawn_brush_spray_scatters_particles_outward_with_varied_velocities() {
+        /*
+         * -s-
+         * sss
+         * -s-
+         */
+        static SPRAY: AtomicUsize = AtomicUsize::new(1);
+        let mut g = Grid::new(3, 3)
+            .with_initial_particle_velocity((0, 0))
+            .with_rand_spray_velocity(|_| {
+                let n = SPRAY.fetch_add(1, Ordering::Relaxed) as i16;
+                (n * 10, n * 100)
+            });
+
+        g.spawn_brush_spray(
+            (1, 1),
+            3,
+            Some(&ParticleKind::from(Sand::new())),
+            PaintMode::FillEmpty,
+        );
+
+        assert_eq!("-s-/3s/-s-", g.to_rle());
+        let velocity = |position| g[position].particle.as_ref().unwrap().velocity;
+        assert!(velocity((0, 1)).0 < 0);
+        assert!(velocity((2, 1)).0 > 0);
+        let velocities: Vec<_> = [(1, 0), (0, 1), (1, 1), (2, 1), (1, 2)]
+            .into_iter()
+            .map(velocity)
+            .collect();
+        assert!(velocities.iter().all(|&v| v != (0, 0)));
+        for (i, v) in velocities.iter().enumerate() {
+            assert!(!velocities[i + 1..].contains(v));
+        }
+    }
+
+    #[test]
+    fn test_spawn_brush_spray_scatters_particle_positions_away_from_the_brush() {
+        /*
+         * -----------    -------s---
+         * -----------    -----------
+         * -----------    -----------
+         * -----------    -----------
+         * -----s-----    -----------
+         * ----sss---- -> -----------
+         * -----s-----    -----------
+         * -----------    -----------
+         * -----------    -----------
+         * -----------    --s----ss--
+         * -----------    -------s---
+         */
+        let mut g = Grid::new(11, 11)
+            .with_rand_spray_velocity(|_| (512, i16::MAX))
+            .with_rand_spray_direction(|_| false);
+
+        g.spawn_brush_spray(
+            (5, 5),
+            3,
+            Some(&ParticleKind::from(Sand::new())),
+            PaintMode::FillEmpty,
+        );
+
+        assert_eq!(
+            "7-s3-/11-/11-/11-/11-/11-/11-/11-/11-/2-s4-2s2-/7-s3-",
+            g.to_rle()
+        );
+    }
+
+    #[test]
+    fn test_spawn_brush_spray_and_mix_follow_the_paint_mode() {
+        /*
+         * -w-    -s-      -w-
+         * r-- -> 3s     | r2s
+         * ---    -s-      -s-
+         *        Replace  Behind
+         */
+        let sand = ParticleKind::from(Sand::new());
+        let grid = || {
+            Grid::from_rle("-w-/r2-/3-")
+                .unwrap()
+                .with_rand_spray_velocity(|_| (0, 0))
+                .with_rand_mix_probability(|_| 1.)
+        };
+        for (mode, expected) in [
+            (PaintMode::Replace, "-s-/3s/-s-"),
+            (PaintMode::Behind, "-w-/r2s/-s-"),
+        ] {
+            let mut g = grid();
+            g.spawn_brush_spray((1, 1), 3, Some(&sand), mode);
+            assert_eq!(expected, g.to_rle());
+
+            let mut g = grid();
+            g.spawn_brush_mix((1, 1), 3, Some(&sand), &sand, 0.5, mode);
+            assert_eq!(expected, g.to_rle());
+        }
+    }
+
+    #[test]
+    fn test_spawn_brush_spray_drops_particles_sprayed_past_the_edge() {
+        let mut g = Grid::new(3, 3)
+            .with_rand_spray_velocity(|_| (1024, i16::MAX))
+            .with_rand_spray_direction(|_| true);
+
+        g.spawn_brush_spray(
+            (0, 0),
+            1,
+            Some(&ParticleKind::from(Sand::new())),
+            PaintMode::FillEmpty,
+        );
+
+        assert_eq!("3-/3-/3-", g.to_rle());
+        assert_eq!(0, g.particle_count);
+    }
+
+    #[test]
+    fn test_spawn_brush_spray_on_an_empty_grid_does_nothing() {
+        for (width, height) in [(0, 3), (3, 0)] {
+            let mut g = Grid::new(width, height);
+
+            g.spawn_brush_spray(
+                (1, 1),
+                3,
+                Some(&ParticleKind::from(Sand::new())),
+                PaintMode::FillEmpty,
+            );
+
+            assert_eq!(0, g.particle_count);
+        }
+    }
+
     #[test]
     fn test_spawn_particles_brush_sets_initial_velocity_to_particles() {
         let mut g = Grid::new(1, 1)
@@ -694,98 +3387,190 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_spawn_particles_brush_size_zero_paints_nothing() {
+        let mut g = Grid::new(3, 3).with_rand_seed(|_| 255);
+        g.spawn_brush((1, 1), 0, Some(&ParticleKind::from(Sand::new())));
+
+        assert_eq!("3-/3-/3-", g.to_rle());
+    }
+
+    #[test]
+    fn test_spawn_particles_brush_even_size_paints_size_cells_across() {
+        let sand = ParticleKind::from(Sand::new());
+        for size in [2, 4, 6] {
+            let mut g = Grid::new(9, 9).with_rand_seed(|_| 255);
+            g.spawn_brush((4, 4), size, Some(&sand));
+
+            let row = (0..9)
+                .filter(|&x| g.get_cells()[g.to_index((x, 4))].particle.is_some())
+                .count();
+            let column = (0..9)
+                .filter(|&y| g.get_cells()[g.to_index((4, y))].particle.is_some())
+                .count();
+            assert_eq!((size, size), (row, column));
+        }
+    }
+
+    #[test]
+    fn test_spawn_particles_brush_clips_at_grid_corner() {
+        /*
+         * ss-
+         * s--
+         * ---
+         */
+        let mut g = Grid::new(3, 3).with_rand_seed(|_| 255);
+        g.spawn_brush((0, 0), 3, Some(&ParticleKind::from(Sand::new())));
+
+        assert_eq!("2s-/s2-/3-", g.to_rle());
+    }
+
     #[test]
     fn test_spawn_particles_brush_size_one() {
         /*
-         * ---
-         * -s-
-         * ---
+         * ---
+         * -s-
+         * ---
+         */
+        let mut g = Grid::new(3, 3).with_rand_seed(|_| 255);
+        g.spawn_brush((1, 1), 1, Some(&ParticleKind::from(Sand::new())));
+
+        let particle = Particle::from(Sand::new());
+        assert_eq!(
+            vec![
+                Cell::empty(),
+                Cell::empty(),
+                Cell::empty(),
+                Cell::empty(),
+                Cell::new(particle.clone()),
+                Cell::empty(),
+                Cell::empty(),
+                Cell::empty(),
+                Cell::empty(),
+            ],
+            *g.get_cells()
+        );
+    }
+
+    #[test]
+    fn test_spawn_particles_brush_size_two() {
+        /*
+         * ss-
+         * ss-
+         * ---
+         */
+        let mut g = Grid::new(3, 3).with_rand_seed(|_| 255);
+        g.spawn_brush((1, 1), 2, Some(&ParticleKind::from(Sand::new())));
+
+        assert_eq!("2s-/2s-/3-", g.to_rle());
+    }
+
+    #[test]
+    fn test_spawn_particles_brush_size_four() {
+        /*
+         * -ss--
+         * ssss-
+         * ssss-
+         * -ss--
+         * -----
+         */
+        let mut g = Grid::new(5, 5).with_rand_seed(|_| 255);
+        g.spawn_brush((2, 2), 4, Some(&ParticleKind::from(Sand::new())));
+
+        assert_eq!("-2s2-/4s-/4s-/-2s2-/5-", g.to_rle());
+    }
+
+    #[test]
+    fn test_despawn_brush_erases_a_circle_from_a_filled_grid() {
+        /*
+         * sssss -> sssss
+         * sssss    ss-ss
+         * sssss    s---s
+         * sssss    ss-ss
+         * sssss    sssss
+         */
+        let mut g = Grid::new(5, 5);
+        g.flood_to_level(0, ParticleKind::from(Sand::new()));
+
+        g.despawn_brush((2, 2), 3, BrushShape::Circle);
+
+        assert_eq!("5s/2s-2s/s3-s/2s-2s/5s", g.to_rle());
+    }
+
+    #[test]
+    fn test_despawn_brush_even_size_erases_size_cells_across() {
+        /*
+         * sssss -> sssss
+         * sssss    s--ss
+         * sssss    s--ss
+         * sssss    sssss
+         * sssss    sssss
+         */
+        let mut g = Grid::new(5, 5);
+        g.flood_to_level(0, ParticleKind::from(Sand::new()));
+
+        g.despawn_brush((2, 2), 2, BrushShape::Square);
+
+        assert_eq!("5s/s2-2s/s2-2s/5s/5s", g.to_rle());
+    }
+
+    #[test]
+    fn test_despawn_brush_erases_a_square_from_a_filled_grid() {
+        /*
+         * sss -> --s
+         * sss    --s
+         * sss    sss
          */
-        let mut g = Grid::new(3, 3).with_rand_seed(|_| 255);
-        g.spawn_brush((1, 1), 1, Some(&ParticleKind::from(Sand::new())));
+        let mut g = Grid::new(3, 3);
+        g.flood_to_level(0, ParticleKind::from(Sand::new()));
 
-        let particle = Particle::from(Sand::new());
-        assert_eq!(
-            vec![
-                Cell::empty(),
-                Cell::empty(),
-                Cell::empty(),
-                Cell::empty(),
-                Cell::new(particle.clone()),
-                Cell::empty(),
-                Cell::empty(),
-                Cell::empty(),
-                Cell::empty(),
-            ],
-            *g.get_cells()
-        );
+        g.despawn_brush((0, 0), 3, BrushShape::Square);
+
+        assert_eq!("2-s/2-s/3s", g.to_rle());
     }
 
     #[test]
-    fn test_spawn_particles_brush_size_two() {
+    fn test_despawn_particles_brush() {
         /*
-         * -s-
-         * sss
-         * -s-
+         * --- -> ss- -> ---
+         * ---    ss-    ---
+         * ---    ---    ---
          */
         let mut g = Grid::new(3, 3).with_rand_seed(|_| 255);
+
         g.spawn_brush((1, 1), 2, Some(&ParticleKind::from(Sand::new())));
 
         let particle = Particle::from(Sand::new());
         assert_eq!(
             vec![
-                Cell::empty(),
                 Cell::new(particle.clone()),
-                Cell::empty(),
                 Cell::new(particle.clone()),
+                Cell::empty(),
                 Cell::new(particle.clone()),
                 Cell::new(particle.clone()),
                 Cell::empty(),
-                Cell::new(particle.clone()),
+                Cell::empty(),
+                Cell::empty(),
                 Cell::empty(),
             ],
             *g.get_cells()
         );
-    }
 
-    #[test]
-    fn test_spawn_particles_brush_size_four() {
-        /*
-         * --s--
-         * -sss-
-         * sssss
-         * -sss-
-         * --s--
-         */
-        let mut g = Grid::new(5, 5).with_rand_seed(|_| 255);
-        g.spawn_brush((2, 2), 4, Some(&ParticleKind::from(Sand::new())));
+        {
+            let this = &mut g;
+            let position = (1, 1);
+            this.spawn_brush(position, 2, None);
+        };
 
-        let particle = Particle::from(Sand::new());
         assert_eq!(
             vec![
                 Cell::empty(),
                 Cell::empty(),
-                Cell::new(particle.clone()),
-                Cell::empty(),
-                Cell::empty(),
-                Cell::empty(),
-                Cell::new(particle.clone()),
-                Cell::new(particle.clone()),
-                Cell::new(particle.clone()),
                 Cell::empty(),
-                Cell::new(particle.clone()),
-                Cell::new(particle.clone()),
-                Cell::new(particle.clone()),
-                Cell::new(particle.clone()),
-                Cell::new(particle.clone()),
                 Cell::empty(),
-                Cell::new(particle.clone()),
-                Cell::new(particle.clone()),
-                Cell::new(particle.clone()),
                 Cell::empty(),
                 Cell::empty(),
                 Cell::empty(),
-                Cell::new(particle.clone()),
                 Cell::empty(),
                 Cell::empty(),
             ],
@@ -794,54 +3579,53 @@ mod tests {
     }
 
     #[test]
-    fn test_despawn_particles_brush() {
+    fn test_draw_line_between_two_points() {
         /*
-         * --- -> -s- -> ---
-         * ---    sss    ---
-         * ---    -s-    ---
+         * s---
+         * -s--
+         * --s-
+         * ---s
          */
-        let mut g = Grid::new(3, 3).with_rand_seed(|_| 255);
-
-        g.spawn_brush((1, 1), 2, Some(&ParticleKind::from(Sand::new())));
+        let mut g = Grid::new(4, 4).with_rand_seed(|_| 255);
+        g.draw_line((0, 0), (3, 3), 1, Some(&ParticleKind::from(Sand::new())));
 
         let particle = Particle::from(Sand::new());
         assert_eq!(
             vec![
-                Cell::empty(),
                 Cell::new(particle.clone()),
                 Cell::empty(),
-                Cell::new(particle.clone()),
-                Cell::new(particle.clone()),
-                Cell::new(particle.clone()),
                 Cell::empty(),
-                Cell::new(particle.clone()),
                 Cell::empty(),
-            ],
-            *g.get_cells()
-        );
-
-        {
-            let this = &mut g;
-            let position = (1, 1);
-            this.spawn_brush(position, 2, None);
-        };
-
-        assert_eq!(
-            vec![
                 Cell::empty(),
+                Cell::new(particle.clone()),
                 Cell::empty(),
                 Cell::empty(),
                 Cell::empty(),
                 Cell::empty(),
+                Cell::new(particle.clone()),
                 Cell::empty(),
                 Cell::empty(),
                 Cell::empty(),
                 Cell::empty(),
+                Cell::new(particle.clone()),
             ],
             *g.get_cells()
         );
     }
 
+    #[test]
+    fn test_draw_line_traverses_cells_between_the_points() {
+        assert_eq!(
+            vec![(0, 0), (1, 1), (2, 1), (3, 2), (4, 2)],
+            Grid::line((0, 0), (4, 2)).collect::<Vec<_>>()
+        );
+        assert_eq!(
+            vec![(2, 3), (2, 2), (1, 1), (1, 0)],
+            Grid::line((2, 3), (1, 0)).collect::<Vec<_>>()
+        );
+        assert_eq!(vec![(1, 1)], Grid::line((1, 1), (1, 1)).collect::<Vec<_>>());
+    }
+
     #[test]
     fn test_draw_grid() {
         let mut g = Grid::new(2, 2);
@@ -875,6 +3659,100 @@ mod tests {
         assert_color_srgb_eq!(BACKGROUND_COLOR, image.get_color_at(1, 1).unwrap());
     }
 
+    #[test]
+    fn test_draw_grid_dither_only_changes_low_order_bits() {
+        let draw = |dither: bool| {
+            let mut g = Grid::new(4, 4).with_rand_seed(|_| 127).with_dither(dither);
+            g.flood_to_level(0, ParticleKind::from(Sand::new()));
+            let mut image = Grid::create_output_frame(4, 4);
+            g.draw_grid(&mut image);
+            image.data.unwrap()
+        };
+
+        let (plain, dithered) = (draw(false), draw(true));
+
+        assert_ne!(plain, dithered);
+        for (a, b) in plain.iter().zip(dithered.iter()) {
+            assert!(a.abs_diff(*b) <= 2);
+        }
+    }
+
+    #[test]
+    fn test_draw_grid_dither_leaves_background_untouched() {
+        let mut image = Grid::create_output_frame(4, 4);
+        Grid::new(4, 4).with_dither(true).draw_grid(&mut image);
+
+        let mut plain = Grid::create_output_frame(4, 4);
+        Grid::new(4, 4).draw_grid(&mut plain);
+
+        assert_eq!(plain.data, image.data);
+    }
+
+    #[test]
+    fn test_draw_grid_border_surrounds_the_grid_without_hiding_cells() {
+        let mut g = Grid::new(5, 5).with_border(Color::WHITE, 1);
+        g.flood_to_level(0, ParticleKind::from(Sand::new()));
+        assert_eq!((7, 7), g.frame_size());
+        let mut image = Grid::create_output_frame(7, 7);
+
+        g.draw_grid(&mut image);
+
+        for y in 0..7 {
+            for x in 0..7 {
+                let color = image.get_color_at(x, y).unwrap();
+                if x == 0 || y == 0 || x == 6 || y == 6 {
+                    assert_color_srgb_eq!(Color::WHITE, color);
+                } else {
+                    assert_color_srgb_eq!(Particle::from(Sand::new()).color(), color, 0.1);
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn test_draw_grid_border_thickness() {
+        let mut g = Grid::new(6, 6).with_border(Color::WHITE, 2);
+        assert_eq!((10, 10), g.frame_size());
+        let mut image = Grid::create_output_frame(10, 10);
+        g.draw_grid(&mut image);
+
+        assert_color_srgb_eq!(Color::WHITE, image.get_color_at(1, 1).unwrap());
+        assert_color_srgb_eq!(Color::WHITE, image.get_color_at(8, 2).unwrap());
+        assert_color_srgb_eq!(BACKGROUND_COLOR, image.get_color_at(2, 2).unwrap());
+        assert_color_srgb_eq!(BACKGROUND_COLOR, image.get_color_at(7, 7).unwrap());
+    }
+
+    #[test]
+    fn test_draw_grid_dims_cells_in_long_inactive_windows() {
+        /*
+         * window: (0,0) (1,0)
+         *          r     r
+         */
+        let mut g = Grid::new(2, 1)
+            .with_window_size((1, 1))
+            .with_sleep_dimming(5);
+        g.spawn_particle((0, 0), Particle::from(Rock::new()));
+        g.spawn_particle((1, 0), Particle::from(Rock::new()));
+        let mut image = Grid::create_output_frame(2, 1);
+        for _ in 0..10 {
+            g.update_grid();
+        }
+        g.draw_grid(&mut image);
+        let dimmed = image.get_color_at(0, 0).unwrap();
+
+        g.window_grid
+            .get_window_mut((1, 0))
+            .unwrap()
+            .activate(g.cycle);
+        g.draw_grid(&mut image);
+
+        let awake = image.get_color_at(1, 0).unwrap();
+        let asleep = image.get_color_at(0, 0).unwrap();
+        assert!(Hsva::from(asleep).value < Hsva::from(awake).value);
+        assert_color_srgb_eq!(Particle::from(Rock::new()).color(), awake);
+        assert_color_srgb_eq!(dimmed, asleep);
+    }
+
     #[test]
     fn test_draw_grid_only_redraw_changed_cells() {
         let mut g = Grid::new(2, 2);
@@ -949,6 +3827,41 @@ mod tests {
         assert_color_srgb_eq!(Color::Hsva(Hsva::BLACK), image.get_color_at(1, 1).unwrap());
     }
 
+    #[test]
+    fn test_draw_grid_draws_paint_added_without_updating_the_grid() {
+        let mut g = Grid::new(2, 1);
+        g.update_grid();
+        let mut image = Grid::create_output_frame(2, 1);
+        g.draw_grid(&mut image);
+
+        g.spawn_brush((1, 0), 1, Some(&ParticleKind::from(Rock::new())));
+        g.draw_grid(&mut image);
+
+        assert_color_srgb_eq!(BACKGROUND_COLOR, image.get_color_at(0, 0).unwrap());
+        assert_color_srgb_eq!(
+            g.get_cell(1).particle.as_ref().unwrap().color(),
+            image.get_color_at(1, 0).unwrap()
+        );
+
+        g.spawn_brush((1, 0), 1, None);
+        g.draw_grid(&mut image);
+
+        assert_color_srgb_eq!(BACKGROUND_COLOR, image.get_color_at(1, 0).unwrap());
+    }
+
+    #[test]
+    fn test_particle_color_bytes_match_its_color() {
+        for particle in [
+            Particle::from(Sand::new()),
+            Particle::from(Water::with_capacity(1)),
+            Particle::from(Rock::new()),
+        ] {
+            let [r, g, b, a] = particle.color().to_srgba().to_u8_array();
+            assert_eq!([r, g, b], particle.color_bytes());
+            assert_eq!([r, g, b, a], particle.color_rgba());
+        }
+    }
+
     #[test]
     fn test_get_particle_color() {
         assert_color_srgb_eq!(
@@ -1020,6 +3933,95 @@ mod tests {
         assert_eq!("d", Cell::new(Particle::from(Drain::new())).to_string());
         assert_eq!("t", Cell::new(Particle::from(Tap::new())).to_string());
         assert_eq!("a", Cell::new(Particle::from(Acid::new())).to_string());
+        assert_eq!("i", Cell::new(Particle::from(Wire::new())).to_string());
+        assert_eq!("I", Cell::new(Particle::from(Wire::powered())).to_string());
+        assert_eq!("g", Cell::new(Particle::from(Generator::new())).to_string());
+        assert_eq!("l", Cell::new(Particle::from(Slime::new())).to_string());
+        assert_eq!("v", Cell::new(Particle::from(Virus::new())).to_string());
+        assert_eq!("R", Cell::new(Particle::from(Reservoir::new())).to_string());
+        assert_eq!(
+            "T",
+            Cell::new(Particle::from(Teleporter::new())).to_string()
+        );
+        assert_eq!("c", Cell::new(Particle::from(Crystal::new())).to_string());
+        assert_eq!("D", Cell::new(Particle::from(Dust::new())).to_string());
+        assert_eq!("B", Cell::new(Particle::from(Bedrock::new())).to_string());
+        assert_eq!("C", Cell::new(Particle::from(Cryo::new())).to_string());
+        assert_eq!("e", Cell::new(Particle::from(Ice::new())).to_string());
+        assert_eq!("m", Cell::new(Particle::from(Metal::new())).to_string());
+        assert_eq!("M", Cell::new(Particle::from(Magnet::new())).to_string());
+        assert_eq!("n", Cell::new(Particle::from(Membrane::new())).to_string());
+        assert_eq!("p", Cell::new(Particle::from(Rope::new())).to_string());
+        assert_eq!("y", Cell::new(Particle::from(Dye::new())).to_string());
+        assert_eq!("f", Cell::new(Particle::from(Fuse::new())).to_string());
+        assert_eq!("F", Cell::new(Particle::from(Fuse::lit())).to_string());
+        assert_eq!("h", Cell::new(Particle::from(Gate::new())).to_string());
+        assert_eq!("H", Cell::new(Particle::from(Gate::open())).to_string());
+        assert_eq!("G", Cell::new(Particle::from(Glass::new())).to_string());
+        assert_eq!("k", Cell::new(Particle::from(Sticky::new())).to_string());
+        assert_eq!("K", Cell::new(Particle::from(Sticky::stuck())).to_string());
+        assert_eq!("W", Cell::new(Particle::from(Steam::new())).to_string());
+    }
+
+    #[test]
+    fn test_grid_to_rle_and_back() {
+        /*
+         * sssss---ww
+         * rrrrrrrrrr
+         * -a-S-d-t-I
+         */
+        let mut g = Grid::new(10, 3);
+        for x in 0..5 {
+            g.spawn_particle((x, 0), Particle::from(Sand::new()));
+        }
+        for x in 8..10 {
+            g.spawn_particle((x, 0), Particle::from(Water::new()));
+        }
+        for x in 0..10 {
+            g.spawn_particle((x, 1), Particle::from(Rock::new()));
+        }
+        g.spawn_particle((1, 2), Particle::from(Acid::new()));
+        g.spawn_particle((3, 2), Particle::from(Salt::new()));
+        g.spawn_particle((5, 2), Particle::from(Drain::new()));
+        g.spawn_particle((7, 2), Particle::from(Tap::new()));
+        g.spawn_particle((9, 2), Particle::from(Wire::powered()));
+
+        let rle = g.to_rle();
+        assert_eq!("5s3-2w/10r/-a-S-d-t-I", rle);
+
+        let restored = Grid::from_rle(&rle).unwrap();
+        assert_eq!(g.to_string(), restored.to_string());
+        assert_eq!(rle, restored.to_rle());
+    }
+
+    #[test]
+    fn test_grid_to_rle_of_a_zero_width_grid_is_empty() {
+        let g = Grid::new(0, 2);
+
+        assert_eq!("", g.to_rle());
+        assert_eq!(Grid::fnv1a(b""), g.layout_checksum());
+    }
+
+    #[test]
+    fn test_grid_from_rle_rejects_malformed_input() {
+        assert_eq!(Some(GridError::InvalidFormat), Grid::from_rle("3x").err());
+        assert_eq!(Some(GridError::InvalidFormat), Grid::from_rle("3s2").err());
+        assert_eq!(
+            Some(GridError::InvalidFormat),
+            Grid::from_rle("99999999999999999999999s").err()
+        );
+        assert_eq!(
+            Some(GridError::InvalidFormat),
+            Grid::from_rle("65537s").err()
+        );
+        assert_eq!(
+            Some(GridError::InvalidFormat),
+            Grid::from_rle("40000s40000w").err()
+        );
+        assert_eq!(
+            Some(GridError::DimensionMismatch),
+            Grid::from_rle("3s/2s").err()
+        );
     }
 
     #[test]
@@ -1034,13 +4036,189 @@ mod tests {
         (0..255).for_each(|_| g.update_grid());
         assert_eq!(127, g.particle_seed());
 
-        let mut g = Grid::new(1, 1).with_rand_seed(|_| 255);
-        (0..255).for_each(|_| g.update_grid());
-        assert_eq!(254, g.particle_seed());
+        let mut g = Grid::new(1, 1).with_rand_seed(|_| 255);
+        (0..255).for_each(|_| g.update_grid());
+        assert_eq!(254, g.particle_seed());
+
+        let mut g = Grid::new(1, 1).with_rand_seed(|_| 0);
+        (0..256).for_each(|_| g.update_grid());
+        assert_eq!(0, g.particle_seed(), "cycle is moduloed by 256");
+    }
+
+    #[test]
+    fn test_set_cycle_matches_the_seed_of_updating_to_that_cycle() {
+        let mut updated = Grid::new(1, 1).with_rand_seed(|_| 255);
+        (0..100).for_each(|_| updated.update_grid());
+
+        let mut g = Grid::new(1, 1).with_rand_seed(|_| 255);
+        g.set_cycle(100);
+
+        assert_eq!(100, g.cycle());
+        assert_eq!(updated.particle_seed(), g.particle_seed());
+        assert!(!g.is_settled());
+    }
+
+    #[test]
+    fn test_set_draw_cycle_skips_cells_older_than_it() {
+        let mut g = Grid::new(2, 1);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        g.set_cycle(3);
+        g.spawn_particle((1, 0), Particle::from(Sand::new()));
+        let mut image = Grid::create_output_frame(2, 1);
+        g.set_draw_cycle(3);
+
+        g.draw_grid(&mut image);
+
+        assert_color_srgb_eq!(Color::from(css::BLACK), image.get_color_at(0, 0).unwrap());
+        assert_color_srgb_eq!(
+            Particle::from(Sand::new()).color(),
+            image.get_color_at(1, 0).unwrap(),
+            0.1
+        );
+    }
+
+    #[test]
+    fn test_shuffled_updates_follow_the_random_update_order() {
+        /*
+         * bottom-up:  top-down:
+         * s -> -      s -> s
+         * s    s      s    -
+         * -    s      -    s
+         */
+        let mut g = Grid::from_rle("s/s/-")
+            .unwrap()
+            .with_rand_vertical_velocity_probability(|_| 0);
+        g.update_grid();
+        assert_eq!("-/s/s", g.to_rle());
+
+        let mut g = Grid::from_rle("s/s/-")
+            .unwrap()
+            .with_rand_vertical_velocity_probability(|_| 0)
+            .with_shuffle_updates(true)
+            .with_rand_update_order(|_, _| ());
+        g.update_grid();
+        assert_eq!("s/-/s", g.to_rle());
+    }
+
+    #[test]
+    fn test_shuffled_updates_are_deterministic_under_a_seed() {
+        let settle = |shuffle_updates: bool| {
+            let mut g = Grid::new(8, 8)
+                .with_random(Random::new().with_rng_seed(7))
+                .with_shuffle_updates(shuffle_updates);
+            g.spawn_brush((4, 1), 5, Some(&ParticleKind::from(Water::new())));
+            g.spawn_brush((4, 3), 3, Some(&ParticleKind::from(Sand::new())));
+            for _ in 0..10 {
+                g.update_grid();
+            }
+            g.to_rle()
+        };
+
+        assert_eq!(settle(true), settle(true));
+        assert_ne!(settle(false), settle(true));
+    }
+
+    #[test]
+    fn test_one_random_configuration_can_drive_several_grids() {
+        /*
+         * -s- -> ---
+         * ---    --s
+         */
+        let random = Random::new()
+            .with_horizontal_velocity_probability(|_| i16::MAX)
+            .with_vertical_velocity_probability(|_| 0)
+            .with_particle_seed_with_cycle(|_| 42);
+        let mut grids = [
+            Grid::new(3, 2).with_random(random.clone()),
+            Grid::new(3, 2).with_random(random),
+        ];
+
+        for g in &mut grids {
+            g.spawn_particle((1, 0), Particle::from(Water::new()));
+            g.update_grid();
+            g.update_grid();
+        }
+
+        assert_eq!("3-/2-w", grids[0].to_rle());
+        assert_eq!(grids[0].to_rle(), grids[1].to_rle());
+        assert_eq!(42, grids[1].particle_seed());
+    }
+
+    #[test]
+    fn test_remove_connected_clears_one_pool_and_leaves_a_disconnected_one() {
+        /*
+         * ww-w -> ---w
+         * wr-w    -r-w
+         * ww-w    ---w
+         */
+        let mut g = Grid::from_rle("2w-w/wr-w/2w-w").unwrap();
+
+        assert_eq!(5, g.remove_connected((0, 0), true));
+        assert_eq!("3-w/-r-w/3-w", g.to_rle());
+    }
+
+    #[test]
+    fn test_remove_connected_can_follow_any_particle_kind() {
+        /*
+         * sw- -> ---
+         * -r-    ---
+         * --s    --s
+         */
+        let mut g = Grid::from_rle("sw-/-r-/2-s").unwrap();
+
+        assert_eq!(0, g.remove_connected((0, 1), false));
+        assert_eq!(3, g.remove_connected((0, 0), false));
+        assert_eq!("3-/3-/2-s", g.to_rle());
+    }
+
+    #[test]
+    fn test_hourglass_sand_runs_through_the_neck_into_the_bottom_chamber() {
+        let count_sand = |g: &Grid, rows: std::ops::Range<usize>| {
+            rows.flat_map(|y| (0..g.width).map(move |x| (x, y)))
+                .filter(|&p| {
+                    matches!(
+                        g.get_cell(g.to_index(p)).particle,
+                        Some(Particle {
+                            kind: ParticleKind::Sand(..),
+                            ..
+                        })
+                    )
+                })
+                .count()
+        };
+        let mut g = Grid::hourglass(15, 21);
+        let sand = count_sand(&g, 0..21);
+        assert_eq!(sand, count_sand(&g, 0..10));
+
+        assert!(g.run_until_settled(2000) < 2000);
+        assert_eq!(sand, count_sand(&g, 0..21));
+        assert_eq!(sand, count_sand(&g, 11..21));
+    }
+
+    #[test]
+    fn test_flood_to_level_fills_empty_cells_below_the_row() {
+        /*
+         * ---- -> ----
+         * ----    ----
+         * -r--    wrww
+         * --r-    wwrw
+         */
+        let mut g = Grid::new(4, 4).with_rand_seed(|_| 255);
+        g.spawn_particle((1, 2), Particle::from(Rock::new()));
+        g.spawn_particle((2, 3), Particle::from(Rock::new()));
+
+        g.flood_to_level(2, ParticleKind::from(Water::new()));
+
+        assert_eq!("4-/4-/wr2w/2wrw", g.to_rle());
+    }
+
+    #[test]
+    fn test_flood_to_level_past_the_bottom_is_a_noop() {
+        let mut g = Grid::new(2, 2);
+
+        g.flood_to_level(5, ParticleKind::from(Water::new()));
 
-        let mut g = Grid::new(1, 1).with_rand_seed(|_| 0);
-        (0..256).for_each(|_| g.update_grid());
-        assert_eq!(0, g.particle_seed(), "cycle is moduloed by 256");
+        assert_eq!("2-/2-", g.to_rle());
     }
 
     #[test]
@@ -1079,6 +4257,334 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_velocity_histogram_buckets_vertical_speeds() {
+        /*
+         * sss-
+         * -sw-
+         */
+        let mut g = Grid::new(4, 2);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()).with_velocity((0, 0)));
+        g.spawn_particle((1, 0), Particle::from(Sand::new()).with_velocity((0, 128)));
+        g.spawn_particle(
+            (2, 0),
+            Particle::from(Sand::new()).with_velocity((0, i16::MAX)),
+        );
+        g.spawn_particle((1, 1), Particle::from(Sand::new()).with_velocity((50, 0)));
+        g.spawn_particle(
+            (2, 1),
+            Particle::from(Water::new()).with_velocity((0, i16::MIN)),
+        );
+
+        assert_eq!([3, 0, 0, 0, 0, 0, 0, 2], g.velocity_histogram());
+    }
+
+    #[test]
+    fn test_region_stats_only_counts_the_cells_inside_the_region() {
+        /*
+         * s---
+         * -sw-
+         * -ww-
+         */
+        let mut g = Grid::new(4, 3);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        g.spawn_particle((1, 1), Particle::from(Sand::new()).with_velocity((0, 100)));
+        g.spawn_particle((2, 1), Particle::from(Water::new()).with_velocity((6, 0)));
+        g.spawn_particle(
+            (1, 2),
+            Particle::from(Water::new()).with_velocity((-3, 200)),
+        );
+        g.spawn_particle((2, 2), Particle::from(Water::new()).with_velocity((1, 0)));
+
+        let stats = g.region_stats((1, 1), (2, 2));
+
+        assert_eq!(
+            BTreeMap::from([("sand".to_string(), 1), ("water".to_string(), 3)]),
+            stats.counts
+        );
+        assert_eq!((1., 75.), stats.average_velocity);
+        assert_eq!([4, 0, 0, 0, 0, 0, 0, 0], stats.velocity_histogram);
+        assert_eq!("sand: 1 water: 3 | velocity: (1, 75)", stats.to_string());
+    }
+
+    #[test]
+    fn test_region_stats_clips_the_region_to_the_grid() {
+        let mut g = Grid::new(2, 2);
+        g.spawn_particle((1, 1), Particle::from(Sand::new()).with_velocity((0, 0)));
+
+        let stats = g.region_stats((1, 1), (10, 10));
+
+        assert_eq!(BTreeMap::from([("sand".to_string(), 1)]), stats.counts);
+        assert_eq!(RegionStats::default(), g.region_stats((0, 0), (0, 0)));
+    }
+
+    #[test]
+    fn test_restore_cells_replaces_the_cells_and_the_cycle() {
+        let mut g = Grid::new(1, 2);
+        let cells = vec![
+            Cell::empty().with_cycle(7),
+            Cell::new(Particle::from(Sand::new())).with_cycle(7),
+        ];
+
+        assert_eq!(Ok(()), g.restore_cells(&cells, 7));
+
+        assert_eq!(cells, *g.get_cells());
+        assert_eq!(7, g.cycle());
+    }
+
+    #[test]
+    fn test_restore_cells_with_a_different_size_fails() {
+        let mut g = Grid::new(1, 2);
+
+        assert_eq!(
+            Err(GridError::DimensionMismatch),
+            g.restore_cells(&[Cell::empty()], 1)
+        );
+        assert_eq!(vec![Cell::empty(), Cell::empty()], *g.get_cells());
+    }
+
+    #[test]
+    fn test_grid_rotate_turns_the_cells_clockwise() {
+        /*
+         * s-r -> ws -> 2-w
+         * w--    2-    r-s
+         *        -r
+         */
+        let mut g = Grid::from_rle("s-r/w2-").unwrap().with_window_size((3, 1));
+
+        g.rotate(1);
+        assert_eq!("ws/2-/-r", g.to_rle());
+        assert_eq!(2, g.active_window_count());
+
+        g.rotate(1);
+        assert_eq!("2-w/r-s", g.to_rle());
+
+        g.rotate(6);
+        assert_eq!("s-r/w2-", g.to_rle());
+    }
+
+    #[test]
+    fn test_particles_in_radius_returns_the_particles_inside_the_circle() {
+        /*
+         * s-w--
+         * -sss-
+         * rs-s-
+         * -sss-
+         * ----r
+         */
+        let g = Grid::from_rle("s-w2-/-3s-/rs-s-/-3s-/4-r").unwrap();
+
+        let found = |center, radius| {
+            g.particles_in_radius(center, radius)
+                .into_iter()
+                .map(|(position, p)| (position, p.to_string()))
+                .collect::<Vec<_>>()
+        };
+
+        assert_eq!(vec![((2, 0), "water".to_string())], found((2, 0), 0));
+        assert_eq!(
+            vec![
+                ((2, 1), "sand".to_string()),
+                ((1, 2), "sand".to_string()),
+                ((3, 2), "sand".to_string()),
+                ((2, 3), "sand".to_string()),
+            ],
+            found((2, 2), 1)
+        );
+        assert_eq!(10, found((2, 2), 2).len());
+        assert_eq!(
+            vec![
+                ((0, 0), "sand".to_string()),
+                ((2, 0), "water".to_string()),
+                ((1, 1), "sand".to_string()),
+                ((0, 2), "rock".to_string()),
+            ],
+            found((0, 0), 2)
+        );
+    }
+
+    #[test]
+    fn test_recorded_deltas_step_back_to_the_initial_state() {
+        /*
+         * -s-    ---
+         * -w- -> ---
+         * ---    ws-
+         */
+        let mut g = Grid::from_rle("-s-/-w-/3-")
+            .unwrap()
+            .with_random(Random::new().with_rng_seed(1))
+            .with_deterministic_row_order(true);
+        let initial = g.get_cells().clone();
+
+        let replay: Vec<_> = (0..6).map(|_| g.update_grid_recording()).collect();
+        let last = g.get_cells().clone();
+        assert_eq!("3-/3-/ws-", g.to_rle());
+        assert_eq!(6, g.cycle);
+
+        for delta in replay.iter().rev() {
+            assert_eq!(Ok(()), g.apply_delta_inverse(delta));
+        }
+        assert_eq!(initial, *g.get_cells());
+        assert_eq!(0, g.cycle);
+
+        for delta in &replay {
+            assert_eq!(Ok(()), g.apply_delta(delta));
+        }
+        assert_eq!(last, *g.get_cells());
+        assert_eq!(6, g.cycle);
+    }
+
+    #[test]
+    fn test_draw_grid_paints_the_cells_a_delta_puts_back() {
+        /*
+         * -s-    ---    -s-
+         * -w- -> --- -> -w-
+         * ---    ws-    ---
+         */
+        let mut g = Grid::from_rle("-s-/-w-/3-")
+            .unwrap()
+            .with_random(Random::new().with_rng_seed(1))
+            .with_deterministic_row_order(true);
+        let mut image = Grid::create_output_frame(3, 3);
+        g.draw_grid(&mut image);
+        let replay: Vec<_> = (0..6)
+            .map(|_| {
+                let delta = g.update_grid_recording();
+                g.draw_grid(&mut image);
+                delta
+            })
+            .collect();
+        let color_at = |g: &Grid, position| match &g.get_cell(g.to_index(position)).particle {
+            Some(p) => p.color(),
+            None => BACKGROUND_COLOR,
+        };
+
+        for delta in replay.iter().rev() {
+            assert_eq!(Ok(()), g.apply_delta_inverse(delta));
+            g.draw_grid(&mut image);
+        }
+        assert_eq!("-s-/-w-/3-", g.to_rle());
+        for (x, y) in [(1, 0), (1, 1), (0, 2), (1, 2)] {
+            assert_color_srgb_eq!(
+                color_at(&g, (x, y)),
+                image.get_color_at(x as u32, y as u32).unwrap()
+            );
+        }
+
+        for delta in &replay {
+            assert_eq!(Ok(()), g.apply_delta(delta));
+            g.draw_grid(&mut image);
+        }
+        assert_eq!("3-/3-/ws-", g.to_rle());
+        for (x, y) in [(1, 0), (1, 1), (0, 2), (1, 2)] {
+            assert_color_srgb_eq!(
+                color_at(&g, (x, y)),
+                image.get_color_at(x as u32, y as u32).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_settled_scene_is_cached_and_restored() {
+        /*
+         * -s-    ---
+         * s-s -> ---
+         * ---    sss
+         */
+        let mut g = Grid::from_rle("-s-/s-s/3-").unwrap();
+        let initial = g.get_cells().clone();
+        let scene = g.layout_checksum();
+
+        assert!(!g.try_restore_cached(scene));
+        assert!(!g.cache_if_settled(scene));
+        g.run_until_settled(20);
+        assert!(g.cache_if_settled(scene));
+        let settled = g.get_cells().clone();
+
+        assert_eq!(Ok(()), g.restore_cells(&initial, 0));
+        assert!(g.try_restore_cached(g.layout_checksum()));
+        assert_eq!(settled, *g.get_cells());
+        assert_eq!("3-/3-/3s", g.to_rle());
+    }
+
+    #[test]
+    fn test_draw_grid_paints_a_restored_settled_scene() {
+        /*
+         * -s-    ---
+         * s-s -> ---
+         * ---    sss
+         */
+        let mut g = Grid::from_rle("-s-/s-s/3-").unwrap();
+        let initial = g.get_cells().clone();
+        let scene = g.layout_checksum();
+        g.run_until_settled(20);
+        assert!(g.cache_if_settled(scene));
+        assert_eq!(Ok(()), g.restore_cells(&initial, g.cycle()));
+        let mut image = Grid::create_output_frame(3, 3);
+        g.draw_grid(&mut image);
+
+        assert!(g.try_restore_cached(scene));
+        g.draw_grid(&mut image);
+
+        assert_color_srgb_eq!(BACKGROUND_COLOR, image.get_color_at(1, 0).unwrap());
+        assert_color_srgb_eq!(BACKGROUND_COLOR, image.get_color_at(0, 1).unwrap());
+        for x in 0..3 {
+            assert_color_srgb_eq!(
+                g.get_cell(g.to_index((x, 2)))
+                    .particle
+                    .as_ref()
+                    .unwrap()
+                    .color(),
+                image.get_color_at(x as u32, 2).unwrap()
+            );
+        }
+    }
+
+    #[test]
+    fn test_grid_mirror_flips_the_cells() {
+        /*
+         * rB- -> -Br -> 2-r
+         * r--    2-r    -Br
+         */
+        let mut g = Grid::from_rle("rB-/r2-").unwrap().with_window_size((3, 1));
+        g.run_until_settled(10);
+        assert!(g.is_settled());
+
+        g.mirror_horizontal();
+        assert_eq!("-Br/2-r", g.to_rle());
+        assert!(!g.is_settled());
+
+        g.mirror_vertical();
+        assert_eq!("2-r/-Br", g.to_rle());
+
+        g.mirror_vertical();
+        g.mirror_horizontal();
+        assert_eq!("rB-/r2-", g.to_rle());
+    }
+
+    #[test]
+    fn test_grid_from_cells_keeps_the_given_cells() {
+        /*
+         * s-
+         * -r
+         */
+        let cells = vec![
+            Cell::new(Particle::from(Sand::new())),
+            Cell::empty(),
+            Cell::empty(),
+            Cell::new(Particle::from(Rock::new())).with_cycle(3),
+        ];
+
+        let g = Grid::from_cells(2, 2, cells.clone()).unwrap();
+
+        assert_eq!(cells, *g.get_cells());
+        assert_eq!("s-/-r", g.to_rle());
+        assert_eq!(
+            Some(GridError::DimensionMismatch),
+            Grid::from_cells(3, 2, cells).err()
+        );
+    }
+
     #[test]
     fn test_grid_update_cycle_overflows_and_wraps_to_zero() {
         let mut g = Grid::new(2, 2);
@@ -1172,11 +4678,131 @@ mod windowing {
 
     use pretty_assertions::assert_eq;
 
+    use bevy::math::URect;
+
     use crate::component::{
-        grid::{Cell, Grid, GridAccess, Window, WindowGrid},
-        particles::{particle::Particle, rock::Rock, sand::Sand},
+        grid::{Cell, Grid, GridAccess, GridError, Window, WindowGrid},
+        particles::{particle::Particle, rock::Rock, sand::Sand, water::Water},
     };
 
+    #[test]
+    fn test_window_grid_from_rects_simulates_across_mixed_size_windows() {
+        /*
+         * -s--    ----
+         * ----    ----
+         * ----    ----
+         * ----    ----
+         * ---- -> ----
+         * ----    -s--
+         *
+         * the top half is one window, the bottom half two narrow ones
+         */
+        let rect = |min: (u32, u32), max: (u32, u32)| URect::new(min.0, min.1, max.0, max.1);
+        let window_grid = WindowGrid::from_rects(
+            (4, 6),
+            vec![
+                rect((0, 0), (4, 3)),
+                rect((0, 3), (2, 6)),
+                rect((2, 3), (4, 6)),
+            ],
+        )
+        .unwrap();
+        let mut g = Grid::new(4, 6).with_window_grid(window_grid);
+        g.spawn_particle((1, 0), Particle::from(Sand::new()));
+
+        assert_eq!(3, g.window_grid.windows.len());
+
+        g.run_until_settled(20);
+
+        assert_eq!("4-/4-/4-/4-/4-/-s2-", g.to_rle());
+        assert!(g.is_settled());
+    }
+
+    #[test]
+    fn test_window_grid_from_rects_rejects_gaps_and_overlaps() {
+        let rect = |min: (u32, u32), max: (u32, u32)| URect::new(min.0, min.1, max.0, max.1);
+
+        assert_eq!(
+            Some(GridError::WindowCoverage),
+            WindowGrid::from_rects((4, 4), vec![rect((0, 0), (4, 3))]).err()
+        );
+        assert_eq!(
+            Some(GridError::WindowCoverage),
+            WindowGrid::from_rects((4, 4), vec![rect((0, 0), (4, 3)), rect((0, 2), (4, 4))]).err()
+        );
+        assert_eq!(
+            Some(GridError::WindowCoverage),
+            WindowGrid::from_rects((4, 4), vec![rect((0, 0), (4, 5))]).err()
+        );
+    }
+
+    #[test]
+    fn test_windowing_off_simulates_cells_in_sleeping_windows() {
+        /*
+         * s- -> s- | --
+         * --    -- | s-
+         */
+        let mut g = Grid::new(2, 2).with_window_size((1, 1));
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        g.cycle = 5;
+        g.update_grid();
+        assert_eq!("s-/2-", g.to_rle());
+
+        let mut g = Grid::new(2, 2)
+            .with_window_size((1, 1))
+            .with_windowing(false);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        g.cycle = 5;
+        g.update_grid();
+        assert_eq!("2-/s-", g.to_rle());
+    }
+
+    #[test]
+    fn test_windowing_on_and_off_settle_a_scene_the_same_way() {
+        fn settle(windowing: bool) -> String {
+            let mut g = Grid::new(8, 8)
+                .with_window_size((2, 2))
+                .with_windowing(windowing)
+                .with_rng_seed(3)
+                .with_deterministic_row_order(true)
+                .with_rand_horizontal_velocity_probability(|_| 1)
+                .with_rand_vertical_velocity_probability(|_| 0);
+            for y in 0..4 {
+                g.spawn_particle((6, y), Particle::from(Rock::new()));
+            }
+            for position in [(1, 0), (1, 3), (3, 2), (5, 1)] {
+                g.spawn_particle(position, Particle::from(Sand::new()));
+            }
+            for _ in 0..40 {
+                g.update_grid();
+            }
+            g.to_rle()
+        }
+
+        assert_eq!("6-r-/6-r-/6-r-/6-r-/8-/8-/8-/-3s-s2-", settle(true));
+        assert_eq!(settle(true), settle(false));
+    }
+
+    #[test]
+    fn test_seeded_runs_with_deterministic_row_order_match_exactly() {
+        fn run() -> Grid {
+            let mut g = Grid::new(8, 8)
+                .with_window_size((4, 4))
+                .with_rng_seed(7)
+                .with_deterministic_row_order(true);
+            for x in 0..8 {
+                g.spawn_particle((x, 0), Particle::from(Water::new()));
+                g.spawn_particle((x, 2), Particle::from(Sand::new()));
+            }
+            for _ in 0..40 {
+                g.update_grid();
+            }
+            g
+        }
+
+        assert_eq!(run().get_cells(), run().get_cells());
+    }
+
     #[test]
     fn test_window_is_active_for_n_cycles_from_threshold() {
         let mut w = Window::new((0, 0), (2, 2)).with_threshold(2);
@@ -1245,6 +4871,33 @@ mod windowing {
         );
     }
 
+    #[test]
+    fn test_grid_clamps_a_zero_window_size_to_single_cells() {
+        let g = Grid::new(2, 1).with_window_size((0, 0));
+
+        assert_eq!(
+            HashMap::from([
+                ((0, 0), Window::new((0, 0), (0, 0))),
+                ((1, 0), Window::new((1, 0), (1, 0))),
+            ]),
+            g.window_grid.windows
+        );
+    }
+
+    #[test]
+    fn test_grid_clamps_an_oversized_window_size_to_the_grid() {
+        let mut g = Grid::new(3, 2).with_window_size((10, 5));
+
+        assert_eq!(
+            HashMap::from([((0, 0), Window::new((0, 0), (2, 1)))]),
+            g.window_grid.windows
+        );
+
+        g.spawn_particle((2, 0), Particle::from(Sand::new()));
+        g.update_grid();
+        assert_eq!("3-/2-s", g.to_rle());
+    }
+
     #[test]
     fn test_get_mutable_particle_window_from_position() {
         let mut g = Grid::new(6, 6).with_window_size((2, 2));
@@ -1601,6 +5254,73 @@ mod windowing {
         );
     }
 
+    #[test]
+    fn test_particle_falling_across_window_boundary_moves_exactly_one_cell() {
+        /*
+         * -    -
+         * s -> -
+         * ..........
+         * -    s
+         * -    -
+         */
+        for _ in 0..10 {
+            let mut g = Grid::new(1, 4)
+                .with_window_size((1, 2))
+                .with_rand_vertical_velocity_probability(|_| 0);
+
+            g.spawn_particle((0, 1), Particle::from(Sand::new()));
+            assert!(g.window_grid.windows.values().all(|w| w.is_active(1)));
+
+            g.update_grid();
+
+            assert_eq!(
+                vec![
+                    Cell::empty(),
+                    Cell::empty().with_cycle(1),
+                    Cell::new(Particle::from(Sand::new())).with_cycle(1),
+                    Cell::empty(),
+                ],
+                *g.get_cells()
+            );
+        }
+    }
+
+    #[test]
+    fn test_active_window_count() {
+        let mut g = Grid::new(4, 2).with_window_size((1, 1));
+        g.cycle = 2;
+        assert_eq!(0, g.active_window_count());
+
+        g.spawn_particle((0, 0), Particle::from(Rock::new()));
+        assert_eq!(4, g.active_window_count());
+    }
+
+    #[test]
+    fn test_run_until_settled_stops_once_every_window_is_asleep() {
+        /*
+         * s -> -
+         * s    -
+         * s    -
+         * s    -
+         * -    s
+         * -    s
+         * -    s
+         * -    s
+         */
+        let mut g = Grid::new(1, 8).with_window_size((1, 2));
+        for y in 0..4 {
+            g.spawn_particle((0, y), Particle::from(Sand::new()));
+        }
+        assert!(!g.is_settled());
+
+        let cycles = g.run_until_settled(100);
+
+        assert!(cycles < 100);
+        assert!(g.is_settled());
+        assert_eq!("-/-/-/-/s/s/s/s", g.to_rle());
+        assert_eq!(0, g.run_until_settled(100));
+    }
+
     #[test]
     fn test_activate_window_should_activate_neighboring_windows() {
         let mut g = Grid::new(3, 3)
@@ -1650,3 +5370,67 @@ mod windowing {
         );
     }
 }
+
+#[cfg(test)]
+mod golden {
+    use pretty_assertions::assert_eq;
+
+    use super::{Grid, Random};
+
+    const SEED: u64 = 42;
+
+    /// Runs a seeded scene for `cycles` cycles and returns its cells, so any
+    /// change in a particle's behavior shows up as a changed golden.
+    fn run_scene(rle: &str, cycles: u32) -> String {
+        let mut g = Grid::from_rle(rle)
+            .unwrap()
+            .with_random(Random::new().with_rng_seed(SEED))
+            .with_deterministic_row_order(true);
+        for _ in 0..cycles {
+            g.update_grid();
+        }
+        g.to_rle()
+    }
+
+    #[test]
+    fn test_golden_sand() {
+        assert_eq!(
+            "8-/8-/8-/3-s4-/2-3s3-/8r",
+            run_scene("3-s4-/2-3s3-/8-/8-/8-/8r", 20)
+        );
+    }
+
+    #[test]
+    fn test_golden_water() {
+        assert_eq!(
+            "8-/8-/8-/8-/2-w2-w-w/8r",
+            run_scene("w7-/w7-/w7-/8-/8-/8r", 30)
+        );
+    }
+
+    #[test]
+    fn test_golden_salt() {
+        assert_eq!("8-/8-/8-/8w/8w/8r", run_scene("3-2S3-/8-/8-/8w/8w/8r", 30));
+    }
+
+    #[test]
+    fn test_golden_acid() {
+        assert_eq!(
+            "8-/2-B-B3-/2-B-B3-/2-BsB3-/2-3B3-/8r",
+            run_scene("8-/2-BaB3-/2-BsB3-/2-BsB3-/2-3B3-/8r", 40)
+        );
+    }
+
+    #[test]
+    fn test_golden_drain() {
+        assert_eq!("8-/8-/8-/8-/8-/8d", run_scene("3s2w3s/8-/8-/8-/8-/8d", 20));
+    }
+
+    #[test]
+    fn test_golden_tap() {
+        assert_eq!(
+            "8-/2-3w3-/2-wtw3-/2-3w3-/8-/8r",
+            run_scene("8-/3-w4-/3-t4-/8-/8-/8r", 20)
+        );
+    }
+}
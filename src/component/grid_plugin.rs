@@ -1,4 +1,4 @@
-use std::collections::VecDeque;
+use std::{collections::VecDeque, fmt, time::Duration};
 
 use bevy::{
     app::{App, FixedUpdate, Plugin, PostStartup, Startup, Update},
@@ -6,19 +6,29 @@ use bevy::{
     color::{Alpha, Color},
     ecs::{
         bundle::Bundle,
+        change_detection::DetectChanges,
         children,
         component::Component,
         entity::Entity,
         observer::On,
         query::With,
         resource::Resource,
-        system::{Commands, Query, Res, ResMut},
+        schedule::IntoScheduleConfigs,
+        system::{Commands, Local, Query, Res, ResMut},
     },
     image::Image,
+    input::{
+        ButtonInput,
+        keyboard::KeyCode,
+        mouse::{AccumulatedMouseMotion, AccumulatedMouseScroll, MouseButton},
+    },
+    math::{Rect, Vec2},
     picking::{
         Pickable,
         events::{Click, Move, Out, Pointer, Press, Release},
+        pointer::PointerButton,
     },
+    platform::time::Instant,
     prelude::{SpawnRelated, Vec3},
     text::{Font, TextFont},
     time::{Fixed, Time},
@@ -31,15 +41,21 @@ use bevy::{
 };
 
 use crate::component::{
-    grid::{BACKGROUND_COLOR, WindowGrid},
+    brush_config::{BrushConfig, BrushConfigFile},
+    grid::{BACKGROUND_COLOR, GridAccess, RegionStats, Stamp, WindowGrid},
     particles::rock::Rock,
+    sim_thread::SimThread,
 };
 
 use super::{
-    grid::Grid,
+    grid::{BrushShape, Grid, PaintMode},
     particles::{
-        acid::Acid, drain::Drain, particle::Particle, particle::ParticleKind, salt::Salt,
-        sand::Sand, tap::Tap, water::Water,
+        acid::Acid, bedrock::Bedrock, cryo::Cryo, crystal::Crystal, drain::Drain, dust::Dust,
+        dye::Dye, fuse::Fuse, gate::Gate, generator::Generator, glass::Glass, ice::Ice,
+        magnet::Magnet, membrane::Membrane, metal::Metal, particle::Particle,
+        particle::ParticleKind, reservoir::Reservoir, rope::Rope, salt::Salt, sand::Sand,
+        slime::Slime, steam::Steam, sticky::Sticky, tap::Tap, teleporter::Teleporter, virus::Virus,
+        water::Water, wire::Wire,
     },
 };
 
@@ -54,12 +70,44 @@ struct ParticleRadio(Option<ParticleKind>);
 #[derive(Component, Debug)]
 struct ClearButton;
 
+#[derive(Component, Debug)]
+struct LineButton;
+
+#[derive(Component, Debug)]
+struct EraseButton;
+
+#[derive(Component, Debug)]
+struct MixButton;
+
+#[derive(Component, Debug)]
+struct ReplaceButton;
+
+#[derive(Component, Debug)]
+struct BehindButton;
+
+#[derive(Component, Debug)]
+struct SprayButton;
+
+#[derive(Component, Debug)]
+struct ProbeReadout;
+
 #[derive(Component)]
 pub struct ParticleBrush {
     pub spawning: bool,
     pub positions: VecDeque<(usize, usize)>,
     pub particle_kind: Option<ParticleKind>,
     pub size: usize,
+    pub line: bool,
+    pub erase_only: bool,
+    pub paint_mode: PaintMode,
+    pub spray: bool,
+    pub secondary_kind: Option<ParticleKind>,
+    pub mix_ratio: f32,
+    pub stamp: Option<Stamp>,
+    pub interpolation_steps: usize,
+    line_anchor: Option<(usize, usize)>,
+    copy_anchor: Option<(usize, usize)>,
+    probe_anchor: Option<(usize, usize)>,
     last_position: Option<Vec3>,
 }
 
@@ -76,6 +124,17 @@ impl ParticleBrush {
             positions: VecDeque::new(),
             particle_kind: Some(ParticleKind::from(Sand::new())),
             size: 8,
+            line: false,
+            erase_only: false,
+            paint_mode: PaintMode::FillEmpty,
+            spray: false,
+            secondary_kind: None,
+            mix_ratio: 0.5,
+            stamp: None,
+            interpolation_steps: 10,
+            line_anchor: None,
+            copy_anchor: None,
+            probe_anchor: None,
             last_position: None,
         }
     }
@@ -88,16 +147,69 @@ impl ParticleBrush {
         self.spawning = false;
     }
 
-    fn set_position(&mut self, position: Vec3, grid_size: (usize, usize)) {
-        self.positions.push_back((
+    fn grid_position(position: Vec3, grid_size: (usize, usize)) -> (usize, usize) {
+        (
             ((position.x + 0.5) * grid_size.0 as f32) as usize,
             ((position.y + 0.5) * grid_size.1 as f32) as usize,
-        ));
+        )
+    }
+
+    fn set_position(&mut self, position: Vec3, grid_size: (usize, usize)) {
+        self.positions
+            .push_back(Self::grid_position(position, grid_size));
+    }
+
+    fn set_line_point(
+        &mut self,
+        position: Vec3,
+        grid_size: (usize, usize),
+    ) -> Option<((usize, usize), (usize, usize))> {
+        let position = Self::grid_position(position, grid_size);
+        match self.line_anchor.take() {
+            Some(anchor) => Some((anchor, position)),
+            None => {
+                self.line_anchor = Some(position);
+                None
+            }
+        }
+    }
+
+    fn stroke(&self, position: (usize, usize)) -> impl FnOnce(&mut Grid) + Send + 'static {
+        let (size, kind, erase_only) = (self.size, self.particle_kind.clone(), self.erase_only);
+        let (secondary_kind, mix_ratio) = (self.secondary_kind.clone(), self.mix_ratio);
+        let (paint_mode, spray) = (self.paint_mode, self.spray);
+        move |g| match (erase_only, kind, secondary_kind) {
+            (true, Some(k), _) => g.erase_brush(position, size, &k),
+            (_, k, Some(s)) => {
+                g.spawn_brush_mix(position, size, k.as_ref(), &s, mix_ratio, paint_mode)
+            }
+            (_, None, None) => g.despawn_brush(position, size, BrushShape::Circle),
+            (_, k, None) if spray => g.spawn_brush_spray(position, size, k.as_ref(), paint_mode),
+            (_, k, None) => g.paint_brush(position, size, k.as_ref(), paint_mode),
+        }
+    }
+
+    fn config(&self) -> BrushConfig {
+        BrushConfig {
+            particle_kind_id: self.particle_kind.as_ref().map(|k| k.id()),
+            size: self.size,
+            paint_mode: self.paint_mode,
+            erase_only: self.erase_only,
+            mix_ratio: self.mix_ratio,
+        }
+    }
+
+    fn apply_config(&mut self, config: &BrushConfig) {
+        self.particle_kind = config.particle_kind_id.and_then(ParticleKind::with_id);
+        self.size = config.size;
+        self.paint_mode = config.paint_mode;
+        self.erase_only = config.erase_only;
+        self.mix_ratio = config.mix_ratio;
     }
 
     fn set_position_linear(&mut self, position: Vec3, grid_size: (usize, usize)) {
         if let Some(last_position) = self.last_position {
-            let steps = 10;
+            let steps = self.interpolation_steps.max(1);
             for s in 1..=steps {
                 let n = last_position.lerp(position, s as f32 / steps as f32);
                 self.set_position(n, grid_size);
@@ -109,6 +221,91 @@ impl ParticleBrush {
     }
 }
 
+#[derive(Resource, Default, Debug)]
+pub struct SimMetrics {
+    pub update_duration: Duration,
+    pub active_windows: usize,
+    pub cycle: u32,
+}
+
+#[derive(Resource, Default, Debug)]
+pub struct RegionProbe {
+    pub stats: Option<RegionStats>,
+}
+
+#[derive(Resource, Clone, Debug)]
+pub struct RainSpawner {
+    pub particle_kind: ParticleKind,
+    pub rate: usize,
+}
+
+#[derive(Resource, Clone, Debug, PartialEq)]
+pub struct ViewTransform {
+    pub zoom: f32,
+    pub center: Vec2,
+}
+
+impl Default for ViewTransform {
+    fn default() -> Self {
+        Self {
+            zoom: 1.,
+            center: Vec2::splat(0.5),
+        }
+    }
+}
+
+impl ViewTransform {
+    const MAX_ZOOM: f32 = 32.;
+
+    pub fn apply(&self, position: Vec3) -> Vec3 {
+        Vec3::new(
+            self.center.x - 0.5 + position.x / self.zoom,
+            self.center.y - 0.5 + position.y / self.zoom,
+            position.z,
+        )
+    }
+
+    pub fn zoom_by(&mut self, factor: f32) {
+        self.zoom = (self.zoom * factor).clamp(1., Self::MAX_ZOOM);
+        self.clamp_center();
+    }
+
+    pub fn pan(&mut self, delta: Vec2) {
+        self.center += delta;
+        self.clamp_center();
+    }
+
+    pub fn visible_rect(&self) -> Rect {
+        Rect::from_center_half_size(self.center, Vec2::splat(0.5 / self.zoom))
+    }
+
+    fn clamp_center(&mut self) {
+        let half = 0.5 / self.zoom;
+        self.center = self.center.clamp(Vec2::splat(half), Vec2::splat(1. - half));
+    }
+}
+
+#[derive(Debug, PartialEq)]
+pub enum ConfigError {
+    ZeroWidth,
+    ZeroHeight,
+    InvalidUpdateRate,
+}
+
+impl fmt::Display for ConfigError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ConfigError::ZeroWidth => write!(f, "grid width must be greater than zero"),
+            ConfigError::ZeroHeight => write!(f, "grid height must be greater than zero"),
+            ConfigError::InvalidUpdateRate => {
+                write!(f, "update rate must be a finite, non-negative number")
+            }
+        }
+    }
+}
+
+impl std::error::Error for ConfigError {}
+
 #[derive(Resource, Clone)]
 pub struct ConfigResource {
     pub width: usize,
@@ -118,6 +315,8 @@ pub struct ConfigResource {
     window_width: usize,
     window_height: usize,
     window_threshold: u32,
+    dither: bool,
+    border: Option<(Color, usize)>,
 }
 
 impl ConfigResource {
@@ -135,7 +334,35 @@ impl ConfigResource {
             window_width: width,
             window_height: height,
             window_threshold: 0,
+            dither: false,
+            border: None,
+        }
+    }
+
+    /// Like `new`, but rejects values that would make the plugin panic later.
+    /// An update rate of zero is allowed and keeps the simulation paused.
+    pub fn try_new(
+        width: usize,
+        height: usize,
+        update_rate: f64,
+        initial_particle_velocity: (i16, i16),
+    ) -> Result<Self, ConfigError> {
+        let config = Self::new(width, height, update_rate, initial_particle_velocity);
+        config.validate()?;
+        Ok(config)
+    }
+
+    pub fn validate(&self) -> Result<(), ConfigError> {
+        if self.width == 0 {
+            return Err(ConfigError::ZeroWidth);
         }
+        if self.height == 0 {
+            return Err(ConfigError::ZeroHeight);
+        }
+        if !self.update_rate.is_finite() || self.update_rate < 0. {
+            return Err(ConfigError::InvalidUpdateRate);
+        }
+        Ok(())
     }
 
     pub fn with_window_config(
@@ -149,6 +376,34 @@ impl ConfigResource {
         self.window_threshold = window_threshold;
         self
     }
+
+    pub fn with_dither(mut self, dither: bool) -> ConfigResource {
+        self.dither = dither;
+        self
+    }
+
+    pub fn with_border(mut self, color: Color, thickness: usize) -> ConfigResource {
+        self.border = Some((color, thickness));
+        self
+    }
+
+    /// Size of the output frame: the grid plus the border around it.
+    pub fn frame_size(&self) -> (usize, usize) {
+        let thickness = self.border.map_or(0, |(_, thickness)| thickness);
+        (self.width + 2 * thickness, self.height + 2 * thickness)
+    }
+
+    /// Maps a position on the output frame to the same spot on the grid,
+    /// both centered on zero and spanning one unit.
+    pub fn frame_to_grid(&self, position: Vec3) -> Vec3 {
+        let thickness = self.border.map_or(0, |(_, thickness)| thickness) as f32;
+        let (frame_width, frame_height) = self.frame_size();
+        Vec3::new(
+            ((position.x + 0.5) * frame_width as f32 - thickness) / self.width as f32 - 0.5,
+            ((position.y + 0.5) * frame_height as f32 - thickness) / self.height as f32 - 0.5,
+            position.z,
+        )
+    }
 }
 
 pub struct GridPlugin {
@@ -156,15 +411,56 @@ pub struct GridPlugin {
 }
 impl Plugin for GridPlugin {
     fn build(&self, app: &mut App) {
+        if let Err(error) = self.config.validate() {
+            panic!("invalid GridPlugin config: {error}");
+        }
         app.insert_resource(self.config.clone())
-            .insert_resource(Time::<Fixed>::from_hz(self.config.update_rate))
+            .init_resource::<SimMetrics>()
+            .init_resource::<ViewTransform>()
+            .init_resource::<RegionProbe>()
             .add_systems(Startup, init_grid_system)
-            .add_systems(FixedUpdate, update_grid_system)
-            .add_systems(Update, draw_grid_system)
+            .add_systems(Update, (read_sim_thread_system, draw_grid_system).chain())
             .add_systems(PostStartup, init_inputs_system)
             .add_systems(PostStartup, observe_particle_button_particle_brush_system)
             .add_systems(PostStartup, observe_clear_button_system)
-            .add_systems(Update, spawn_brush_system);
+            .add_systems(
+                PostStartup,
+                (
+                    observe_toggle_button_system::<LineButton>,
+                    observe_toggle_button_system::<EraseButton>,
+                    observe_toggle_button_system::<MixButton>,
+                    observe_toggle_button_system::<ReplaceButton>,
+                    observe_toggle_button_system::<BehindButton>,
+                    observe_toggle_button_system::<SprayButton>,
+                ),
+            )
+            .add_systems(Update, spawn_brush_system)
+            .add_systems(Update, save_brush_config_system)
+            .add_systems(Update, probe_readout_system)
+            .add_systems(Update, (view_input_system, apply_view_system).chain());
+        if self.config.update_rate > 0. {
+            app.insert_resource(Time::<Fixed>::from_hz(self.config.update_rate))
+                .add_systems(FixedUpdate, (rain_system, update_grid_system).chain());
+            #[cfg(feature = "parallel_sim")]
+            app.add_systems(Startup, init_sim_thread_system);
+        }
+    }
+}
+
+fn new_grid(config: &ConfigResource) -> Grid {
+    let grid = Grid::new(config.width, config.height)
+        .with_window_grid(
+            WindowGrid::new(
+                (config.width, config.height),
+                (config.window_width, config.window_height),
+            )
+            .with_window_threshold(config.window_threshold),
+        )
+        .with_initial_particle_velocity(config.initial_particle_velocity)
+        .with_dither(config.dither);
+    match config.border {
+        Some((color, thickness)) => grid.with_border(color, thickness),
+        None => grid,
     }
 }
 
@@ -173,19 +469,13 @@ fn init_grid_system(
     config: Res<ConfigResource>,
     mut images: ResMut<Assets<Image>>,
     asset_server: Option<Res<AssetServer>>,
+    brush_config_file: Option<Res<BrushConfigFile>>,
 ) {
-    commands.spawn(
-        Grid::new(config.width, config.height)
-            .with_window_grid(
-                WindowGrid::new(
-                    (config.width, config.height),
-                    (config.window_width, config.window_height),
-                )
-                .with_window_threshold(config.window_threshold),
-            )
-            .with_initial_particle_velocity(config.initial_particle_velocity),
-    );
-    let handle = images.add(Grid::create_output_frame(config.width, config.height));
+    commands.spawn(new_grid(&config));
+    let handle = images.add({
+        let (width, height) = config.frame_size();
+        Grid::create_output_frame(width, height)
+    });
     let font = match asset_server {
         Some(a) => a.load(ASSET_FONT_PATH),
         None => default(),
@@ -196,16 +486,107 @@ fn init_grid_system(
             flex_direction: FlexDirection::Column,
             ..default()
         },
-        children![brush_node(font), grid_node(&handle)],
+        children![
+            brush_node(font.clone()),
+            grid_node(&handle),
+            probe_readout(font)
+        ],
     ));
 
     commands.insert_resource(OutputFrameHandle(handle));
-    commands.spawn(ParticleBrush::new());
+    let mut particle_brush = ParticleBrush::new();
+    if let Some(config) = brush_config_file.and_then(|f| BrushConfig::load(&f)) {
+        particle_brush.apply_config(&config);
+    }
+    commands.spawn(particle_brush);
 }
 
-fn update_grid_system(mut grid: Query<&mut Grid>) {
-    if let Ok(mut g) = grid.single_mut() {
+fn save_brush_config_system(
+    particle_brush: Query<&ParticleBrush>,
+    brush_config_file: Option<Res<BrushConfigFile>>,
+    mut saved: Local<Option<BrushConfig>>,
+) {
+    if let Some(file) = brush_config_file
+        && let Ok(pb) = particle_brush.single()
+    {
+        let config = pb.config();
+        if saved.get_or_insert_with(|| config.clone()) != &config {
+            let _ = config.save(&file);
+            *saved = Some(config);
+        }
+    }
+}
+
+fn probe_readout_system(
+    probe: Res<RegionProbe>,
+    mut readout: Query<&mut Text, With<ProbeReadout>>,
+) {
+    if probe.is_changed()
+        && let Some(stats) = &probe.stats
+        && let Ok(mut text) = readout.single_mut()
+    {
+        text.0 = stats.to_string();
+    }
+}
+
+#[cfg(feature = "parallel_sim")]
+fn init_sim_thread_system(mut commands: Commands, config: Res<ConfigResource>) {
+    commands.insert_resource(SimThread::spawn(
+        new_grid(&config),
+        std::time::Duration::from_secs_f64(1. / config.update_rate),
+    ));
+}
+
+fn read_sim_thread_system(mut grid: Query<&mut Grid>, sim_thread: Option<Res<SimThread>>) {
+    if let Ok(mut g) = grid.single_mut()
+        && let Some(s) = sim_thread
+    {
+        s.read_snapshot(&mut g);
+    }
+}
+
+fn update_grid_system(
+    mut grid: Query<&mut Grid>,
+    mut metrics: ResMut<SimMetrics>,
+    sim_thread: Option<Res<SimThread>>,
+) {
+    if let Ok(mut g) = grid.single_mut()
+        && sim_thread.is_none()
+    {
+        let active_windows = g.active_window_count();
+        let start = Instant::now();
         g.update_grid();
+        *metrics = SimMetrics {
+            update_duration: start.elapsed(),
+            active_windows,
+            cycle: g.cycle(),
+        };
+    }
+}
+
+fn rain_system(
+    rain: Option<Res<RainSpawner>>,
+    mut grid: Query<&mut Grid>,
+    sim_thread: Option<Res<SimThread>>,
+) {
+    if let Some(rain) = rain
+        && let Ok(mut g) = grid.single_mut()
+    {
+        let (kind, rate) = (rain.particle_kind.clone(), rain.rate);
+        edit_grid(&mut g, sim_thread.as_deref(), move |g| {
+            g.spawn_rain(&kind, rate)
+        });
+    }
+}
+
+fn edit_grid(
+    grid: &mut Grid,
+    sim_thread: Option<&SimThread>,
+    edit: impl FnOnce(&mut Grid) + Send + 'static,
+) {
+    match sim_thread {
+        Some(s) => s.send(edit),
+        None => edit(grid),
     }
 }
 
@@ -213,26 +594,78 @@ fn draw_grid_system(
     mut grid: Query<&mut Grid>,
     output_frame_handle: Res<OutputFrameHandle>,
     mut images: ResMut<Assets<Image>>,
+    config: Res<ConfigResource>,
 ) {
-    if let Ok(mut g) = grid.single_mut()
-        && let Some(image) = images.get_mut(&output_frame_handle.0)
+    if let Ok(mut g) = grid.single_mut() {
+        if images.get(&output_frame_handle.0).is_none() {
+            g.redraw_all();
+        }
+        if let Ok(image) = images.get_or_insert_with(&output_frame_handle.0, || {
+            let (width, height) = config.frame_size();
+            Grid::create_output_frame(width, height)
+        }) {
+            g.draw_grid(image);
+        }
+    }
+}
+
+fn view_input_system(
+    mut view: ResMut<ViewTransform>,
+    config: Res<ConfigResource>,
+    scroll: Option<Res<AccumulatedMouseScroll>>,
+    motion: Option<Res<AccumulatedMouseMotion>>,
+    buttons: Option<Res<ButtonInput<MouseButton>>>,
+) {
+    if let Some(scroll) = scroll
+        && scroll.delta.y != 0.
+    {
+        view.zoom_by(1.1_f32.powf(scroll.delta.y));
+    }
+    if let Some(motion) = motion
+        && let Some(buttons) = buttons
+        && buttons.pressed(MouseButton::Middle)
+        && motion.delta != Vec2::ZERO
+    {
+        let (width, height) = config.frame_size();
+        let zoom = view.zoom;
+        view.pan(-motion.delta / (Vec2::new(width as f32, height as f32) * zoom));
+    }
+}
+
+fn apply_view_system(
+    view: Res<ViewTransform>,
+    config: Res<ConfigResource>,
+    mut image_node: Query<&mut ImageNode>,
+) {
+    if view.is_changed()
+        && let Ok(mut node) = image_node.single_mut()
     {
-        g.draw_grid(image);
+        let (width, height) = config.frame_size();
+        let frame_size = Vec2::new(width as f32, height as f32);
+        let rect = view.visible_rect();
+        node.rect = Some(Rect::from_corners(
+            rect.min * frame_size,
+            rect.max * frame_size,
+        ));
     }
 }
 
-fn spawn_brush_system(mut particle_brush: Query<&mut ParticleBrush>, mut grid: Query<&mut Grid>) {
+fn spawn_brush_system(
+    mut particle_brush: Query<&mut ParticleBrush>,
+    mut grid: Query<&mut Grid>,
+    sim_thread: Option<Res<SimThread>>,
+) {
     if let Ok(mut g) = grid.single_mut()
         && let Ok(mut pb) = particle_brush.single_mut()
         && pb.spawning
     {
         while pb.positions.len() != 1 {
             if let Some(position) = pb.positions.pop_front() {
-                g.spawn_brush(position, pb.size, pb.particle_kind.as_ref());
+                edit_grid(&mut g, sim_thread.as_deref(), pb.stroke(position));
             }
         }
         if let Some(&position) = pb.positions.front() {
-            g.spawn_brush(position, pb.size, pb.particle_kind.as_ref());
+            edit_grid(&mut g, sim_thread.as_deref(), pb.stroke(position));
         }
     }
 }
@@ -261,13 +694,86 @@ fn observe_clear_button_system(
     clear_button: Query<Entity, With<ClearButton>>,
 ) {
     if let Ok(entity) = clear_button.single() {
-        commands
-            .entity(entity)
-            .observe(|_: On<Pointer<Click>>, mut grid: Query<&mut Grid>| {
+        commands.entity(entity).observe(
+            |_: On<Pointer<Click>>,
+             mut grid: Query<&mut Grid>,
+             sim_thread: Option<Res<SimThread>>| {
                 if let Ok(mut g) = grid.single_mut() {
-                    g.clear_grid();
+                    edit_grid(&mut g, sim_thread.as_deref(), |g| g.clear_grid());
+                }
+            },
+        );
+    }
+}
+
+/// A brush setting flipped by clicking the button carrying this marker.
+trait BrushToggle: Component {
+    fn toggle(pb: &mut ParticleBrush);
+}
+
+impl BrushToggle for LineButton {
+    fn toggle(pb: &mut ParticleBrush) {
+        pb.line = !pb.line;
+        pb.line_anchor = None;
+    }
+}
+
+impl BrushToggle for EraseButton {
+    fn toggle(pb: &mut ParticleBrush) {
+        pb.erase_only = !pb.erase_only;
+    }
+}
+
+impl BrushToggle for MixButton {
+    fn toggle(pb: &mut ParticleBrush) {
+        pb.secondary_kind = match pb.secondary_kind {
+            Some(_) => None,
+            None => pb.particle_kind.clone(),
+        };
+        // a mixed stroke can't also be sprayed, so one turns the other off
+        pb.spray &= pb.secondary_kind.is_none();
+    }
+}
+
+impl BrushToggle for ReplaceButton {
+    fn toggle(pb: &mut ParticleBrush) {
+        pb.paint_mode = match pb.paint_mode {
+            PaintMode::Replace => PaintMode::FillEmpty,
+            _ => PaintMode::Replace,
+        };
+    }
+}
+
+impl BrushToggle for BehindButton {
+    fn toggle(pb: &mut ParticleBrush) {
+        pb.paint_mode = match pb.paint_mode {
+            PaintMode::Behind => PaintMode::FillEmpty,
+            _ => PaintMode::Behind,
+        };
+    }
+}
+
+impl BrushToggle for SprayButton {
+    fn toggle(pb: &mut ParticleBrush) {
+        pb.spray = !pb.spray;
+        if pb.spray {
+            pb.secondary_kind = None;
+        }
+    }
+}
+
+fn observe_toggle_button_system<T: BrushToggle>(
+    mut commands: Commands,
+    toggle_button: Query<Entity, With<T>>,
+) {
+    if let Ok(entity) = toggle_button.single() {
+        commands.entity(entity).observe(
+            |_: On<Pointer<Click>>, mut particle_brush: Query<&mut ParticleBrush>| {
+                if let Ok(mut pb) = particle_brush.single_mut() {
+                    T::toggle(&mut pb);
                 }
-            });
+            },
+        );
     }
 }
 
@@ -278,10 +784,73 @@ fn init_inputs_system(mut commands: Commands, image_node_query: Query<Entity, Wi
             .observe(
                 |m: On<Pointer<Press>>,
                  mut particle_brush: Query<&mut ParticleBrush>,
-                 config: Res<ConfigResource>| {
+                 mut grid: Query<&mut Grid>,
+                 sim_thread: Option<Res<SimThread>>,
+                 config: Res<ConfigResource>,
+                 view: Res<ViewTransform>,
+                 keys: Option<Res<ButtonInput<KeyCode>>>| {
+                    if m.button != PointerButton::Primary {
+                        return;
+                    }
                     if let Ok(mut pb) = particle_brush.single_mut() {
+                        let position = m.hit.position.map(|p| config.frame_to_grid(view.apply(p)));
+                        let grid_size = (config.width, config.height);
+                        if keys.as_ref().is_some_and(|k| {
+                            k.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+                        }) {
+                            pb.copy_anchor =
+                                position.map(|p| ParticleBrush::grid_position(p, grid_size));
+                            return;
+                        }
+                        if keys
+                            .as_ref()
+                            .is_some_and(|k| k.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]))
+                        {
+                            pb.probe_anchor =
+                                position.map(|p| ParticleBrush::grid_position(p, grid_size));
+                            return;
+                        }
+                        if keys.as_ref().is_some_and(|k| {
+                            k.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+                        }) {
+                            if let Some(p) = position
+                                && let Ok(mut g) = grid.single_mut()
+                            {
+                                let position = ParticleBrush::grid_position(p, grid_size);
+                                edit_grid(&mut g, sim_thread.as_deref(), move |g| {
+                                    g.remove_connected(position, true);
+                                });
+                            }
+                            return;
+                        }
+                        if let Some(p) = position
+                            && let Some(stamp) = pb.stamp.take()
+                            && let Ok(mut g) = grid.single_mut()
+                        {
+                            let (position, replace) = (
+                                ParticleBrush::grid_position(p, grid_size),
+                                pb.paint_mode == PaintMode::Replace,
+                            );
+                            edit_grid(&mut g, sim_thread.as_deref(), move |g| {
+                                g.paste_stamp(position, &stamp, replace)
+                            });
+                            return;
+                        }
+                        if pb.line {
+                            if let Some(p) = position
+                                && let Some((start, end)) =
+                                    pb.set_line_point(p, (config.width, config.height))
+                                && let Ok(mut g) = grid.single_mut()
+                            {
+                                let (size, kind) = (pb.size, pb.particle_kind.clone());
+                                edit_grid(&mut g, sim_thread.as_deref(), move |g| {
+                                    g.draw_line(start, end, size, kind.as_ref())
+                                });
+                            }
+                            return;
+                        }
                         pb.start_spawning();
-                        if let Some(p) = m.hit.position {
+                        if let Some(p) = position {
                             pb.positions = VecDeque::new();
                             pb.set_position(p, (config.width, config.height));
                             pb.last_position = Some(p);
@@ -290,9 +859,43 @@ fn init_inputs_system(mut commands: Commands, image_node_query: Query<Entity, Wi
                 },
             )
             .observe(
-                |_: On<Pointer<Release>>, mut particle_brush: Query<&mut ParticleBrush>| {
+                |m: On<Pointer<Release>>,
+                 mut particle_brush: Query<&mut ParticleBrush>,
+                 grid: Query<&Grid>,
+                 mut probe: ResMut<RegionProbe>,
+                 config: Res<ConfigResource>,
+                 view: Res<ViewTransform>| {
+                    if m.button != PointerButton::Primary {
+                        return;
+                    }
                     if let Ok(mut pb) = particle_brush.single_mut() {
                         pb.stop_spawning();
+                        if let Some(anchor) = pb.probe_anchor.take()
+                            && let Some(p) = m.hit.position
+                            && let Ok(g) = grid.single()
+                        {
+                            let corner = ParticleBrush::grid_position(
+                                config.frame_to_grid(view.apply(p)),
+                                (config.width, config.height),
+                            );
+                            probe.stats = Some(g.region_stats(
+                                (anchor.0.min(corner.0), anchor.1.min(corner.1)),
+                                (anchor.0.max(corner.0), anchor.1.max(corner.1)),
+                            ));
+                        }
+                        if let Some(anchor) = pb.copy_anchor.take()
+                            && let Some(p) = m.hit.position
+                            && let Ok(g) = grid.single()
+                        {
+                            let corner = ParticleBrush::grid_position(
+                                config.frame_to_grid(view.apply(p)),
+                                (config.width, config.height),
+                            );
+                            pb.stamp = Some(g.copy_region(
+                                (anchor.0.min(corner.0), anchor.1.min(corner.1)),
+                                (anchor.0.max(corner.0), anchor.1.max(corner.1)),
+                            ));
+                        }
                     }
                 },
             )
@@ -306,12 +909,16 @@ fn init_inputs_system(mut commands: Commands, image_node_query: Query<Entity, Wi
             .observe(
                 |m: On<Pointer<Move>>,
                  mut particle_brush: Query<&mut ParticleBrush>,
-                 config: Res<ConfigResource>| {
+                 config: Res<ConfigResource>,
+                 view: Res<ViewTransform>| {
                     if let Ok(mut pb) = particle_brush.single_mut()
                         && let Some(p) = m.hit.position
                         && pb.spawning
                     {
-                        pb.set_position_linear(p, (config.width, config.height));
+                        pb.set_position_linear(
+                            config.frame_to_grid(view.apply(p)),
+                            (config.width, config.height),
+                        );
                     }
                 },
             );
@@ -334,15 +941,44 @@ fn brush_node(font: Handle<Font>) -> impl Bundle {
             radio(Some(Particle::from(Salt::new())), font.clone()),
             radio(Some(Particle::from(Water::new())), font.clone()),
             radio(Some(Particle::from(Acid::new())), font.clone()),
+            radio(Some(Particle::from(Slime::new())), font.clone()),
+            radio(Some(Particle::from(Virus::new())), font.clone()),
             radio(Some(Particle::from(Rock::new())), font.clone()),
             radio(Some(Particle::from(Drain::new())), font.clone()),
             radio(Some(Particle::from(Tap::new())), font.clone()),
+            radio(Some(Particle::from(Generator::new())), font.clone()),
+            radio(Some(Particle::from(Reservoir::new())), font.clone()),
+            radio(Some(Particle::from(Teleporter::new())), font.clone()),
+            radio(Some(Particle::from(Crystal::new())), font.clone()),
+            radio(Some(Particle::from(Dust::new())), font.clone()),
+            radio(Some(Particle::from(Bedrock::new())), font.clone()),
+            radio(Some(Particle::from(Cryo::new())), font.clone()),
+            radio(Some(Particle::from(Ice::new())), font.clone()),
+            radio(Some(Particle::from(Metal::new())), font.clone()),
+            radio(Some(Particle::from(Magnet::new())), font.clone()),
+            radio(Some(Particle::from(Membrane::new())), font.clone()),
+            radio(Some(Particle::from(Rope::new())), font.clone()),
+            radio(Some(Particle::from(Dye::new())), font.clone()),
+            radio(Some(Particle::from(Fuse::new())), font.clone()),
+            radio(Some(Particle::from(Fuse::lit())), font.clone()),
+            radio(Some(Particle::from(Gate::new())), font.clone()),
+            radio(Some(Particle::from(Glass::new())), font.clone()),
+            radio(Some(Particle::from(Sticky::new())), font.clone()),
+            radio(Some(Particle::from(Steam::new())), font.clone()),
+            radio(Some(Particle::from(Wire::new())), font.clone()),
+            radio(Some(Particle::from(Wire::powered())), font.clone()),
             radio(None, font.clone()),
             (Node {
                 flex_grow: 100.0,
                 width: Val::Auto,
                 ..default()
             }),
+            toggle_button("replace", ReplaceButton, font.clone()),
+            toggle_button("behind", BehindButton, font.clone()),
+            toggle_button("spray", SprayButton, font.clone()),
+            toggle_button("mix", MixButton, font.clone()),
+            toggle_button("erase only", EraseButton, font.clone()),
+            toggle_button("line", LineButton, font.clone()),
             clear_button(font.clone()),
         ],
     )
@@ -418,6 +1054,53 @@ fn clear_button(font: Handle<Font>) -> impl Bundle {
     )
 }
 
+fn toggle_button(label: &str, marker: impl Component, font: Handle<Font>) -> impl Bundle {
+    let color = Color::WHITE;
+    (
+        Node {
+            height: px(26),
+            flex_grow: 1.0,
+            padding: UiRect::all(px(2)),
+            margin: UiRect::all(px(2)),
+            border: UiRect::all(px(3)),
+            align_items: AlignItems::Center,
+            justify_content: JustifyContent::Center,
+            ..default()
+        },
+        BorderColor::all(color),
+        marker,
+        BackgroundColor(color.with_alpha(0.3)),
+        Button,
+        children![(
+            Text::new(label),
+            TextFont {
+                font_size: 16.,
+                font,
+                ..default()
+            }
+        )],
+    )
+}
+
+fn probe_readout(font: Handle<Font>) -> impl Bundle {
+    (
+        Node {
+            padding: UiRect::all(px(2)),
+            ..default()
+        },
+        BackgroundColor(Color::BLACK),
+        children![(
+            ProbeReadout,
+            Text::new("alt-drag to probe a region"),
+            TextFont {
+                font_size: 16.,
+                font,
+                ..default()
+            }
+        )],
+    )
+}
+
 fn grid_node(handle: &Handle<Image>) -> impl Bundle {
     (
         Node {
@@ -436,7 +1119,9 @@ mod tests {
 
     use std::time::Duration;
 
-    use crate::component::grid::{Cell, GridAccess};
+    #[cfg(not(feature = "parallel_sim"))]
+    use crate::component::grid::Cell;
+    use crate::component::grid::GridAccess;
     use crate::component::particles::particle::Particle;
     use crate::component::particles::rock::Rock;
     use crate::component::{grid::BACKGROUND_COLOR, macros::assert_color_srgb_eq};
@@ -448,7 +1133,7 @@ mod tests {
     use bevy::picking::DefaultPickingPlugins;
     use bevy::picking::backend::HitData;
     use bevy::picking::events::Click;
-    use bevy::picking::pointer::{Location, PointerButton, PointerId};
+    use bevy::picking::pointer::{Location, PointerId};
     use bevy::prelude::default;
     use bevy::window::Window;
     use bevy::{
@@ -477,22 +1162,124 @@ mod tests {
     }
 
     #[test]
-    fn test_draw_grid_system() {
+    fn test_brush_config_file_is_loaded_on_startup_and_saved_on_change() {
+        let file = BrushConfigFile(std::env::temp_dir().join(format!(
+            "sandsim-plugin-brush-config-{}.cfg",
+            std::process::id()
+        )));
+        BrushConfig {
+            particle_kind_id: Some(ParticleKind::from(Water::new()).id()),
+            size: 3,
+            ..default()
+        }
+        .save(&file)
+        .unwrap();
+
         let mut app = App::new();
         app.init_resource::<Assets<Image>>();
+        app.insert_resource(file.clone());
         app.add_plugins(GridPlugin {
             config: ConfigResource::new(2, 2, 100., (0, 50)),
         });
-
         app.update();
 
-        let mut grid = app.world_mut().query::<&mut Grid>();
-        if let Ok(mut g) = grid.single_mut(app.world_mut()) {
-            g.spawn_particle((0, 1), Particle::from(Water::new()));
-            g.spawn_particle((1, 1), Particle::from(Sand::new()));
-        } else {
-            panic!("grid not found");
-        }
+        let pb = query_particle_brush(&mut app);
+        assert_eq!(
+            (Some(ParticleKind::from(Water::new())), 3),
+            (pb.particle_kind.clone(), pb.size)
+        );
+
+        let mut particle_brush = app.world_mut().query::<&mut ParticleBrush>();
+        particle_brush.single_mut(app.world_mut()).unwrap().size = 5;
+        app.update();
+
+        let loaded = BrushConfig::load(&file);
+        let _ = std::fs::remove_file(&file.0);
+        assert_eq!(Some(5), loaded.map(|c| c.size));
+    }
+
+    #[test]
+    fn test_try_new_rejects_invalid_configs() {
+        assert!(ConfigResource::try_new(2, 3, 60., (0, 50)).is_ok());
+        assert!(ConfigResource::try_new(2, 3, 0., (0, 50)).is_ok());
+        assert_eq!(
+            Some(ConfigError::ZeroWidth),
+            ConfigResource::try_new(0, 3, 60., (0, 50)).err()
+        );
+        assert_eq!(
+            Some(ConfigError::ZeroHeight),
+            ConfigResource::try_new(2, 0, 60., (0, 50)).err()
+        );
+        for rate in [-1., f64::NAN, f64::INFINITY] {
+            assert_eq!(
+                Some(ConfigError::InvalidUpdateRate),
+                ConfigResource::try_new(2, 3, rate, (0, 50)).err()
+            );
+        }
+    }
+
+    #[test]
+    fn test_border_grows_the_frame_and_maps_frame_positions_onto_the_grid() {
+        let config = ConfigResource::new(10, 10, 60., (0, 50)).with_border(Color::WHITE, 2);
+
+        assert_eq!((14, 14), config.frame_size());
+        assert_eq!(
+            (0, 9),
+            ParticleBrush::grid_position(
+                config.frame_to_grid(vec3(2.5 / 14. - 0.5, 11.5 / 14. - 0.5, 0.)),
+                (10, 10)
+            )
+        );
+    }
+
+    #[test]
+    #[should_panic(expected = "invalid GridPlugin config: grid width must be greater than zero")]
+    fn test_grid_plugin_panics_with_the_config_error() {
+        App::new().add_plugins(GridPlugin {
+            config: ConfigResource::new(0, 3, 60., (0, 50)),
+        });
+    }
+
+    #[test]
+    fn test_zero_update_rate_never_advances_the_grid() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(1, 2, 0., (0, 50)),
+        });
+
+        app.update();
+        let mut grid = app.world_mut().query::<&mut Grid>();
+        if let Ok(mut g) = grid.single_mut(app.world_mut()) {
+            g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        }
+        for _ in 0..3 {
+            let _ = app.world_mut().try_run_schedule(FixedUpdate);
+            app.update();
+        }
+
+        let mut grid = app.world_mut().query::<&Grid>();
+        let g = grid.single(app.world()).unwrap();
+        assert_eq!((0, "s/-".to_string()), (g.cycle(), g.to_rle()));
+    }
+
+    #[test]
+    fn test_draw_grid_system() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(2, 2, 100., (0, 50)),
+        });
+
+        app.update();
+
+        let mut grid = app.world_mut().query::<&mut Grid>();
+        if let Ok(mut g) = grid.single_mut(app.world_mut()) {
+            g.spawn_particle((0, 1), Particle::from(Water::new()));
+            g.spawn_particle((1, 1), Particle::from(Sand::new()));
+        } else {
+            panic!("grid not found");
+        }
 
         app.update();
 
@@ -515,6 +1302,39 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "parallel_sim"))]
+    fn test_draw_grid_system_recreates_a_removed_output_frame() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(2, 2, 100., (0, 50)),
+        });
+
+        app.update();
+        let mut grid = app.world_mut().query::<&mut Grid>();
+        if let Ok(mut g) = grid.single_mut(app.world_mut()) {
+            g.spawn_particle((1, 1), Particle::from(Rock::new()));
+        }
+        app.update();
+
+        let handle = app.world().resource::<OutputFrameHandle>().0.clone();
+        app.world_mut()
+            .resource_mut::<Assets<Image>>()
+            .remove(&handle);
+        app.update();
+
+        let images = app.world().resource::<Assets<Image>>();
+        let image = images.get(&handle).expect("image not recreated");
+        assert_eq!((2, 2), (image.width(), image.height()));
+        assert_color_srgb_eq!(BACKGROUND_COLOR, image.get_color_at(0, 0).unwrap());
+        assert_color_srgb_eq!(
+            Particle::from(Rock::new()).color(),
+            image.get_color_at(1, 1).unwrap()
+        );
+    }
+
+    #[test]
+    #[cfg(not(feature = "parallel_sim"))]
     fn test_particle_brush_spawn_position_until_no_positions_remains_but_keep_the_last_one() {
         let mut app = App::new();
         app.init_resource::<Assets<Image>>();
@@ -569,6 +1389,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "parallel_sim"))]
     fn test_particle_brush_size() {
         let mut app = App::new();
         app.init_resource::<Assets<Image>>();
@@ -592,7 +1413,7 @@ mod tests {
         if let Ok(mut s) = s.single_mut(app.world_mut()) {
             s.spawning = true;
             s.size = 2;
-            s.positions.push_back((0, 0));
+            s.positions.push_back((1, 1));
         } else {
             panic!("ParticleBrush not found");
         }
@@ -601,7 +1422,7 @@ mod tests {
         let mut grid = app.world_mut().query::<&Grid>();
         if let Ok(g) = grid.single(app.world()) {
             assert_eq!(
-                vec![Some(()), Some(()), Some(()), None],
+                vec![Some(()), Some(()), Some(()), Some(())],
                 g.get_cells()
                     .iter()
                     .map(|c| match c.particle {
@@ -615,6 +1436,34 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_particle_brush_stroke_applies_the_paint_mode_to_spray_and_mix() {
+        /*
+         * w -> s (spray) | r (mix)
+         */
+        let stroke = |spray: bool, secondary_kind: Option<ParticleKind>, paint_mode| {
+            let pb = ParticleBrush {
+                size: 1,
+                paint_mode,
+                spray,
+                secondary_kind,
+                ..ParticleBrush::new()
+            };
+            let mut g = Grid::from_rle("w")
+                .unwrap()
+                .with_rand_spray_velocity(|_| (0, 0))
+                .with_rand_mix_probability(|_| 0.);
+            pb.stroke((0, 0))(&mut g);
+            g.to_rle()
+        };
+        let rock = Some(ParticleKind::from(Rock::new()));
+
+        assert_eq!("s", stroke(true, None, PaintMode::Replace));
+        assert_eq!("w", stroke(true, None, PaintMode::Behind));
+        assert_eq!("r", stroke(false, rock.clone(), PaintMode::Replace));
+        assert_eq!("w", stroke(false, rock, PaintMode::Behind));
+    }
+
     #[test]
     fn test_particle_brush_start_and_stop_spawning() {
         let mut app = App::new();
@@ -637,7 +1486,7 @@ mod tests {
         trigger_pressed_event(&mut app, Vec3::ZERO);
         assert!(query_particle_brush(&mut app).spawning);
 
-        trigger_released_event(&mut app);
+        trigger_released_event(&mut app, None);
         assert!(!query_particle_brush(&mut app).spawning);
 
         trigger_pressed_event(&mut app, Vec3::ZERO);
@@ -647,6 +1496,42 @@ mod tests {
         assert!(!query_particle_brush(&mut app).spawning);
     }
 
+    #[test]
+    fn test_middle_button_press_does_not_spawn_particles() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(300, 200, 100., (0, 50)),
+        });
+
+        app.update();
+
+        trigger_pressed_event_with_button(&mut app, Vec3::ZERO, PointerButton::Middle);
+        assert!(!query_particle_brush(&mut app).spawning);
+
+        trigger_move_event(&mut app, vec3(0.1, 0.1, 0.));
+        app.update();
+
+        let mut grid = app.world_mut().query::<&Grid>();
+        let g = grid.single(app.world()).unwrap();
+        assert_eq!(
+            0,
+            g.get_cells()
+                .iter()
+                .filter(|c| c.particle.is_some())
+                .count()
+        );
+    }
+
     #[test]
     fn test_particle_brush_pressed_event_sets_brush_position_and_clears_the_positions() {
         let mut app = App::new();
@@ -687,6 +1572,226 @@ mod tests {
             query_particle_brush(&mut app).last_position.unwrap()
         );
     }
+    #[cfg(not(feature = "parallel_sim"))]
+    #[test]
+    fn test_shift_drag_copies_a_stamp_and_the_next_press_pastes_it() {
+        /*
+         * ss-- -> ss--
+         * ----    ----
+         * ----    --ss
+         * ----    ----
+         */
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(4, 4, 100., (0, 50)),
+        });
+
+        app.update();
+        let mut grid = app.world_mut().query::<&mut Grid>();
+        if let Ok(mut g) = grid.single_mut(app.world_mut()) {
+            g.spawn_particle((0, 0), Particle::from(Sand::new()));
+            g.spawn_particle((1, 0), Particle::from(Sand::new()));
+        }
+
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.press(KeyCode::ShiftLeft);
+        trigger_pressed_event(&mut app, vec3(-0.5, -0.5, 0.));
+        trigger_released_event(&mut app, Some(vec3(-0.25, -0.5, 0.)));
+        assert_eq!(
+            Some((2, 1)),
+            query_particle_brush(&mut app)
+                .stamp
+                .as_ref()
+                .map(|s| s.size)
+        );
+
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.release(KeyCode::ShiftLeft);
+        trigger_pressed_event(&mut app, vec3(0., 0., 0.));
+
+        assert_eq!(None, query_particle_brush(&mut app).stamp);
+        let mut grid = app.world_mut().query::<&Grid>();
+        assert_eq!(
+            "2s2-/4-/2-2s/4-",
+            grid.single(app.world()).unwrap().to_rle()
+        );
+    }
+
+    #[test]
+    fn test_alt_drag_probes_the_stats_of_a_region() {
+        /*
+         * ss--
+         * --s-
+         * ----
+         * ---w
+         */
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(4, 4, 0., (0, 50)),
+        });
+
+        app.update();
+        let mut grid = app.world_mut().query::<&mut Grid>();
+        if let Ok(mut g) = grid.single_mut(app.world_mut()) {
+            for position in [(0, 0), (1, 0), (2, 1)] {
+                g.spawn_particle(position, Particle::from(Sand::new()));
+            }
+            g.spawn_particle((3, 3), Particle::from(Water::new()));
+        }
+
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.press(KeyCode::AltLeft);
+        trigger_pressed_event(&mut app, vec3(0., 0., 0.));
+        trigger_released_event(&mut app, Some(vec3(-0.5, -0.5, 0.)));
+        app.update();
+
+        assert!(!query_particle_brush(&mut app).spawning);
+        let stats = app.world().resource::<RegionProbe>().stats.clone().unwrap();
+        assert_eq!(Some(&3), stats.counts.get("sand"));
+        assert_eq!(None, stats.counts.get("water"));
+        let mut readout = app
+            .world_mut()
+            .query_filtered::<&Text, With<ProbeReadout>>();
+        assert_eq!(stats.to_string(), readout.single(app.world()).unwrap().0);
+    }
+
+    #[test]
+    fn test_linear_positions_follow_the_interpolation_steps() {
+        let mut pb = ParticleBrush::new();
+        pb.interpolation_steps = 2;
+
+        pb.set_position_linear(vec3(-0.5, -0.5, 0.), (4, 4));
+        pb.set_position_linear(vec3(0.25, -0.5, 0.), (4, 4));
+
+        assert_eq!(VecDeque::from([(0, 0), (1, 0), (3, 0)]), pb.positions);
+    }
+
+    #[cfg(not(feature = "parallel_sim"))]
+    #[test]
+    fn test_control_press_removes_the_connected_particles_of_the_same_kind() {
+        /*
+         * ww-- -> ----
+         * ----    ----
+         * --ww    --ww
+         * ----    ----
+         */
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(4, 4, 100., (0, 50)),
+        });
+
+        app.update();
+        let mut grid = app.world_mut().query::<&mut Grid>();
+        if let Ok(mut g) = grid.single_mut(app.world_mut()) {
+            for position in [(0, 0), (1, 0), (2, 2), (3, 2)] {
+                g.spawn_particle(position, Particle::from(Water::new()));
+            }
+        }
+
+        let mut keys = app.world_mut().resource_mut::<ButtonInput<KeyCode>>();
+        keys.press(KeyCode::ControlLeft);
+        trigger_pressed_event(&mut app, vec3(-0.5, -0.5, 0.));
+
+        assert!(!query_particle_brush(&mut app).spawning);
+        let mut grid = app.world_mut().query::<&Grid>();
+        assert_eq!("4-/4-/2-2w/4-", grid.single(app.world()).unwrap().to_rle());
+    }
+
+    #[test]
+    fn test_zoomed_view_maps_pressed_event_to_the_visible_cells() {
+        /*
+         * zoom 2 on the bottom right quarter:
+         * +---+---+
+         * |   |   |
+         * +---+---+
+         * |   |###|
+         * +---+---+
+         */
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(300, 200, 100., (0, 50)),
+        });
+        app.insert_resource(ViewTransform {
+            zoom: 2.,
+            center: Vec2::splat(0.75),
+        });
+
+        app.update();
+
+        trigger_pressed_event(&mut app, vec3(-0.5, -0.5, 0.));
+        assert_eq!(
+            VecDeque::from([(150, 100)]),
+            query_particle_brush(&mut app).positions
+        );
+
+        trigger_pressed_event(&mut app, vec3(0., 0., 0.));
+        assert_eq!(
+            VecDeque::from([(225, 150)]),
+            query_particle_brush(&mut app).positions
+        );
+        assert_eq!(
+            Some(Rect::new(150., 100., 300., 200.)),
+            app.world_mut()
+                .query::<&ImageNode>()
+                .single(app.world())
+                .unwrap()
+                .rect
+        );
+    }
+
+    #[test]
+    fn test_view_transform_keeps_the_visible_rect_inside_the_grid() {
+        let mut view = ViewTransform::default();
+
+        view.zoom_by(0.5);
+        assert_eq!(ViewTransform::default(), view);
+
+        view.zoom_by(4.);
+        view.pan(Vec2::new(-1., 1.));
+        assert_eq!(Rect::new(0., 0.75, 0.25, 1.), view.visible_rect());
+    }
+
     #[test]
     fn test_particle_brush_move_brush_after_press_event() {
         let mut app = App::new();
@@ -872,6 +1977,7 @@ mod tests {
     }
 
     #[test]
+    #[cfg(not(feature = "parallel_sim"))]
     fn test_clear_buttons_clears_the_grid() {
         let mut app = App::new();
         app.init_resource::<Assets<Image>>();
@@ -941,21 +2047,426 @@ mod tests {
         }
     }
 
-    fn trigger_pressed_event(app: &mut App, position: Vec3) {
-        let mut entity_query = app.world_mut().query_filtered::<Entity, With<ImageNode>>();
-        if let Ok(entity) = entity_query.single(app.world()) {
-            let event = Pointer::new(
-                PointerId::Mouse,
-                Location {
-                    target: NormalizedRenderTarget::Window(
-                        WindowRef::Entity(Entity::from_raw_u32(0).unwrap())
-                            .normalize(Some(Entity::from_raw_u32(0).unwrap()))
-                            .unwrap(),
-                    ),
-                    position: Vec2::ZERO,
-                },
+    #[test]
+    fn test_line_button_toggles_line_mode_in_particle_brush() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(300, 200, 100., (0, 50)),
+        });
+
+        app.update();
+
+        assert!(
+            !query_particle_brush(&mut app).line,
+            "line mode is off by default"
+        );
+
+        let line_button = app
+            .world_mut()
+            .query::<(Entity, &LineButton)>()
+            .single(app.world());
+        if let Ok((entity, _)) = line_button {
+            trigger_button_click_event(&mut app, entity);
+            assert!(query_particle_brush(&mut app).line);
+            trigger_button_click_event(&mut app, entity);
+            assert!(!query_particle_brush(&mut app).line);
+        } else {
+            panic!("line button not found");
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "parallel_sim"))]
+    fn test_sim_metrics_are_populated_after_an_update() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(4, 4, 100., (0, 50)).with_window_config(2, 2, 0),
+        });
+
+        app.update();
+        let mut grid = app.world_mut().query::<&mut Grid>();
+        if let Ok(mut g) = grid.single_mut(app.world_mut()) {
+            g.spawn_particle((0, 0), Particle::from(Rock::new()));
+        }
+        app.world_mut().run_schedule(FixedUpdate);
+
+        let metrics = app.world().resource::<SimMetrics>();
+        assert_eq!(1, metrics.cycle);
+        assert_eq!(4, metrics.active_windows);
+        assert!(Duration::ZERO < metrics.update_duration);
+    }
+
+    #[cfg(not(feature = "parallel_sim"))]
+    #[test]
+    fn test_rain_spawner_adds_particles_on_fixed_update() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(4, 4, 100., (0, 50)),
+        });
+        app.insert_resource(RainSpawner {
+            particle_kind: ParticleKind::from(Water::new()),
+            rate: 1,
+        });
+
+        app.update();
+        app.world_mut().run_schedule(FixedUpdate);
+
+        let mut grid = app.world_mut().query::<&Grid>();
+        let g = grid.single(app.world()).unwrap();
+        assert_eq!(
+            1,
+            g.get_cells()
+                .iter()
+                .filter(|c| c.particle.is_some())
+                .count()
+        );
+    }
+
+    #[test]
+    fn test_mix_button_keeps_the_selected_kind_as_the_secondary_kind() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(300, 200, 100., (0, 50)),
+        });
+
+        app.update();
+
+        assert_eq!(None, query_particle_brush(&mut app).secondary_kind);
+
+        let mix_button = app
+            .world_mut()
+            .query::<(Entity, &MixButton)>()
+            .single(app.world());
+        if let Ok((entity, _)) = mix_button {
+            trigger_button_click_event(&mut app, entity);
+            assert_eq!(
+                Some(ParticleKind::from(Sand::new())),
+                query_particle_brush(&mut app).secondary_kind
+            );
+            trigger_button_click_event(&mut app, entity);
+            assert_eq!(None, query_particle_brush(&mut app).secondary_kind);
+        } else {
+            panic!("mix button not found");
+        }
+    }
+
+    #[test]
+    fn test_replace_button_toggles_replace_mode_in_particle_brush() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(300, 200, 100., (0, 50)),
+        });
+
+        app.update();
+
+        assert_eq!(
+            PaintMode::FillEmpty,
+            query_particle_brush(&mut app).paint_mode,
+            "replace mode is off by default"
+        );
+
+        let replace_button = app
+            .world_mut()
+            .query::<(Entity, &ReplaceButton)>()
+            .single(app.world());
+        if let Ok((entity, _)) = replace_button {
+            trigger_button_click_event(&mut app, entity);
+            assert_eq!(
+                PaintMode::Replace,
+                query_particle_brush(&mut app).paint_mode
+            );
+            trigger_button_click_event(&mut app, entity);
+            assert_eq!(
+                PaintMode::FillEmpty,
+                query_particle_brush(&mut app).paint_mode
+            );
+        } else {
+            panic!("replace button not found");
+        }
+    }
+
+    #[test]
+    fn test_behind_button_switches_the_paint_mode_with_the_replace_button() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(300, 200, 100., (0, 50)),
+        });
+
+        app.update();
+
+        let behind_button = app
+            .world_mut()
+            .query_filtered::<Entity, With<BehindButton>>()
+            .single(app.world())
+            .unwrap();
+        let replace_button = app
+            .world_mut()
+            .query_filtered::<Entity, With<ReplaceButton>>()
+            .single(app.world())
+            .unwrap();
+
+        trigger_button_click_event(&mut app, behind_button);
+        assert_eq!(PaintMode::Behind, query_particle_brush(&mut app).paint_mode);
+        trigger_button_click_event(&mut app, replace_button);
+        assert_eq!(
+            PaintMode::Replace,
+            query_particle_brush(&mut app).paint_mode
+        );
+        trigger_button_click_event(&mut app, behind_button);
+        assert_eq!(PaintMode::Behind, query_particle_brush(&mut app).paint_mode);
+        trigger_button_click_event(&mut app, behind_button);
+        assert_eq!(
+            PaintMode::FillEmpty,
+            query_particle_brush(&mut app).paint_mode
+        );
+    }
+
+    #[test]
+    fn test_spray_button_toggles_spray_mode_in_particle_brush() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(300, 200, 100., (0, 50)),
+        });
+
+        app.update();
+
+        assert!(
+            !query_particle_brush(&mut app).spray,
+            "spray mode is off by default"
+        );
+
+        let spray_button = app
+            .world_mut()
+            .query::<(Entity, &SprayButton)>()
+            .single(app.world());
+        if let Ok((entity, _)) = spray_button {
+            trigger_button_click_event(&mut app, entity);
+            assert!(query_particle_brush(&mut app).spray);
+            trigger_button_click_event(&mut app, entity);
+            assert!(!query_particle_brush(&mut app).spray);
+        } else {
+            panic!("spray button not found");
+        }
+    }
+
+    #[test]
+    fn test_spray_and_mix_buttons_turn_each_other_off() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(300, 200, 100., (0, 50)),
+        });
+
+        app.update();
+
+        let spray_button = app
+            .world_mut()
+            .query_filtered::<Entity, With<SprayButton>>()
+            .single(app.world())
+            .unwrap();
+        let mix_button = app
+            .world_mut()
+            .query_filtered::<Entity, With<MixButton>>()
+            .single(app.world())
+            .unwrap();
+
+        trigger_button_click_event(&mut app, spray_button);
+        assert!(query_particle_brush(&mut app).spray);
+        trigger_button_click_event(&mut app, mix_button);
+        assert!(!query_particle_brush(&mut app).spray);
+        assert!(query_particle_brush(&mut app).secondary_kind.is_some());
+        trigger_button_click_event(&mut app, spray_button);
+        assert!(query_particle_brush(&mut app).spray);
+        assert_eq!(None, query_particle_brush(&mut app).secondary_kind);
+    }
+
+    #[test]
+    fn test_erase_button_toggles_erase_only_mode_in_particle_brush() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(300, 200),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(300, 200, 100., (0, 50)),
+        });
+
+        app.update();
+
+        assert!(
+            !query_particle_brush(&mut app).erase_only,
+            "erase only mode is off by default"
+        );
+
+        let erase_button = app
+            .world_mut()
+            .query::<(Entity, &EraseButton)>()
+            .single(app.world());
+        if let Ok((entity, _)) = erase_button {
+            trigger_button_click_event(&mut app, entity);
+            assert!(query_particle_brush(&mut app).erase_only);
+            trigger_button_click_event(&mut app, entity);
+            assert!(!query_particle_brush(&mut app).erase_only);
+        } else {
+            panic!("erase button not found");
+        }
+    }
+
+    #[test]
+    #[cfg(not(feature = "parallel_sim"))]
+    fn test_particle_brush_line_mode_draws_a_line_between_two_presses() {
+        let mut app = App::new();
+        app.init_resource::<Assets<Image>>();
+        app.add_plugins(InputPlugin);
+        app.add_plugins(DefaultPickingPlugins);
+        app.add_plugins(WindowPlugin {
+            primary_window: Some(Window {
+                resolution: WindowResolution::new(4, 4),
+                ..default()
+            }),
+            ..default()
+        });
+        app.add_plugins(GridPlugin {
+            config: ConfigResource::new(4, 4, 100., (0, 50)),
+        });
+
+        app.update();
+
+        let mut s = app.world_mut().query::<&mut ParticleBrush>();
+        if let Ok(mut s) = s.single_mut(app.world_mut()) {
+            s.line = true;
+            s.size = 1;
+        } else {
+            panic!("ParticleBrush not found");
+        }
+
+        trigger_pressed_event(&mut app, vec3(-0.5, -0.5, 0.));
+        assert!(!query_particle_brush(&mut app).spawning);
+        assert_eq!(Some((0, 0)), query_particle_brush(&mut app).line_anchor);
+
+        trigger_pressed_event(&mut app, vec3(0.25, 0.25, 0.));
+        assert_eq!(None, query_particle_brush(&mut app).line_anchor);
+
+        let mut grid = app.world_mut().query::<&Grid>();
+        if let Ok(g) = grid.single(app.world()) {
+            assert_eq!(
+                vec![
+                    Some(()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(()),
+                    None,
+                    None,
+                    None,
+                    None,
+                    Some(()),
+                ],
+                g.get_cells()
+                    .iter()
+                    .map(|c| match c.particle {
+                        Some(_) => Some(()),
+                        None => None,
+                    })
+                    .collect::<Vec<_>>()
+            );
+        } else {
+            panic!("grid not found");
+        }
+    }
+
+    fn trigger_pressed_event(app: &mut App, position: Vec3) {
+        trigger_pressed_event_with_button(app, position, PointerButton::Primary);
+    }
+
+    fn trigger_pressed_event_with_button(app: &mut App, position: Vec3, button: PointerButton) {
+        let mut entity_query = app.world_mut().query_filtered::<Entity, With<ImageNode>>();
+        if let Ok(entity) = entity_query.single(app.world()) {
+            let event = Pointer::new(
+                PointerId::Mouse,
+                Location {
+                    target: NormalizedRenderTarget::Window(
+                        WindowRef::Entity(Entity::from_raw_u32(0).unwrap())
+                            .normalize(Some(Entity::from_raw_u32(0).unwrap()))
+                            .unwrap(),
+                    ),
+                    position: Vec2::ZERO,
+                },
                 Press {
-                    button: PointerButton::Primary,
+                    button,
                     hit: HitData {
                         camera: Entity::from_raw_u32(0).unwrap(),
                         depth: 0.,
@@ -971,7 +2482,7 @@ mod tests {
         }
     }
 
-    fn trigger_released_event(app: &mut App) {
+    fn trigger_released_event(app: &mut App, position: Option<Vec3>) {
         let mut entity_query = app.world_mut().query_filtered::<Entity, With<ImageNode>>();
         if let Ok(entity) = entity_query.single(app.world()) {
             let event = Pointer::new(
@@ -989,7 +2500,7 @@ mod tests {
                     hit: HitData {
                         camera: Entity::from_raw_u32(0).unwrap(),
                         depth: 0.,
-                        position: None,
+                        position,
                         normal: None,
                     },
                 },
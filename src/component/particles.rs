@@ -1,8 +1,30 @@
 pub mod acid;
+pub mod bedrock;
+pub mod cryo;
+pub mod crystal;
 pub mod drain;
+pub mod dust;
+pub mod dye;
+pub mod fuse;
+pub mod gate;
+pub mod generator;
+pub mod glass;
+pub mod ice;
+pub mod magnet;
+pub mod membrane;
+pub mod metal;
 pub mod particle;
+pub mod registry;
+pub mod reservoir;
 pub mod rock;
+pub mod rope;
 pub mod salt;
 pub mod sand;
+pub mod slime;
+pub mod steam;
+pub mod sticky;
 pub mod tap;
+pub mod teleporter;
+pub mod virus;
 pub mod water;
+pub mod wire;
@@ -1,6 +1,10 @@
-use super::particle;
+use super::particle::{self, ORTHOGONAL_NEIGHBORS};
 use crate::component::grid::GridAccess;
 
+/// Acidity is how much health an acid takes from itself and each neighbor it
+/// touches per cycle. It is clamped to `0..=MAX_ACIDITY`; zero is inert.
+pub const MAX_ACIDITY: u8 = 15;
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct Acid {
     acidity: u8,
@@ -14,24 +18,43 @@ impl Default for Acid {
 
 impl Acid {
     pub fn new() -> Self {
-        Self::with_acidity(15)
+        Self::with_acidity(MAX_ACIDITY)
     }
 
     pub fn with_acidity(acidity: u8) -> Self {
-        Self { acidity }
+        Self {
+            acidity: acidity.min(MAX_ACIDITY),
+        }
+    }
+
+    pub fn acidity(&self) -> u8 {
+        self.acidity
     }
 
     pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
-        for offset in [(0, -1), (0, 1), (-1, 0), (1, 0)] {
+        for offset in ORTHOGONAL_NEIGHBORS {
+            let spent = grid
+                .get_cell(grid.to_index(position))
+                .particle
+                .as_ref()
+                .is_none_or(|p| p.health == 0);
+            if spent {
+                return;
+            }
             if let Ok(index) = grid.get_neighbor_index(position, offset)
                 && let Some(p) = &grid.get_cell(index).particle
                 && 0 < self.acidity
+                && !p.is_indestructible()
             {
                 match p.kind {
                     particle::ParticleKind::Acid(..) => (),
                     particle::ParticleKind::Drain(..) => (),
                     particle::ParticleKind::Tap(..) => (),
                     _ => {
+                        if !grid.reacts() {
+                            grid.activate_window(position);
+                            continue;
+                        }
                         let cycle = grid.cycle();
                         let cell = grid.get_cell_mut(index);
                         if let Some(particle) = &mut cell.particle {
@@ -55,10 +78,33 @@ impl Acid {
 mod tests {
     use crate::component::{
         grid::{Cell, Grid, GridAccess},
-        particles::{acid::Acid, drain::Drain, particle::Particle, rock::Rock, tap::Tap},
+        particles::{
+            acid::{Acid, MAX_ACIDITY},
+            bedrock::Bedrock,
+            drain::Drain,
+            particle::Particle,
+            rock::Rock,
+            sand::Sand,
+            tap::Tap,
+        },
     };
+    use bevy::color::{Hsva, Saturation};
     use pretty_assertions::assert_eq;
 
+    #[test]
+    fn test_acidity_is_clamped_and_scales_the_color_saturation() {
+        assert_eq!(MAX_ACIDITY, Acid::with_acidity(u8::MAX).acidity());
+        assert_eq!(Acid::new(), Acid::with_acidity(u8::MAX));
+
+        let weakest = Particle::from(Acid::with_acidity(0));
+        let strongest = Particle::from(Acid::with_acidity(MAX_ACIDITY));
+        assert!(weakest.is_liquid() && strongest.is_liquid());
+        assert_eq!(weakest.weight, strongest.weight);
+        assert!(
+            Hsva::from(weakest.color()).saturation() < Hsva::from(strongest.color()).saturation()
+        );
+    }
+
     fn corrosion_resistant_particle() -> Vec<Particle> {
         vec![
             Particle::from(Drain::with_rate(0)),
@@ -217,4 +263,100 @@ mod tests {
             *g.get_cells()
         );
     }
+
+    #[test]
+    fn test_acid_gradually_corrodes_sand_to_zero() {
+        /* sa -> -a
+         */
+        let mut g = Grid::new(2, 1);
+
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        g.spawn_particle((1, 0), Particle::from(Acid::new()));
+
+        for _ in 0..17 {
+            g.update_grid();
+        }
+
+        assert_eq!(
+            vec![
+                Cell::new(Particle::from(Sand::new()).with_health(0)).with_cycle(17),
+                Cell::new(Particle::from(Acid::new()).with_health(0)).with_cycle(17),
+            ],
+            *g.get_cells()
+        );
+    }
+
+    #[test]
+    fn test_acid_is_consumed_once_per_corroded_neighbor() {
+        /*
+         * rrr -> rrr
+         * rar    rar
+         * rrr    rrr
+         */
+        let mut g = Grid::new(3, 3);
+        for y in 0..3 {
+            for x in 0..3 {
+                g.spawn_particle((x, y), Particle::from(Rock::new()));
+            }
+        }
+        g.despawn_particle((1, 1));
+        g.spawn_particle((1, 1), Particle::from(Acid::new()));
+
+        g.update_grid();
+
+        let health = |position| {
+            g.get_cell(g.to_index(position))
+                .particle
+                .as_ref()
+                .map(|p| p.health)
+        };
+        assert_eq!(Some(255 - 4 * MAX_ACIDITY), health((1, 1)));
+        assert_eq!(Some(255 - MAX_ACIDITY), health((1, 0)));
+        assert_eq!(Some(255), health((0, 0)));
+    }
+
+    #[test]
+    fn test_spent_acid_stops_corroding_the_remaining_neighbors() {
+        /*
+         * rar -> (r)(a)r
+         */
+        let mut g = Grid::new(3, 1);
+        g.spawn_particle((0, 0), Particle::from(Rock::new()));
+        g.spawn_particle((1, 0), Particle::from(Acid::new()).with_health(MAX_ACIDITY));
+        g.spawn_particle((2, 0), Particle::from(Rock::new()));
+
+        g.update_grid();
+
+        assert_eq!(
+            vec![Some(255 - MAX_ACIDITY), Some(0), Some(255)],
+            g.get_cells()
+                .iter()
+                .map(|c| c.particle.as_ref().map(|p| p.health))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_acid_flows_as_a_liquid_over_ground_it_cannot_corrode() {
+        /*
+         * -a- -> --a
+         * BBB    BBB
+         */
+        let mut g = Grid::new(3, 2).with_rand_horizontal_velocity_probability(|_| i16::MAX);
+        g.spawn_particle((1, 0), Particle::from(Acid::new()));
+        for x in 0..3 {
+            g.spawn_particle((x, 1), Particle::from(Bedrock::new()));
+        }
+
+        g.update_grid();
+
+        assert_eq!("2-a/3B", g.to_rle());
+        assert_eq!(
+            Some(255),
+            g.get_cell(g.to_index((2, 0)))
+                .particle
+                .as_ref()
+                .map(|p| p.health)
+        );
+    }
 }
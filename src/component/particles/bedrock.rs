@@ -0,0 +1,78 @@
+#[derive(Clone, PartialEq, Debug)]
+pub struct Bedrock;
+
+impl Default for Bedrock {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Bedrock {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::{Grid, GridAccess},
+        particles::{acid::Acid, bedrock::Bedrock, drain::Drain, particle::Particle, rock::Rock},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_acid_eats_rock_but_leaves_bedrock_untouched() {
+        /*
+         * Bar -> B--
+         */
+        let mut g = Grid::new(3, 1);
+        g.spawn_particle((0, 0), Particle::from(Bedrock::new()));
+        g.spawn_particle((1, 0), Particle::from(Acid::new()));
+        g.spawn_particle((2, 0), Particle::from(Rock::new()));
+
+        for _ in 0..20 {
+            g.update_grid();
+        }
+
+        assert_eq!("B2-", g.to_rle());
+        assert_eq!(
+            Some(u8::MAX),
+            g.get_cell(0).particle.as_ref().map(|p| p.health)
+        );
+    }
+
+    #[test]
+    fn test_drain_drains_rock_but_leaves_bedrock_untouched() {
+        /*
+         * Bdr -> Bd-
+         */
+        let mut g = Grid::new(3, 1);
+        g.spawn_particle((0, 0), Particle::from(Bedrock::new()));
+        g.spawn_particle((1, 0), Particle::from(Drain::new()));
+        g.spawn_particle((2, 0), Particle::from(Rock::new()));
+
+        for _ in 0..10 {
+            g.update_grid();
+        }
+
+        assert_eq!("Bd-", g.to_rle());
+        assert_eq!(
+            Some(u8::MAX),
+            g.get_cell(0).particle.as_ref().map(|p| p.health)
+        );
+    }
+
+    #[test]
+    fn test_bedrock_survives_having_no_health() {
+        /*
+         * B -> B
+         */
+        let mut g = Grid::new(1, 1);
+        g.spawn_particle((0, 0), Particle::from(Bedrock::new()).with_health(0));
+
+        g.update_grid();
+
+        assert_eq!("B", g.to_rle());
+    }
+}
@@ -0,0 +1,100 @@
+use crate::component::grid::GridAccess;
+
+use super::{
+    ice::Ice,
+    particle::{ORTHOGONAL_NEIGHBORS, Particle, ParticleKind},
+};
+
+const CRYO_LIFETIME: u8 = 60;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Cryo {
+    warmth: u8,
+}
+
+impl Default for Cryo {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Cryo {
+    pub fn new() -> Self {
+        Self { warmth: 0 }
+    }
+
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        let cycle = grid.cycle();
+        for offset in ORTHOGONAL_NEIGHBORS {
+            if let Ok(i) = grid.get_neighbor_index(position, offset)
+                && let Some(p) = &grid.get_cell(i).particle
+                && let ParticleKind::Water(..) = p.kind
+            {
+                let seed = p.seed;
+                grid.place_particle(i, Particle::from(Ice::new()).with_seed(seed));
+                grid.get_cell_mut(i).cycle = cycle;
+            }
+        }
+
+        let cell = grid.get_cell_mut(grid.to_index(position));
+        if let Some(p) = &mut cell.particle {
+            match self.warmth + 1 {
+                CRYO_LIFETIME => *p = Particle::from(Ice::new()).with_seed(p.seed),
+                warmth => p.kind = ParticleKind::from(Cryo { warmth }),
+            }
+            cell.cycle = cycle;
+        }
+        grid.activate_window(position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::Grid,
+        particles::{
+            cryo::{CRYO_LIFETIME, Cryo},
+            particle::Particle,
+            water::Water,
+        },
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_cryo_freezes_the_surrounding_water_in_one_cycle() {
+        /*
+         * www -> wew
+         * wCw    eCe
+         * www    wew
+         */
+        let mut g = Grid::new(3, 3);
+        for y in 0..3 {
+            for x in 0..3 {
+                g.spawn_particle((x, y), Particle::from(Water::new()));
+            }
+        }
+        g.despawn_particle((1, 1));
+        g.spawn_particle((1, 1), Particle::from(Cryo::new()));
+
+        g.update_grid();
+
+        assert_eq!("wew/eCe/wew", g.to_rle());
+    }
+
+    #[test]
+    fn test_cryo_warms_up_and_turns_into_ice() {
+        /*
+         * C -> ... -> C -> e
+         */
+        let mut g = Grid::new(1, 1);
+        g.spawn_particle((0, 0), Particle::from(Cryo::new()));
+
+        for _ in 1..CRYO_LIFETIME {
+            g.update_grid();
+        }
+        assert_eq!("C", g.to_rle());
+
+        g.update_grid();
+        assert_eq!("e", g.to_rle());
+    }
+}
@@ -0,0 +1,131 @@
+use crate::component::grid::GridAccess;
+
+use super::particle::{ORTHOGONAL_NEIGHBORS, Particle, ParticleKind};
+
+const GROWTH_CYCLES: u8 = 4;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Crystal {
+    growth: u8,
+}
+
+impl Default for Crystal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Crystal {
+    pub fn new() -> Self {
+        Self { growth: 0 }
+    }
+
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        let Some(solution) = ORTHOGONAL_NEIGHBORS.into_iter().find_map(|offset| {
+            grid.get_neighbor_index(position, offset)
+                .ok()
+                .filter(|&i| Self::is_solution(grid.get_cell(i).particle.as_ref()))
+        }) else {
+            return;
+        };
+        let cycle = grid.cycle();
+        let growth = match self.growth + 1 {
+            GROWTH_CYCLES => {
+                if let Some(seed) = grid.get_cell(solution).particle.as_ref().map(|p| p.seed) {
+                    grid.place_particle(solution, Particle::from(Crystal::new()).with_seed(seed));
+                    grid.get_cell_mut(solution).cycle = cycle;
+                }
+                0
+            }
+            growth => growth,
+        };
+        let cell = grid.get_cell_mut(grid.to_index(position));
+        if let Some(p) = &mut cell.particle {
+            p.kind = ParticleKind::from(Crystal { growth });
+            cell.cycle = cycle;
+        }
+        grid.activate_window(position);
+    }
+
+    fn is_solution(particle: Option<&Particle>) -> bool {
+        matches!(
+            particle,
+            Some(Particle {
+                kind: ParticleKind::Water(w),
+                ..
+            }) if w.solvant_capacity == 0
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::{Grid, GridAccess},
+        particles::{
+            crystal::Crystal,
+            particle::{Particle, ParticleKind},
+            water::Water,
+        },
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_crystal_seed_grows_through_brine() {
+        /*
+         * www -> ... -> ccc
+         * wcw           ccc
+         * www           ccc
+         */
+        let mut g = Grid::new(3, 3);
+        for y in 0..3 {
+            for x in 0..3 {
+                g.spawn_particle((x, y), Particle::from(Water::with_capacity(0)));
+            }
+        }
+        g.despawn_particle((1, 1));
+        g.spawn_particle((1, 1), Particle::from(Crystal::new()));
+        let crystals = |g: &Grid| {
+            g.get_cells()
+                .iter()
+                .filter(|c| matches!(&c.particle, Some(p) if matches!(p.kind, ParticleKind::Crystal(..))))
+                .count()
+        };
+
+        for _ in 0..3 {
+            g.update_grid();
+        }
+        assert_eq!(1, crystals(&g));
+
+        g.update_grid();
+        assert_eq!(2, crystals(&g));
+
+        for _ in 0..40 {
+            g.update_grid();
+        }
+        assert_eq!("3c/3c/3c", g.to_rle());
+    }
+
+    #[test]
+    fn test_crystal_does_not_grow_into_fresh_water() {
+        /*
+         * www -> www
+         * wcw    wcw
+         * www    www
+         */
+        let mut g = Grid::new(3, 3);
+        for y in 0..3 {
+            for x in 0..3 {
+                g.spawn_particle((x, y), Particle::from(Water::new()));
+            }
+        }
+        g.despawn_particle((1, 1));
+        g.spawn_particle((1, 1), Particle::from(Crystal::new()));
+
+        for _ in 0..20 {
+            g.update_grid();
+        }
+
+        assert_eq!("3w/wcw/3w", g.to_rle());
+    }
+}
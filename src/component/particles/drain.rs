@@ -1,10 +1,11 @@
-use crate::component::grid::GridAccess;
+use crate::component::grid::{GridAccess, SimEvent};
 
-use super::particle;
+use super::particle::{self, ORTHOGONAL_NEIGHBORS};
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Drain {
     rate: u8,
+    fanout: usize,
 }
 
 impl Default for Drain {
@@ -19,29 +20,51 @@ impl Drain {
     }
 
     pub fn with_rate(rate: u8) -> Self {
-        Self { rate }
+        Self { rate, fanout: 1 }
+    }
+
+    /// How many neighbors are drained per update; at least one.
+    pub fn with_fanout(mut self, fanout: usize) -> Self {
+        self.fanout = fanout.max(1);
+        self
     }
 
     pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
-        for offset in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+        let mut drained = 0;
+        for offset in ORTHOGONAL_NEIGHBORS {
             if let Ok(index) = grid.get_neighbor_index(position, offset)
                 && let Some(p) = &grid.get_cell(index).particle
                 && 0 < p.health
                 && 0 < self.rate
+                && !p.is_indestructible()
             {
                 match p.kind {
                     particle::ParticleKind::Drain(..) => (),
                     _ => {
+                        if !grid.reacts() {
+                            grid.activate_window(position);
+                            continue;
+                        }
                         let cycle = grid.cycle();
                         let cell = grid.get_cell_mut(index);
+                        let mut killed = false;
                         if let Some(particle) = &mut cell.particle {
                             particle.health = particle.health.saturating_sub(self.rate);
+                            killed = particle.health == 0;
                             cell.cycle = cycle;
                         }
+                        if killed {
+                            grid.push_event(SimEvent::Drain {
+                                position: grid.to_position(index),
+                            });
+                        }
                         let cell = grid.get_cell_mut(grid.to_index(position));
                         cell.cycle = cycle;
                         grid.activate_window(position);
-                        return;
+                        drained += 1;
+                        if drained == self.fanout {
+                            return;
+                        }
                     }
                 }
             }
@@ -52,7 +75,7 @@ impl Drain {
 #[cfg(test)]
 mod tests {
     use crate::component::{
-        grid::{Cell, Grid, GridAccess},
+        grid::{Cell, Grid, GridAccess, SimEvent},
         particles::{drain::Drain, particle::Particle, rock::Rock},
     };
     use pretty_assertions::assert_eq;
@@ -91,6 +114,20 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_drain_fires_an_event_when_it_kills_a_particle() {
+        /*
+         * dr -> d(r)
+         */
+        let mut g = Grid::new(2, 1);
+        g.spawn_particle((0, 0), Particle::from(Drain::with_rate(255)));
+        g.spawn_particle((1, 0), Particle::from(Rock::new()));
+
+        g.update_grid();
+
+        assert_eq!(vec![SimEvent::Drain { position: (1, 0) }], g.drain_events());
+    }
+
     #[test]
     fn test_drain_with_rate_of_zero_doent_lower_the_neighbor_particles_health() {
         /*
@@ -169,4 +206,57 @@ mod tests {
             *g.get_cells()
         );
     }
+
+    #[test]
+    fn test_drain_with_fanout_lowers_all_neighbors_health_at_once() {
+        /*
+         * rrr -> r-r
+         * rdr    -d-
+         * rrr    r-r
+         */
+        let mut g = Grid::new(3, 3);
+
+        g.spawn_particle((1, 1), Particle::from(Drain::new().with_fanout(4)));
+        for y in 0..3 {
+            for x in 0..3 {
+                if (x, y) == (1, 1) {
+                    continue;
+                }
+                g.spawn_particle((x, y), Particle::from(Rock::new()));
+            }
+        }
+
+        for _ in 0..7 {
+            g.update_grid();
+        }
+        assert_eq!("3r/rdr/3r", g.to_rle());
+
+        g.update_grid();
+        assert_eq!("r-r/-d-/r-r", g.to_rle());
+    }
+
+    #[test]
+    fn test_drain_with_zero_fanout_drains_one_neighbor() {
+        /*
+         * rrr -> r-r
+         * rdr    rdr
+         * rrr    rrr
+         */
+        let mut g = Grid::new(3, 3);
+
+        g.spawn_particle((1, 1), Particle::from(Drain::new().with_fanout(0)));
+        for y in 0..3 {
+            for x in 0..3 {
+                if (x, y) == (1, 1) {
+                    continue;
+                }
+                g.spawn_particle((x, y), Particle::from(Rock::new()));
+            }
+        }
+
+        for _ in 0..8 {
+            g.update_grid();
+        }
+        assert_eq!("r-r/rdr/3r", g.to_rle());
+    }
 }
@@ -0,0 +1,65 @@
+#[derive(Clone, PartialEq, Debug)]
+pub struct Dust;
+
+impl Default for Dust {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dust {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::{Grid, GridAccess},
+        particles::{dust::Dust, particle::Particle, sand::Sand},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_dust_falls_slower_than_sand() {
+        /*
+         * -D-s- -> -D---
+         * -----    -----
+         * -----    ---s-
+         */
+        let mut g = Grid::new(5, 3).with_rand_vertical_velocity_probability(|_| i16::MAX / 4);
+        g.spawn_particle((1, 0), Particle::from(Dust::new()));
+        g.spawn_particle((3, 0), Particle::from(Sand::new()));
+
+        for _ in 0..3 {
+            g.update_grid();
+        }
+
+        assert_eq!("-D3-/5-/3-s-", g.to_rle());
+    }
+
+    #[test]
+    fn test_dust_drifts_sideways_while_sand_falls_straight() {
+        /*
+         * ---s--- -> ------- | -------
+         * -------    ------- | -------
+         * -------    ------- | -------
+         * -------    ---s--- | ------D
+         */
+        fn landing_column(particle: Particle) -> Option<usize> {
+            let mut g = Grid::new(7, 4)
+                .with_rand_vertical_velocity_probability(|_| 0)
+                .with_rand_horizontal_velocity_probability(|_| 1)
+                .with_rand_drift_probability(|_| 0);
+            g.spawn_particle((3, 0), particle);
+            for _ in 0..3 {
+                g.update_grid();
+            }
+            (0..7).find(|&x| g.get_cell(g.to_index((x, 3))).particle.is_some())
+        }
+
+        assert_eq!(Some(3), landing_column(Particle::from(Sand::new())));
+        assert_eq!(Some(6), landing_column(Particle::from(Dust::new())));
+    }
+}
@@ -0,0 +1,146 @@
+use bevy::color::Color;
+
+use super::particle::{ORTHOGONAL_NEIGHBORS, Particle, ParticleKind};
+use crate::component::grid::GridAccess;
+
+const TINT_COST: u8 = 64;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Dye {
+    pub color: Color,
+}
+
+impl Default for Dye {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Dye {
+    pub fn new() -> Self {
+        Self::with_color(Color::hsva(285.00, 0.80, 0.75, 1.00))
+    }
+
+    pub fn with_color(color: Color) -> Self {
+        Self { color }
+    }
+
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        tint_water(grid, self.color, position);
+    }
+}
+
+/// Tints one neighboring water cell per cycle; every tint uses up part of the
+/// dye until it is gone.
+fn tint_water<T: GridAccess>(grid: &mut T, color: Color, position: (usize, usize)) -> bool {
+    for offset in ORTHOGONAL_NEIGHBORS {
+        if let Ok(i) = grid.get_neighbor_index(position, offset)
+            && let Some(Particle {
+                kind: ParticleKind::Water(water),
+                ..
+            }) = &grid.get_cell(i).particle
+            && water.tint != Some(color)
+        {
+            let cycle = grid.cycle();
+            let cell = grid.get_cell_mut(i);
+            if let Some(Particle {
+                kind: ParticleKind::Water(water),
+                ..
+            }) = &mut cell.particle
+            {
+                water.tint = Some(color);
+                cell.cycle = cycle;
+            }
+            let cell = grid.get_cell_mut(grid.to_index(position));
+            if let Some(particle) = &mut cell.particle {
+                particle.health = particle.health.saturating_sub(TINT_COST);
+                cell.cycle = cycle;
+            }
+            grid.activate_window(position);
+            return true;
+        }
+    }
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::color::Color;
+
+    use crate::component::{
+        grid::{Grid, GridAccess},
+        macros::assert_color_srgb_eq,
+        particles::{
+            dye::{Dye, TINT_COST},
+            particle::{Particle, ParticleKind},
+            rock::Rock,
+            water::Water,
+        },
+    };
+    use pretty_assertions::assert_eq;
+
+    fn tint(g: &Grid, position: (usize, usize)) -> Option<Color> {
+        match &g.get_cell(g.to_index(position)).particle {
+            Some(Particle {
+                kind: ParticleKind::Water(water),
+                ..
+            }) => water.tint,
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_dye_next_to_water_tints_the_water_over_cycles() {
+        /*
+         * rrrrr    rrrrr
+         * wwyww -> wwyww
+         * rrrrr    rrrrr
+         */
+        let mut g = Grid::from_rle("5r/2wy2w/5r").unwrap();
+        let dye = Dye::new();
+
+        g.update_grid();
+        let tinted = [(0, 1), (1, 1), (3, 1), (4, 1)]
+            .iter()
+            .filter(|&&p| tint(&g, p) == Some(dye.color))
+            .count();
+        assert_eq!(1, tinted);
+
+        for _ in 0..3 {
+            g.update_grid();
+        }
+        assert_eq!(None, tint(&g, (0, 1)));
+        assert_eq!(Some(dye.color), tint(&g, (1, 1)));
+        assert_eq!(Some(dye.color), tint(&g, (3, 1)));
+        assert_eq!(None, tint(&g, (4, 1)));
+        assert_color_srgb_eq!(
+            dye.color,
+            Particle::from(Water::new().with_tint(dye.color)).color(),
+            0.1
+        );
+    }
+
+    #[test]
+    fn test_dye_is_used_up_by_tinting() {
+        /*
+         * rrr    rrr
+         * wyr -> ?-r
+         * rrr    rrr
+         */
+        let mut g = Grid::new(3, 3);
+        g.spawn_many(
+            (0..3)
+                .flat_map(|x| [(x, 0), (x, 2)])
+                .map(|p| (p, Particle::from(Rock::new()))),
+        );
+        g.spawn_particle((2, 1), Particle::from(Rock::new()));
+        g.spawn_particle((0, 1), Particle::from(Water::new()));
+        g.spawn_particle((1, 1), Particle::from(Dye::new()).with_health(TINT_COST));
+
+        g.update_grid();
+        assert_eq!(Some(Dye::new().color), tint(&g, (0, 1)));
+
+        g.update_grid();
+        assert!(!g.to_rle().contains('y'));
+    }
+}
@@ -0,0 +1,117 @@
+use crate::component::grid::GridAccess;
+
+use super::{
+    particle::{ORTHOGONAL_NEIGHBORS, Particle, ParticleKind},
+    wire::WireState,
+};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Fuse {
+    pub lit: bool,
+}
+
+impl Default for Fuse {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Fuse {
+    pub fn new() -> Self {
+        Self { lit: false }
+    }
+
+    pub fn lit() -> Self {
+        Self { lit: true }
+    }
+
+    /// A lit fuse lights its neighboring fuses and burns away. An unlit fuse
+    /// catches from a neighboring spark, or from the cooling wire a spark
+    /// leaves behind when it was updated first.
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        if self.lit {
+            for offset in ORTHOGONAL_NEIGHBORS {
+                if let Ok(index) = grid.get_neighbor_index(position, offset) {
+                    Self::light(grid, index);
+                }
+            }
+            let cycle = grid.cycle();
+            grid.remove_particle(grid.to_index(position));
+            grid.get_cell_mut(grid.to_index(position)).cycle = cycle;
+            grid.activate_window(position);
+        } else if ORTHOGONAL_NEIGHBORS.iter().any(|&offset| {
+            grid.get_neighbor_index(position, offset)
+                .is_ok_and(|i| Self::is_spark(grid.get_cell(i).particle.as_ref()))
+        }) {
+            Self::light(grid, grid.to_index(position));
+            grid.activate_window(position);
+        }
+    }
+
+    fn is_spark(particle: Option<&Particle>) -> bool {
+        matches!(
+            particle,
+            Some(Particle {
+                kind: ParticleKind::Wire(wire),
+                ..
+            }) if wire.state != WireState::Off
+        )
+    }
+
+    fn light<T: GridAccess>(grid: &mut T, index: usize) {
+        let cycle = grid.cycle();
+        let cell = grid.get_cell_mut(index);
+        if let Some(Particle {
+            kind: ParticleKind::Fuse(fuse),
+            ..
+        }) = &mut cell.particle
+            && !fuse.lit
+        {
+            fuse.lit = true;
+            cell.cycle = cycle;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::Grid,
+        particles::{fuse::Fuse, particle::Particle, rock::Rock, wire::Wire},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_lit_fuse_burns_one_cell_per_cycle_to_the_other_end() {
+        /*
+         * Ffff -> -Fff -> --Ff -> ---F -> ----
+         */
+        let mut g = Grid::new(4, 1);
+        g.spawn_particle((0, 0), Particle::from(Fuse::lit()));
+        for x in 1..4 {
+            g.spawn_particle((x, 0), Particle::from(Fuse::new()));
+        }
+
+        for expected in ["-F2f", "2-Ff", "3-F", "4-"] {
+            g.update_grid();
+            assert_eq!(expected, g.to_rle());
+        }
+    }
+
+    #[test]
+    fn test_spark_lights_a_fuse_but_a_rock_does_not() {
+        /*
+         * Ifrf -> i-rf
+         */
+        let mut g = Grid::new(4, 1);
+        g.spawn_particle((0, 0), Particle::from(Wire::powered()));
+        g.spawn_particle((1, 0), Particle::from(Fuse::new()));
+        g.spawn_particle((2, 0), Particle::from(Rock::new()));
+        g.spawn_particle((3, 0), Particle::from(Fuse::new()));
+
+        g.update_grid();
+        g.update_grid();
+
+        assert_eq!("i-rf", g.to_rle());
+    }
+}
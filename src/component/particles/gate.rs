@@ -0,0 +1,118 @@
+use crate::component::grid::GridAccess;
+
+use super::{
+    particle::{ORTHOGONAL_NEIGHBORS, Particle, ParticleKind},
+    wire::WireState,
+};
+
+/// How many cycles a gate stays open after the last pulse on its wire.
+const HOLD_CYCLES: u8 = 8;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Gate {
+    pub open_for: u8,
+}
+
+impl Default for Gate {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Gate {
+    pub fn new() -> Self {
+        Self { open_for: 0 }
+    }
+
+    pub fn open() -> Self {
+        Self {
+            open_for: HOLD_CYCLES,
+        }
+    }
+
+    pub fn is_open(&self) -> bool {
+        self.open_for > 0
+    }
+
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        let powered = ORTHOGONAL_NEIGHBORS.iter().any(|&offset| {
+            grid.get_neighbor_index(position, offset).is_ok_and(|i| {
+                matches!(
+                    &grid.get_cell(i).particle,
+                    Some(Particle {
+                        kind: ParticleKind::Wire(wire),
+                        ..
+                    }) if wire.state != WireState::Off
+                )
+            })
+        });
+        let open_for = match powered {
+            true => HOLD_CYCLES,
+            false => self.open_for.saturating_sub(1),
+        };
+        if open_for == self.open_for {
+            return;
+        }
+        let cycle = grid.cycle();
+        let cell = grid.get_cell_mut(grid.to_index(position));
+        if let Some(Particle {
+            kind: ParticleKind::Gate(gate),
+            ..
+        }) = &mut cell.particle
+        {
+            gate.open_for = open_for;
+            cell.cycle = cycle;
+        }
+        grid.activate_window(position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::Grid,
+        particles::{gate::Gate, particle::Particle, water::Water, wire::Wire},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_sand_is_held_by_a_closed_gate_and_falls_through_once_it_opens() {
+        /*
+         * sr    sr    -r    -r
+         * hi -> hI -> Hi -> hi
+         * --    --    s-    s-
+         */
+        let mut g = Grid::from_rle("sr/hi/2-")
+            .unwrap()
+            .with_rand_vertical_velocity_probability(|_| 0);
+
+        for _ in 0..5 {
+            g.update_grid();
+        }
+        assert_eq!("sr/hi/2-", g.to_rle());
+
+        g.despawn_particle((1, 1));
+        g.spawn_particle((1, 1), Particle::from(Wire::powered()));
+        g.update_grid();
+        assert_eq!("-r/Hi/s-", g.to_rle());
+
+        for _ in 0..10 {
+            g.update_grid();
+        }
+        assert_eq!("-r/hi/s-", g.to_rle());
+    }
+
+    #[test]
+    fn test_liquid_flows_sideways_through_an_open_gate() {
+        /*
+         * wH- -> -Hw
+         */
+        let mut g = Grid::new(3, 1).with_rand_horizontal_velocity_probability(|_| 1);
+        g.spawn_particle((0, 0), Particle::from(Water::new()));
+        g.spawn_particle((1, 0), Particle::from(Gate::open()));
+
+        g.update_grid();
+
+        assert_eq!("-Hw", g.to_rle());
+    }
+}
@@ -0,0 +1,118 @@
+use crate::component::grid::GridAccess;
+
+use super::{
+    particle::{Particle, ParticleKind},
+    sand::Sand,
+};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Generator {
+    pub particle_kind_id: u8,
+}
+
+impl Default for Generator {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Generator {
+    pub fn new() -> Self {
+        Self::emitting(ParticleKind::from(Sand::new()))
+    }
+
+    pub fn emitting(particle_kind: ParticleKind) -> Self {
+        Self {
+            particle_kind_id: particle_kind.id(),
+        }
+    }
+
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        if let Some(particle_kind) = ParticleKind::with_id(self.particle_kind_id)
+            && let Some(i) = grid.is_empty(position, (0, 1))
+        {
+            if !grid.try_emit() {
+                grid.activate_window(position);
+                return;
+            }
+            let cycle = grid.cycle();
+            let particle = Particle::from(particle_kind)
+                .with_velocity(grid.get_particle_initial_velocity())
+                .with_seed(grid.particle_seed());
+            if !grid.place_particle(i, particle) {
+                grid.activate_window(position);
+                return;
+            }
+            grid.get_cell_mut(i).cycle = cycle;
+            grid.get_cell_mut(grid.to_index(position)).cycle = cycle;
+            grid.activate_window(position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::{Cell, Grid, GridAccess},
+        particles::{
+            generator::Generator,
+            particle::{Particle, ParticleKind},
+            rock::Rock,
+            sand::Sand,
+            water::Water,
+        },
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_sand_generator_fills_the_column_below_it() {
+        /*
+         * g -> g -> g -> g
+         * -    s    s    s
+         * -    -    s    s
+         * -    -    -    s
+         */
+        let generator = Particle::from(Generator::emitting(ParticleKind::from(Sand::new())));
+        let mut g = Grid::new(1, 4)
+            .with_rand_seed_with_cycle(|_| 127)
+            .with_rand_vertical_velocity_probability(|_| 0);
+        g.spawn_particle((0, 0), generator.clone());
+
+        for _ in 0..6 {
+            g.update_grid();
+        }
+
+        assert_eq!(
+            vec![
+                Some(generator),
+                Some(Particle::from(Sand::new())),
+                Some(Particle::from(Sand::new())),
+                Some(Particle::from(Sand::new())),
+            ],
+            g.get_cells()
+                .iter()
+                .map(|c| c.particle.clone().map(|p| p.with_velocity((0, i16::MAX))))
+                .collect::<Vec<_>>()
+        );
+    }
+
+    #[test]
+    fn test_generator_emits_regardless_of_its_neighbors() {
+        /*
+         * rgr -> rgr
+         * ---    -w-
+         */
+        let generator = Particle::from(Generator::emitting(ParticleKind::from(Water::new())));
+        let mut g = Grid::new(3, 2).with_rand_seed_with_cycle(|_| 127);
+        g.spawn_particle((0, 0), Particle::from(Rock::new()));
+        g.spawn_particle((1, 0), generator.clone());
+        g.spawn_particle((2, 0), Particle::from(Rock::new()));
+
+        g.update_grid();
+
+        assert_eq!(
+            Cell::new(Particle::from(Water::new())).with_cycle(1),
+            *g.get_cell(g.to_index((1, 1)))
+        );
+    }
+}
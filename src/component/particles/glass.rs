@@ -0,0 +1,15 @@
+/// Nothing fuses sand into glass yet; that waits on a heat source.
+#[derive(Clone, PartialEq, Debug)]
+pub struct Glass;
+
+impl Default for Glass {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Glass {
+    pub fn new() -> Self {
+        Self
+    }
+}
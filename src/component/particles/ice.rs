@@ -0,0 +1,14 @@
+#[derive(Clone, PartialEq, Debug)]
+pub struct Ice;
+
+impl Default for Ice {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Ice {
+    pub fn new() -> Self {
+        Self
+    }
+}
@@ -0,0 +1,127 @@
+use crate::component::grid::GridAccess;
+
+use super::particle::{Particle, ParticleKind};
+
+const MAGNET_RANGE: i32 = 4;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Magnet;
+
+impl Default for Magnet {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Magnet {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Pulls every metal in range one cell toward the magnet, or holds it in
+    /// place once it touches the magnet. Pulled and held metal is stamped
+    /// with the next cycle so its own update, and gravity with it, is skipped
+    /// until the magnet runs again. Metal whose step is blocked is left to
+    /// fall.
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        let next_cycle = grid.cycle().wrapping_add(1);
+        for (dx, dy) in (-MAGNET_RANGE..=MAGNET_RANGE)
+            .flat_map(|dy| (-MAGNET_RANGE..=MAGNET_RANGE).map(move |dx| (dx, dy)))
+        {
+            let Ok(metal) = grid.get_neighbor_position(position, (dx, dy)) else {
+                continue;
+            };
+            let index = grid.to_index(metal);
+            let c = grid.get_cell(index);
+            let pullable = c.cycle != next_cycle
+                && matches!(
+                    c.particle,
+                    Some(Particle {
+                        kind: ParticleKind::Metal(..),
+                        ..
+                    })
+                );
+            if !pullable {
+                continue;
+            }
+            if dx.abs() <= 1 && dy.abs() <= 1 {
+                grid.get_cell_mut(index).cycle = next_cycle;
+            } else if let Some(next) = grid.is_empty(metal, (-dx.signum(), -dy.signum())) {
+                grid.swap_particles(index, next);
+                grid.get_cell_mut(next).cycle = next_cycle;
+                grid.activate_window(grid.to_position(next));
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::Grid,
+        particles::{magnet::Magnet, metal::Metal, particle::Particle, rock::Rock, sand::Sand},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_metal_falling_past_a_magnet_is_drawn_sideways_toward_it() {
+        /*
+         * ---m -> ---- | ----
+         * ----    ---- | ----
+         * M---    Mm-- | ----
+         * ----    ---- | ----
+         * ----    ---- | ---m
+         */
+        let run = |magnet: bool| {
+            let mut g = Grid::new(4, 5).with_rand_vertical_velocity_probability(|_| 0);
+            g.spawn_particle((3, 0), Particle::from(Metal::new()));
+            if magnet {
+                g.spawn_particle((0, 2), Particle::from(Magnet::new()));
+            }
+            for _ in 0..10 {
+                g.update_grid();
+            }
+            g.to_rle()
+        };
+
+        assert_eq!("4-/4-/Mm2-/4-/4-", run(true));
+        assert_eq!("4-/4-/4-/4-/3-m", run(false));
+    }
+
+    #[test]
+    fn test_magnet_does_not_attract_other_particles() {
+        /*
+         * --s -> ---
+         * M--    M--
+         * ---    --s
+         */
+        let mut g = Grid::new(3, 3).with_rand_vertical_velocity_probability(|_| 0);
+        g.spawn_particle((2, 0), Particle::from(Sand::new()));
+        g.spawn_particle((0, 1), Particle::from(Magnet::new()));
+
+        for _ in 0..5 {
+            g.update_grid();
+        }
+
+        assert_eq!("3-/M2-/2-s", g.to_rle());
+    }
+
+    #[test]
+    fn test_metal_blocked_from_a_magnet_still_falls() {
+        /*
+         * --m -> ---
+         * Mr-    Mr-
+         * ---    --m
+         */
+        let mut g = Grid::new(3, 3).with_rand_vertical_velocity_probability(|_| 0);
+        g.spawn_particle((2, 0), Particle::from(Metal::new()));
+        g.spawn_particle((0, 1), Particle::from(Magnet::new()));
+        g.spawn_particle((1, 1), Particle::from(Rock::new()));
+
+        for _ in 0..5 {
+            g.update_grid();
+        }
+
+        assert_eq!("3-/Mr-/2-m", g.to_rle());
+    }
+}
@@ -0,0 +1,80 @@
+#[derive(Clone, PartialEq, Debug)]
+pub struct Membrane;
+
+impl Default for Membrane {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Membrane {
+    pub fn new() -> Self {
+        Self
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::Grid,
+        particles::{membrane::Membrane, particle::Particle, sand::Sand, water::Water},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_water_passes_through_a_membrane_while_sand_piles_on_top() {
+        /*
+         * sw -> --
+         * --    s-
+         * nn    nn
+         * --    w- | -w
+         */
+        let mut g = Grid::new(2, 4);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        g.spawn_particle((1, 0), Particle::from(Water::new()));
+        for x in 0..2 {
+            g.spawn_particle((x, 2), Particle::from(Membrane::new()));
+        }
+
+        for _ in 0..5 {
+            g.update_grid();
+        }
+
+        let rle = g.to_rle();
+        assert!(
+            ["2-/s-/2n/w-", "2-/s-/2n/-w"].contains(&rle.as_str()),
+            "{rle}"
+        );
+    }
+
+    #[test]
+    fn test_water_flows_sideways_through_a_membrane() {
+        /*
+         * wn- -> -nw
+         */
+        let mut g = Grid::new(3, 1);
+        g.spawn_particle((0, 0), Particle::from(Water::new()));
+        g.spawn_particle((1, 0), Particle::from(Membrane::new()));
+
+        g.update_grid();
+
+        assert_eq!("-nw", g.to_rle());
+    }
+
+    #[test]
+    fn test_water_stays_on_a_membrane_with_nothing_empty_below() {
+        /*
+         * w -> w
+         * n    n
+         * s    s
+         */
+        let mut g = Grid::new(1, 3);
+        g.spawn_particle((0, 0), Particle::from(Water::new()));
+        g.spawn_particle((0, 1), Particle::from(Membrane::new()));
+        g.spawn_particle((0, 2), Particle::from(Sand::new()));
+
+        g.update_grid();
+
+        assert_eq!("w/n/s", g.to_rle());
+    }
+}
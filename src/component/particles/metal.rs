@@ -0,0 +1,14 @@
+#[derive(Clone, PartialEq, Debug)]
+pub struct Metal;
+
+impl Default for Metal {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Metal {
+    pub fn new() -> Self {
+        Self
+    }
+}
@@ -1,13 +1,47 @@
 use core::fmt;
 
 use bevy::{
-    color::Hsva,
+    color::{ColorToPacked, Hsva},
     prelude::{Color, Saturation},
 };
 
-use crate::component::grid::GridAccess;
+use crate::component::grid::{GridAccess, SimEvent, SpreadMode};
+
+use super::{
+    acid::{Acid, MAX_ACIDITY},
+    bedrock::Bedrock,
+    cryo::Cryo,
+    crystal::Crystal,
+    drain::Drain,
+    dust::Dust,
+    dye::Dye,
+    fuse::Fuse,
+    gate::Gate,
+    generator::Generator,
+    glass::Glass,
+    ice::Ice,
+    magnet::Magnet,
+    membrane::Membrane,
+    metal::Metal,
+    registry::Custom,
+    reservoir::Reservoir,
+    rock::Rock,
+    rope::Rope,
+    salt::Salt,
+    sand::Sand,
+    slime::Slime,
+    steam::Steam,
+    sticky::Sticky,
+    tap::Tap,
+    teleporter::Teleporter,
+    virus::Virus,
+    water::Water,
+    wire::{Wire, WireState},
+};
 
-use super::{acid::Acid, drain::Drain, rock::Rock, salt::Salt, sand::Sand, tap::Tap, water::Water};
+// Reactions with orthogonal neighbors scan them in this order and the first
+// match wins: top, left, right, bottom.
+pub const ORTHOGONAL_NEIGHBORS: [(i32, i32); 4] = [(0, -1), (-1, 0), (1, 0), (0, 1)];
 
 #[derive(Clone, PartialEq, Debug)]
 pub enum ParticleKind {
@@ -18,6 +52,28 @@ pub enum ParticleKind {
     Drain(Drain),
     Tap(Tap),
     Acid(Acid),
+    Wire(Wire),
+    Generator(Generator),
+    Slime(Slime),
+    Virus(Virus),
+    Reservoir(Reservoir),
+    Teleporter(Teleporter),
+    Crystal(Crystal),
+    Dust(Dust),
+    Bedrock(Bedrock),
+    Cryo(Cryo),
+    Ice(Ice),
+    Metal(Metal),
+    Magnet(Magnet),
+    Membrane(Membrane),
+    Rope(Rope),
+    Dye(Dye),
+    Fuse(Fuse),
+    Gate(Gate),
+    Glass(Glass),
+    Sticky(Sticky),
+    Steam(Steam),
+    Custom(Custom),
 }
 
 impl ParticleKind {
@@ -30,6 +86,28 @@ impl ParticleKind {
             ParticleKind::Drain(..) => 4,
             ParticleKind::Tap(..) => 5,
             ParticleKind::Acid(..) => 6,
+            ParticleKind::Wire(..) => 7,
+            ParticleKind::Generator(..) => 8,
+            ParticleKind::Slime(..) => 9,
+            ParticleKind::Virus(..) => 10,
+            ParticleKind::Reservoir(..) => 11,
+            ParticleKind::Teleporter(..) => 12,
+            ParticleKind::Crystal(..) => 13,
+            ParticleKind::Dust(..) => 14,
+            ParticleKind::Bedrock(..) => 15,
+            ParticleKind::Cryo(..) => 16,
+            ParticleKind::Ice(..) => 17,
+            ParticleKind::Metal(..) => 18,
+            ParticleKind::Magnet(..) => 19,
+            ParticleKind::Membrane(..) => 20,
+            ParticleKind::Rope(..) => 21,
+            ParticleKind::Dye(..) => 22,
+            ParticleKind::Fuse(..) => 23,
+            ParticleKind::Gate(..) => 24,
+            ParticleKind::Glass(..) => 25,
+            ParticleKind::Sticky(..) => 26,
+            ParticleKind::Steam(..) => 27,
+            ParticleKind::Custom(custom) => custom.id,
         }
     }
 
@@ -42,7 +120,28 @@ impl ParticleKind {
             4 => Some(ParticleKind::from(Drain::new())),
             5 => Some(ParticleKind::from(Tap::new())),
             6 => Some(ParticleKind::from(Acid::new())),
-            _ => None,
+            7 => Some(ParticleKind::from(Wire::new())),
+            8 => Some(ParticleKind::from(Generator::new())),
+            9 => Some(ParticleKind::from(Slime::new())),
+            10 => Some(ParticleKind::from(Virus::new())),
+            11 => Some(ParticleKind::from(Reservoir::new())),
+            12 => Some(ParticleKind::from(Teleporter::new())),
+            13 => Some(ParticleKind::from(Crystal::new())),
+            14 => Some(ParticleKind::from(Dust::new())),
+            15 => Some(ParticleKind::from(Bedrock::new())),
+            16 => Some(ParticleKind::from(Cryo::new())),
+            17 => Some(ParticleKind::from(Ice::new())),
+            18 => Some(ParticleKind::from(Metal::new())),
+            19 => Some(ParticleKind::from(Magnet::new())),
+            20 => Some(ParticleKind::from(Membrane::new())),
+            21 => Some(ParticleKind::from(Rope::new())),
+            22 => Some(ParticleKind::from(Dye::new())),
+            23 => Some(ParticleKind::from(Fuse::new())),
+            24 => Some(ParticleKind::from(Gate::new())),
+            25 => Some(ParticleKind::from(Glass::new())),
+            26 => Some(ParticleKind::from(Sticky::new())),
+            27 => Some(ParticleKind::from(Steam::new())),
+            id => Custom::with_id(id).map(ParticleKind::from),
         }
     }
 }
@@ -89,15 +188,170 @@ impl From<Acid> for ParticleKind {
     }
 }
 
+impl From<Wire> for ParticleKind {
+    fn from(wire: Wire) -> Self {
+        Self::Wire(wire)
+    }
+}
+
+impl From<Generator> for ParticleKind {
+    fn from(generator: Generator) -> Self {
+        Self::Generator(generator)
+    }
+}
+
+impl From<Slime> for ParticleKind {
+    fn from(slime: Slime) -> Self {
+        Self::Slime(slime)
+    }
+}
+
+impl From<Virus> for ParticleKind {
+    fn from(virus: Virus) -> Self {
+        Self::Virus(virus)
+    }
+}
+
+impl From<Reservoir> for ParticleKind {
+    fn from(reservoir: Reservoir) -> Self {
+        Self::Reservoir(reservoir)
+    }
+}
+
+impl From<Teleporter> for ParticleKind {
+    fn from(teleporter: Teleporter) -> Self {
+        Self::Teleporter(teleporter)
+    }
+}
+
+impl From<Crystal> for ParticleKind {
+    fn from(crystal: Crystal) -> Self {
+        Self::Crystal(crystal)
+    }
+}
+
+impl From<Dust> for ParticleKind {
+    fn from(dust: Dust) -> Self {
+        Self::Dust(dust)
+    }
+}
+
+impl From<Bedrock> for ParticleKind {
+    fn from(bedrock: Bedrock) -> Self {
+        Self::Bedrock(bedrock)
+    }
+}
+
+impl From<Cryo> for ParticleKind {
+    fn from(cryo: Cryo) -> Self {
+        Self::Cryo(cryo)
+    }
+}
+
+impl From<Ice> for ParticleKind {
+    fn from(ice: Ice) -> Self {
+        Self::Ice(ice)
+    }
+}
+
+impl From<Metal> for ParticleKind {
+    fn from(metal: Metal) -> Self {
+        Self::Metal(metal)
+    }
+}
+
+impl From<Magnet> for ParticleKind {
+    fn from(magnet: Magnet) -> Self {
+        Self::Magnet(magnet)
+    }
+}
+
+impl From<Membrane> for ParticleKind {
+    fn from(membrane: Membrane) -> Self {
+        Self::Membrane(membrane)
+    }
+}
+
+impl From<Rope> for ParticleKind {
+    fn from(rope: Rope) -> Self {
+        Self::Rope(rope)
+    }
+}
+
+impl From<Dye> for ParticleKind {
+    fn from(dye: Dye) -> Self {
+        Self::Dye(dye)
+    }
+}
+
+impl From<Fuse> for ParticleKind {
+    fn from(fuse: Fuse) -> Self {
+        Self::Fuse(fuse)
+    }
+}
+
+impl From<Gate> for ParticleKind {
+    fn from(gate: Gate) -> Self {
+        Self::Gate(gate)
+    }
+}
+
+impl From<Glass> for ParticleKind {
+    fn from(glass: Glass) -> Self {
+        Self::Glass(glass)
+    }
+}
+
+impl From<Sticky> for ParticleKind {
+    fn from(sticky: Sticky) -> Self {
+        Self::Sticky(sticky)
+    }
+}
+
+impl From<Steam> for ParticleKind {
+    fn from(steam: Steam) -> Self {
+        Self::Steam(steam)
+    }
+}
+
+impl From<Custom> for ParticleKind {
+    fn from(custom: Custom) -> Self {
+        Self::Custom(custom)
+    }
+}
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct Particle {
     pub weight: u8,
     viscosity: u8,
+    repose: u8,
+    buoyancy: u8,
+    drift: u8,
+    terminal_velocity: i16,
+    indestructible: bool,
+    permeable: bool,
     pub cloneable: bool,
     pub kind: ParticleKind,
     pub seed: u8,
     pub velocity: (i16, i16),
     pub health: u8,
+    /// Assigned by `Grid::spawn_particle`, in spawn order starting at 1, and
+    /// carried along as the particle moves; 0 means it was never spawned.
+    #[cfg(feature = "particle_ids")]
+    pub id: ParticleId,
+}
+
+/// Left out of particle equality, so two grains in the same state still
+/// compare equal whichever one was spawned first.
+#[cfg(feature = "particle_ids")]
+#[derive(Clone, Copy, Debug, Default)]
+pub struct ParticleId(pub u64);
+
+#[cfg(feature = "particle_ids")]
+impl PartialEq for ParticleId {
+    fn eq(&self, _: &Self) -> bool {
+        true
+    }
 }
 
 impl Particle {
@@ -105,11 +359,19 @@ impl Particle {
         Self {
             weight: u8::MIN,
             viscosity: u8::MAX,
+            repose: u8::MIN,
+            buoyancy: u8::MIN,
+            drift: u8::MIN,
+            terminal_velocity: i16::MAX,
+            indestructible: false,
+            permeable: false,
             cloneable: true,
             kind,
             seed: 127,
             velocity: (0, i16::MAX),
             health: u8::MAX,
+            #[cfg(feature = "particle_ids")]
+            id: ParticleId(0),
         }
     }
 
@@ -127,6 +389,36 @@ impl Particle {
         self
     }
 
+    pub fn with_repose(mut self, repose: u8) -> Self {
+        self.repose = repose;
+        self
+    }
+
+    pub fn with_buoyancy(mut self, buoyancy: u8) -> Self {
+        self.buoyancy = buoyancy;
+        self
+    }
+
+    pub fn with_drift(mut self, drift: u8) -> Self {
+        self.drift = drift;
+        self
+    }
+
+    pub fn with_terminal_velocity(mut self, terminal_velocity: i16) -> Self {
+        self.terminal_velocity = terminal_velocity;
+        self
+    }
+
+    pub fn with_indestructible(mut self, indestructible: bool) -> Self {
+        self.indestructible = indestructible;
+        self
+    }
+
+    pub fn with_permeable(mut self, permeable: bool) -> Self {
+        self.permeable = permeable;
+        self
+    }
+
     pub fn with_seed(mut self, seed: u8) -> Self {
         self.seed = seed;
         self
@@ -142,17 +434,102 @@ impl Particle {
         self
     }
 
+    /// `u8::MAX` marks a particle that never flows sideways.
+    pub fn viscosity(&self) -> u8 {
+        self.viscosity
+    }
+
+    pub fn is_solid(&self) -> bool {
+        self.weight == u8::MIN && self.viscosity == u8::MAX
+    }
+
+    pub fn is_powder(&self) -> bool {
+        self.weight != u8::MIN && self.viscosity == u8::MAX && !self.is_gas()
+    }
+
+    pub fn is_liquid(&self) -> bool {
+        self.viscosity != u8::MAX
+    }
+
+    pub fn is_gas(&self) -> bool {
+        self.buoyancy != u8::MIN
+    }
+
+    pub fn is_indestructible(&self) -> bool {
+        self.indestructible
+    }
+
+    pub fn is_permeable(&self) -> bool {
+        self.permeable
+    }
+
+    /// Whether a mover can pass through this particle: liquids seep through
+    /// permeable ones, and anything goes through an open gate.
+    fn is_passable(&self, liquid: bool) -> bool {
+        (self.permeable && liquid)
+            || matches!(&self.kind, ParticleKind::Gate(gate) if gate.is_open())
+    }
+
+    pub fn color_bytes(&self) -> [u8; 3] {
+        self.color().to_srgba().to_u8_array_no_alpha()
+    }
+
+    pub fn color_rgba(&self) -> [u8; 4] {
+        let [r, g, b] = self.color_bytes();
+        [r, g, b, u8::MAX]
+    }
+
     pub fn color(&self) -> Color {
         let color: Hsva = match &self.kind {
             ParticleKind::Sand(..) => Color::hsva(43.20, 0.34, 0.76, 1.00),
-            ParticleKind::Water(water) => Color::hsva(201.60, 1.0, 0.80, 1.00)
-                .with_saturation(1.0 - (3 - water.solvant_capacity) as f32 * 0.1),
+            ParticleKind::Water(water) => match water.tint {
+                Some(tint) => tint,
+                None => Color::hsva(201.60, 1.0, 0.80, 1.00)
+                    .with_saturation(1.0 - (3 - water.solvant_capacity) as f32 * 0.1),
+            },
             ParticleKind::Salt(..) => Color::hsva(0.00, 0.00, 1.00, 1.00),
 
             ParticleKind::Rock(..) => Color::hsva(28.0, 0.25, 0.30, 1.00),
             ParticleKind::Drain(..) => Color::hsva(0.0, 0.0, 0.10, 1.00),
             ParticleKind::Tap(..) => Color::hsva(190.00, 0.40, 0.75, 1.00),
-            ParticleKind::Acid(..) => Color::hsva(126.00, 1.0, 0.9, 1.00),
+            ParticleKind::Acid(acid) => Color::hsva(126.00, 1.0, 0.9, 1.00)
+                .with_saturation(1.0 - (MAX_ACIDITY - acid.acidity()) as f32 * 0.05),
+            ParticleKind::Wire(wire) => match wire.state {
+                WireState::Off => Color::hsva(20.00, 0.70, 0.50, 1.00),
+                WireState::On => Color::hsva(55.00, 1.00, 1.00, 1.00),
+                WireState::Cooling => Color::hsva(35.00, 0.80, 0.75, 1.00),
+            },
+            ParticleKind::Generator(..) => Color::hsva(280.00, 0.40, 0.60, 1.00),
+            ParticleKind::Slime(..) => Color::hsva(95.00, 0.75, 0.65, 1.00),
+            ParticleKind::Virus(..) => Color::hsva(320.00, 0.80, 0.70, 1.00),
+            ParticleKind::Reservoir(..) => Color::hsva(210.00, 0.30, 0.45, 1.00),
+            ParticleKind::Teleporter(..) => Color::hsva(170.00, 0.60, 0.70, 1.00),
+            ParticleKind::Crystal(..) => Color::hsva(190.00, 0.25, 0.95, 1.00),
+            ParticleKind::Dust(..) => Color::hsva(35.00, 0.15, 0.65, 1.00),
+            ParticleKind::Bedrock(..) => Color::hsva(240.00, 0.10, 0.22, 1.00),
+            ParticleKind::Cryo(..) => Color::hsva(185.00, 0.70, 0.90, 1.00),
+            ParticleKind::Ice(..) => Color::hsva(200.00, 0.20, 1.00, 1.00),
+            ParticleKind::Metal(..) => Color::hsva(210.00, 0.08, 0.60, 1.00),
+            ParticleKind::Magnet(..) => Color::hsva(0.00, 0.75, 0.55, 1.00),
+            ParticleKind::Membrane(..) => Color::hsva(60.00, 0.35, 0.80, 1.00),
+            ParticleKind::Rope(..) => Color::hsva(30.00, 0.55, 0.55, 1.00),
+            ParticleKind::Dye(dye) => dye.color,
+            ParticleKind::Fuse(fuse) => match fuse.lit {
+                true => Color::hsva(15.00, 1.00, 1.00, 1.00),
+                false => Color::hsva(25.00, 0.45, 0.35, 1.00),
+            },
+            ParticleKind::Gate(gate) => match gate.is_open() {
+                true => Color::hsva(140.00, 0.30, 0.45, 1.00),
+                false => Color::hsva(220.00, 0.15, 0.40, 1.00),
+            },
+            // a shade off the background so glass reads as see-through
+            ParticleKind::Glass(..) => Color::hsva(200.00, 0.12, 0.96, 1.00),
+            ParticleKind::Sticky(..) => Color::hsva(320.00, 0.45, 0.85, 1.00),
+            ParticleKind::Steam(..) => Color::hsva(0.00, 0.00, 0.92, 1.00),
+            ParticleKind::Custom(custom) => match custom.descriptor() {
+                Some(d) => d.color,
+                None => Color::BLACK,
+            },
         }
         .into();
         color
@@ -171,6 +548,28 @@ impl From<ParticleKind> for Particle {
             ParticleKind::Drain(drain) => Self::from(drain),
             ParticleKind::Tap(tap) => Self::from(tap),
             ParticleKind::Acid(acid) => Self::from(acid),
+            ParticleKind::Wire(wire) => Self::from(wire),
+            ParticleKind::Generator(generator) => Self::from(generator),
+            ParticleKind::Slime(slime) => Self::from(slime),
+            ParticleKind::Virus(virus) => Self::from(virus),
+            ParticleKind::Reservoir(reservoir) => Self::from(reservoir),
+            ParticleKind::Teleporter(teleporter) => Self::from(teleporter),
+            ParticleKind::Crystal(crystal) => Self::from(crystal),
+            ParticleKind::Dust(dust) => Self::from(dust),
+            ParticleKind::Bedrock(bedrock) => Self::from(bedrock),
+            ParticleKind::Cryo(cryo) => Self::from(cryo),
+            ParticleKind::Ice(ice) => Self::from(ice),
+            ParticleKind::Metal(metal) => Self::from(metal),
+            ParticleKind::Magnet(magnet) => Self::from(magnet),
+            ParticleKind::Membrane(membrane) => Self::from(membrane),
+            ParticleKind::Rope(rope) => Self::from(rope),
+            ParticleKind::Dye(dye) => Self::from(dye),
+            ParticleKind::Fuse(fuse) => Self::from(fuse),
+            ParticleKind::Gate(gate) => Self::from(gate),
+            ParticleKind::Glass(glass) => Self::from(glass),
+            ParticleKind::Sticky(sticky) => Self::from(sticky),
+            ParticleKind::Steam(steam) => Self::from(steam),
+            ParticleKind::Custom(custom) => Self::from(custom),
         }
     }
 }
@@ -223,6 +622,151 @@ impl From<Acid> for Particle {
     }
 }
 
+impl From<Wire> for Particle {
+    fn from(wire: Wire) -> Self {
+        Self::new(ParticleKind::Wire(wire))
+    }
+}
+
+impl From<Generator> for Particle {
+    fn from(generator: Generator) -> Self {
+        Self::new(ParticleKind::Generator(generator)).with_cloneable(false)
+    }
+}
+
+impl From<Virus> for Particle {
+    fn from(virus: Virus) -> Self {
+        Self::new(ParticleKind::Virus(virus)).with_cloneable(false)
+    }
+}
+
+impl From<Reservoir> for Particle {
+    fn from(reservoir: Reservoir) -> Self {
+        Self::new(ParticleKind::Reservoir(reservoir)).with_cloneable(false)
+    }
+}
+
+impl From<Teleporter> for Particle {
+    fn from(teleporter: Teleporter) -> Self {
+        Self::new(ParticleKind::Teleporter(teleporter)).with_cloneable(false)
+    }
+}
+
+impl From<Crystal> for Particle {
+    fn from(crystal: Crystal) -> Self {
+        Self::new(ParticleKind::Crystal(crystal))
+    }
+}
+
+impl From<Dust> for Particle {
+    fn from(dust: Dust) -> Self {
+        Self::new(ParticleKind::Dust(dust))
+            .with_weight(1)
+            .with_drift(160)
+            .with_terminal_velocity(i16::MAX / 8)
+    }
+}
+
+impl From<Bedrock> for Particle {
+    fn from(bedrock: Bedrock) -> Self {
+        Self::new(ParticleKind::Bedrock(bedrock)).with_indestructible(true)
+    }
+}
+
+impl From<Cryo> for Particle {
+    fn from(cryo: Cryo) -> Self {
+        Self::new(ParticleKind::Cryo(cryo)).with_cloneable(false)
+    }
+}
+
+impl From<Ice> for Particle {
+    fn from(ice: Ice) -> Self {
+        Self::new(ParticleKind::Ice(ice))
+    }
+}
+
+impl From<Metal> for Particle {
+    fn from(metal: Metal) -> Self {
+        Self::new(ParticleKind::Metal(metal)).with_weight(8)
+    }
+}
+
+impl From<Magnet> for Particle {
+    fn from(magnet: Magnet) -> Self {
+        Self::new(ParticleKind::Magnet(magnet))
+    }
+}
+
+impl From<Membrane> for Particle {
+    fn from(membrane: Membrane) -> Self {
+        Self::new(ParticleKind::Membrane(membrane)).with_permeable(true)
+    }
+}
+
+impl From<Rope> for Particle {
+    fn from(rope: Rope) -> Self {
+        Self::new(ParticleKind::Rope(rope)).with_weight(2)
+    }
+}
+
+impl From<Dye> for Particle {
+    fn from(dye: Dye) -> Self {
+        Self::new(ParticleKind::Dye(dye)).with_weight(5)
+    }
+}
+
+impl From<Fuse> for Particle {
+    fn from(fuse: Fuse) -> Self {
+        Self::new(ParticleKind::Fuse(fuse))
+    }
+}
+
+impl From<Gate> for Particle {
+    fn from(gate: Gate) -> Self {
+        Self::new(ParticleKind::Gate(gate))
+    }
+}
+
+impl From<Glass> for Particle {
+    fn from(glass: Glass) -> Self {
+        Self::new(ParticleKind::Glass(glass))
+    }
+}
+
+impl From<Sticky> for Particle {
+    fn from(sticky: Sticky) -> Self {
+        Self::new(ParticleKind::Sticky(sticky)).with_weight(3)
+    }
+}
+
+impl From<Steam> for Particle {
+    fn from(steam: Steam) -> Self {
+        Self::new(ParticleKind::Steam(steam))
+            .with_weight(1)
+            .with_buoyancy(200)
+    }
+}
+
+impl From<Custom> for Particle {
+    fn from(custom: Custom) -> Self {
+        let (weight, viscosity) = match custom.descriptor() {
+            Some(d) => (d.weight, d.viscosity),
+            None => (u8::MIN, u8::MAX),
+        };
+        Self::new(ParticleKind::Custom(custom))
+            .with_weight(weight)
+            .with_viscosity(viscosity)
+    }
+}
+
+impl From<Slime> for Particle {
+    fn from(slime: Slime) -> Self {
+        Self::new(ParticleKind::Slime(slime))
+            .with_weight(3)
+            .with_viscosity(10)
+    }
+}
+
 impl Particle {
     // TODO: research on neighborhood partitioning and how we can apply it to here.
     // if it helps with access performance or not.
@@ -230,12 +774,32 @@ impl Particle {
     pub fn update<T: GridAccess>(grid: &mut T, position: (usize, usize)) {
         Self::kill(grid, position); // TODO; test this and return bool
 
-        if Self::gravity(grid, position) {
+        let rope = grid
+            .get_cell(grid.to_index(position))
+            .particle
+            .as_ref()
+            .is_some_and(|p| matches!(p.kind, ParticleKind::Rope(..)));
+        if rope && Rope::is_anchored(grid, position) {
             return;
         }
 
-        if Self::flow(grid, position) {
-            return;
+        let buoyant = grid
+            .get_cell(grid.to_index(position))
+            .particle
+            .as_ref()
+            .is_some_and(Particle::is_gas);
+        if buoyant {
+            if Self::rise(grid, position) {
+                return;
+            }
+        } else {
+            if Self::gravity(grid, position) {
+                return;
+            }
+
+            if Self::flow(grid, position) {
+                return;
+            }
         }
 
         let c = grid.get_cell(grid.to_index(position));
@@ -248,6 +812,28 @@ impl Particle {
                 ParticleKind::Drain(drain) => drain.update(grid, position),
                 ParticleKind::Tap(tap) => tap.update(grid, position),
                 ParticleKind::Acid(acid) => acid.update(grid, position),
+                ParticleKind::Wire(wire) => wire.update(grid, position),
+                ParticleKind::Generator(generator) => generator.update(grid, position),
+                ParticleKind::Slime(..) => (),
+                ParticleKind::Virus(virus) => virus.update(grid, position),
+                ParticleKind::Reservoir(reservoir) => reservoir.update(grid, position),
+                ParticleKind::Teleporter(teleporter) => teleporter.update(grid, position),
+                ParticleKind::Crystal(crystal) => crystal.update(grid, position),
+                ParticleKind::Dust(..) => (),
+                ParticleKind::Bedrock(..) => (),
+                ParticleKind::Cryo(cryo) => cryo.update(grid, position),
+                ParticleKind::Ice(..) => (),
+                ParticleKind::Metal(..) => (),
+                ParticleKind::Magnet(magnet) => magnet.update(grid, position),
+                ParticleKind::Membrane(..) => (),
+                ParticleKind::Rope(..) => (),
+                ParticleKind::Dye(dye) => dye.update(grid, position),
+                ParticleKind::Fuse(fuse) => fuse.update(grid, position),
+                ParticleKind::Gate(gate) => gate.update(grid, position),
+                ParticleKind::Glass(..) => (),
+                ParticleKind::Sticky(..) => (),
+                ParticleKind::Steam(..) => (),
+                ParticleKind::Custom(custom) => custom.update(grid, position),
             };
         }
     }
@@ -262,6 +848,9 @@ impl Particle {
             Ok(i) => {
                 let c = grid.get_cell(i);
                 match &c.particle {
+                    Some(p) if p.is_passable(true) => {
+                        Self::seep(grid, position, (offset_sign, 0), true)
+                    }
                     Some(p) => {
                         if p.viscosity < viscosity {
                             Some(i)
@@ -287,16 +876,25 @@ impl Particle {
 
     fn flow<T: GridAccess>(grid: &mut T, position: (usize, usize)) -> bool {
         let c = grid.get_cell(grid.to_index(position));
-        let (viscosity, velocity_x) = if let Some(p) = &c.particle {
-            (p.viscosity, p.velocity.0)
-        } else {
-            return false;
+        let (viscosity, velocity_x, kind) = match &c.particle {
+            Some(p) if p.is_liquid() => (p.viscosity, p.velocity.0, &p.kind),
+            _ => return false,
         };
 
-        if viscosity == u8::MAX {
+        if let ParticleKind::Slime(..) = kind
+            && Slime::clings(grid, position)
+        {
             return false;
         }
 
+        let cohesion = grid.cohesion();
+        if cohesion > 0. {
+            let liquid_neighbors = grid.count_neighbors(position, Particle::is_liquid);
+            if grid.cohesion_probability() < cohesion * (8 - liquid_neighbors) as f32 / 8. {
+                return false;
+            }
+        }
+
         let index_left = Self::check_flow_neighbor(grid, position, -1, viscosity);
         let index_right = Self::check_flow_neighbor(grid, position, 1, viscosity);
 
@@ -323,15 +921,121 @@ impl Particle {
             };
             grid.swap_particles(grid.to_index(position), index);
             true
+        } else {
+            Self::jitter_surface(grid, position)
+        }
+    }
+
+    fn jitter_surface<T: GridAccess>(grid: &mut T, position: (usize, usize)) -> bool {
+        let jitter = grid.surface_jitter();
+        if jitter <= 0.
+            || jitter <= grid.jitter_probability()
+            || grid.is_empty(position, (0, -1)).is_none()
+        {
+            return false;
+        }
+        let offset = match grid.horizontal_velocity_probability() {
+            i16::MIN..=0 => -1,
+            1..=i16::MAX => 1,
+        };
+        match grid.get_neighbor_index(position, (offset, 0)) {
+            Ok(i)
+                if grid
+                    .get_cell(i)
+                    .particle
+                    .as_ref()
+                    .is_some_and(|p| p.is_liquid()) =>
+            {
+                grid.swap_particles(grid.to_index(position), i);
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Finds the empty cell right past a run of passable cells in the
+    /// `offset` direction, which the particle at `position` can seep into.
+    fn seep<T: GridAccess>(
+        grid: &T,
+        position: (usize, usize),
+        (x, y): (i32, i32),
+        liquid: bool,
+    ) -> Option<usize> {
+        let mut distance = 1;
+        loop {
+            let index = grid
+                .get_neighbor_index(position, (x * distance, y * distance))
+                .ok()?;
+            match &grid.get_cell(index).particle {
+                Some(p) if p.is_passable(liquid) => distance += 1,
+                Some(_) => return None,
+                None => return (distance > 1).then_some(index),
+            }
+        }
+    }
+
+    fn rise<T: GridAccess>(grid: &mut T, position: (usize, usize)) -> bool {
+        let buoyancy = match &grid.get_cell(grid.to_index(position)).particle {
+            Some(p) => p.buoyancy,
+            None => return false,
+        };
+
+        if buoyancy == u8::MIN {
+            return false;
+        }
+
+        let rise_threshold = (buoyancy as i32 * i16::MAX as i32 / u8::MAX as i32) as i16;
+        let index = if grid.vertical_velocity_probability() <= rise_threshold {
+            grid.is_empty(position, (0, -1)).or_else(|| {
+                let (left, right) = (
+                    grid.is_empty(position, (-1, -1)),
+                    grid.is_empty(position, (1, -1)),
+                );
+                Self::pick_side(grid, left, right)
+            })
+        } else {
+            None
+        };
+        let index = index.or_else(|| {
+            let (left, right) = (
+                grid.is_empty(position, (-1, 0)),
+                grid.is_empty(position, (1, 0)),
+            );
+            Self::pick_side(grid, left, right)
+        });
+
+        if let Some(index) = index {
+            grid.swap_particles(grid.to_index(position), index);
+            true
         } else {
             false
         }
     }
 
+    fn pick_side<T: GridAccess>(
+        grid: &mut T,
+        left: Option<usize>,
+        right: Option<usize>,
+    ) -> Option<usize> {
+        match (left, right) {
+            (Some(l), Some(r)) => match grid.horizontal_velocity_probability() {
+                i16::MIN..=0 => Some(l),
+                1..=i16::MAX => Some(r),
+            },
+            (l, r) => l.or(r),
+        }
+    }
+
     fn gravity<T: GridAccess>(grid: &mut T, position: (usize, usize)) -> bool {
         let c = grid.get_cell(grid.to_index(position));
-        let (weight, velocityy) = if let Some(p) = &c.particle {
-            (p.weight, p.velocity.1)
+        let (weight, velocityy, repose, drift, liquid) = if let Some(p) = &c.particle {
+            (
+                p.weight,
+                p.velocity.1.min(p.terminal_velocity),
+                p.repose,
+                p.drift,
+                p.is_liquid(),
+            )
         } else {
             return false;
         };
@@ -340,11 +1044,35 @@ impl Particle {
             return false;
         }
 
+        let sticky = match &c.particle {
+            Some(Particle {
+                kind: ParticleKind::Sticky(sticky),
+                ..
+            }) => Some(sticky.stuck),
+            _ => None,
+        };
+        if sticky == Some(true) {
+            return false;
+        }
+
         let velocityy_probability = grid.vertical_velocity_probability();
 
+        if drift != u8::MIN
+            && grid.drift_probability() < drift
+            && Self::fall_diagonally(grid, position, weight, velocityy, velocityy_probability)
+        {
+            return true;
+        }
+
         if let Ok(index_n) = grid.get_neighbor_index(position, (0, 1)) {
             let cell = grid.get_cell(index_n);
             match &cell.particle {
+                Some(p) if p.is_passable(liquid) => {
+                    if let Some(index) = Self::seep(grid, position, (0, 1), liquid) {
+                        grid.swap_particles(grid.to_index(position), index);
+                        return true;
+                    }
+                }
                 Some(p) => {
                     if !grid.is_simulated(cell) && p.weight < weight && p.weight != u8::MIN {
                         let neighbor_viscosity = p.viscosity;
@@ -356,7 +1084,8 @@ impl Particle {
                             } else {
                                 velocityy
                             };
-                            this.velocity.1 = velocityy.saturating_add(128);
+                            this.velocity.1 =
+                                velocityy.saturating_add(128).min(this.terminal_velocity);
                         };
                         if velocityy_probability <= velocityy {
                             grid.swap_particles(grid.to_index(position), index_n);
@@ -367,7 +1096,7 @@ impl Particle {
                 None => {
                     if let Some(ref mut this) = grid.get_cell_mut(grid.to_index(position)).particle
                     {
-                        this.velocity.1 = velocityy.saturating_add(128);
+                        this.velocity.1 = velocityy.saturating_add(128).min(this.terminal_velocity);
                     };
                     if velocityy_probability <= velocityy {
                         grid.swap_particles(grid.to_index(position), index_n);
@@ -377,6 +1106,47 @@ impl Particle {
             };
         }
 
+        let holds_slope = repose != u8::MIN && grid.repose_probability() < repose;
+        let spreads_sideways = liquid && grid.liquid_spread() == SpreadMode::Orthogonal;
+
+        if !holds_slope
+            && !spreads_sideways
+            && Self::fall_diagonally(grid, position, weight, velocityy, velocityy_probability)
+        {
+            return true;
+        }
+
+        if sticky == Some(false) && Sticky::touches_anything(grid, position) {
+            let cell = grid.get_cell_mut(grid.to_index(position));
+            cell.particle = cell.particle.take().map(|p| Particle {
+                kind: ParticleKind::Sticky(Sticky::stuck()),
+                ..p
+            });
+            grid.activate_window(position);
+            return false;
+        }
+
+        let initial_velocityy = grid.get_particle_initial_velocity().1;
+        if initial_velocityy < velocityy {
+            let velocityy = velocityy.saturating_sub(128).max(initial_velocityy);
+            if let Some(ref mut this) = grid.get_cell_mut(grid.to_index(position)).particle {
+                this.velocity.1 = velocityy;
+            };
+            if velocityy == initial_velocityy {
+                grid.push_event(SimEvent::Rest { position });
+            }
+            grid.activate_window(position);
+        }
+        false
+    }
+
+    fn fall_diagonally<T: GridAccess>(
+        grid: &mut T,
+        position: (usize, usize),
+        weight: u8,
+        velocityy: i16,
+        velocityy_probability: i16,
+    ) -> bool {
         let bottom_left = match grid.get_neighbor_index(position, (-1, 1)) {
             Ok(index_n) => match &grid.get_cell(index_n).particle {
                 Some(p) => {
@@ -415,41 +1185,35 @@ impl Particle {
             },
         } {
             if let Some(ref mut this) = grid.get_cell_mut(grid.to_index(position)).particle {
-                this.velocity.1 = velocityy.saturating_add(128);
+                this.velocity.1 = velocityy.saturating_add(128).min(this.terminal_velocity);
             };
             if velocityy_probability <= velocityy {
                 grid.swap_particles(grid.to_index(position), index_n);
             }
             return true;
         }
-        let initial_velocityy = grid.get_particle_initial_velocity().1;
-        if initial_velocityy < velocityy {
-            if let Some(ref mut this) = grid.get_cell_mut(grid.to_index(position)).particle {
-                this.velocity.1 = velocityy.saturating_sub(128).max(initial_velocityy);
-            };
-            grid.activate_window(position);
-        }
         false
     }
 
     fn kill<T: GridAccess>(grid: &mut T, position: (usize, usize)) -> bool {
         let cycle = grid.cycle();
-        let c = grid.get_cell_mut(grid.to_index(position));
-        let Some(particle) = &mut c.particle else {
-            return false;
-        };
-        if particle.health == 0 {
-            c.particle = None;
-            c.cycle = cycle;
-            return true;
+        let index = grid.to_index(position);
+        let dead = grid
+            .get_cell(index)
+            .particle
+            .as_ref()
+            .is_some_and(|p| p.health == 0 && !p.indestructible);
+        if dead {
+            grid.remove_particle(index);
+            grid.get_cell_mut(index).cycle = cycle;
         }
-        false
+        dead
     }
 }
 
 impl fmt::Display for Particle {
     fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        let s = match self.kind {
+        let s = match &self.kind {
             ParticleKind::Sand(..) => "sand",
             ParticleKind::Water(..) => "water",
             ParticleKind::Salt(..) => "salt",
@@ -457,6 +1221,33 @@ impl fmt::Display for Particle {
             ParticleKind::Drain(..) => "drain",
             ParticleKind::Tap(..) => "tap",
             ParticleKind::Acid(..) => "acid",
+            ParticleKind::Wire(wire) if wire.is_on() => "spark",
+            ParticleKind::Wire(..) => "wire",
+            ParticleKind::Generator(..) => "generator",
+            ParticleKind::Slime(..) => "slime",
+            ParticleKind::Virus(..) => "virus",
+            ParticleKind::Reservoir(..) => "reservoir",
+            ParticleKind::Teleporter(..) => "teleporter",
+            ParticleKind::Crystal(..) => "crystal",
+            ParticleKind::Dust(..) => "dust",
+            ParticleKind::Bedrock(..) => "bedrock",
+            ParticleKind::Cryo(..) => "cryo",
+            ParticleKind::Ice(..) => "ice",
+            ParticleKind::Metal(..) => "metal",
+            ParticleKind::Magnet(..) => "magnet",
+            ParticleKind::Membrane(..) => "membrane",
+            ParticleKind::Rope(..) => "rope",
+            ParticleKind::Dye(..) => "dye",
+            ParticleKind::Fuse(fuse) if fuse.lit => "lit fuse",
+            ParticleKind::Fuse(..) => "fuse",
+            ParticleKind::Gate(..) => "gate",
+            ParticleKind::Glass(..) => "glass",
+            ParticleKind::Sticky(..) => "sticky",
+            ParticleKind::Steam(..) => "steam",
+            ParticleKind::Custom(custom) => match custom.descriptor() {
+                Some(d) => d.name,
+                None => "custom",
+            },
         };
         write!(f, "{s}")
     }
@@ -476,6 +1267,29 @@ mod tests {
         assert_eq!("drain", Particle::from(Drain::new()).to_string());
         assert_eq!("tap", Particle::from(Tap::new()).to_string());
         assert_eq!("acid", Particle::from(Acid::new()).to_string());
+        assert_eq!("wire", Particle::from(Wire::new()).to_string());
+        assert_eq!("spark", Particle::from(Wire::powered()).to_string());
+        assert_eq!("generator", Particle::from(Generator::new()).to_string());
+        assert_eq!("slime", Particle::from(Slime::new()).to_string());
+        assert_eq!("virus", Particle::from(Virus::new()).to_string());
+        assert_eq!("reservoir", Particle::from(Reservoir::new()).to_string());
+        assert_eq!("teleporter", Particle::from(Teleporter::new()).to_string());
+        assert_eq!("crystal", Particle::from(Crystal::new()).to_string());
+        assert_eq!("dust", Particle::from(Dust::new()).to_string());
+        assert_eq!("bedrock", Particle::from(Bedrock::new()).to_string());
+        assert_eq!("cryo", Particle::from(Cryo::new()).to_string());
+        assert_eq!("ice", Particle::from(Ice::new()).to_string());
+        assert_eq!("metal", Particle::from(Metal::new()).to_string());
+        assert_eq!("magnet", Particle::from(Magnet::new()).to_string());
+        assert_eq!("membrane", Particle::from(Membrane::new()).to_string());
+        assert_eq!("rope", Particle::from(Rope::new()).to_string());
+        assert_eq!("dye", Particle::from(Dye::new()).to_string());
+        assert_eq!("fuse", Particle::from(Fuse::new()).to_string());
+        assert_eq!("lit fuse", Particle::from(Fuse::lit()).to_string());
+        assert_eq!("gate", Particle::from(Gate::new()).to_string());
+        assert_eq!("glass", Particle::from(Glass::new()).to_string());
+        assert_eq!("sticky", Particle::from(Sticky::new()).to_string());
+        assert_eq!("steam", Particle::from(Steam::new()).to_string());
     }
 
     #[test]
@@ -488,10 +1302,69 @@ mod tests {
             ParticleKind::from(Drain::new()),
             ParticleKind::from(Tap::new()),
             ParticleKind::from(Acid::new()),
+            ParticleKind::from(Wire::new()),
+            ParticleKind::from(Generator::new()),
+            ParticleKind::from(Slime::new()),
+            ParticleKind::from(Virus::new()),
+            ParticleKind::from(Reservoir::new()),
+            ParticleKind::from(Teleporter::new()),
+            ParticleKind::from(Crystal::new()),
+            ParticleKind::from(Dust::new()),
+            ParticleKind::from(Bedrock::new()),
+            ParticleKind::from(Cryo::new()),
+            ParticleKind::from(Ice::new()),
+            ParticleKind::from(Metal::new()),
+            ParticleKind::from(Magnet::new()),
+            ParticleKind::from(Membrane::new()),
+            ParticleKind::from(Rope::new()),
+            ParticleKind::from(Dye::new()),
+            ParticleKind::from(Fuse::new()),
+            ParticleKind::from(Gate::new()),
+            ParticleKind::from(Glass::new()),
+            ParticleKind::from(Sticky::new()),
+            ParticleKind::from(Steam::new()),
         ] {
             assert_eq!(Some(pk.clone()), ParticleKind::with_id(pk.id()));
         }
     }
+
+    #[test]
+    fn test_built_in_particle_classification() {
+        let classify = |p: Particle| (p.is_solid(), p.is_powder(), p.is_liquid(), p.is_gas());
+        let solid = (true, false, false, false);
+        let powder = (false, true, false, false);
+        let liquid = (false, false, true, false);
+        let gas = (false, false, false, true);
+
+        assert_eq!(powder, classify(Particle::from(Sand::new())));
+        assert_eq!(powder, classify(Particle::from(Dust::new())));
+        assert_eq!(powder, classify(Particle::from(Salt::new())));
+        assert_eq!(liquid, classify(Particle::from(Water::new())));
+        assert_eq!(liquid, classify(Particle::from(Acid::new())));
+        assert_eq!(liquid, classify(Particle::from(Slime::new())));
+        assert_eq!(solid, classify(Particle::from(Rock::new())));
+        assert_eq!(solid, classify(Particle::from(Drain::new())));
+        assert_eq!(solid, classify(Particle::from(Tap::new())));
+        assert_eq!(solid, classify(Particle::from(Wire::new())));
+        assert_eq!(solid, classify(Particle::from(Generator::new())));
+        assert_eq!(solid, classify(Particle::from(Virus::new())));
+        assert_eq!(solid, classify(Particle::from(Reservoir::new())));
+        assert_eq!(solid, classify(Particle::from(Teleporter::new())));
+        assert_eq!(solid, classify(Particle::from(Crystal::new())));
+        assert_eq!(solid, classify(Particle::from(Bedrock::new())));
+        assert_eq!(solid, classify(Particle::from(Cryo::new())));
+        assert_eq!(solid, classify(Particle::from(Ice::new())));
+        assert_eq!(solid, classify(Particle::from(Magnet::new())));
+        assert_eq!(solid, classify(Particle::from(Membrane::new())));
+        assert_eq!(powder, classify(Particle::from(Rope::new())));
+        assert_eq!(powder, classify(Particle::from(Dye::new())));
+        assert_eq!(solid, classify(Particle::from(Fuse::new())));
+        assert_eq!(solid, classify(Particle::from(Gate::new())));
+        assert_eq!(solid, classify(Particle::from(Glass::new())));
+        assert_eq!(powder, classify(Particle::from(Sticky::new())));
+        assert_eq!(powder, classify(Particle::from(Metal::new())));
+        assert_eq!(gas, classify(Particle::from(Steam::new())));
+    }
 }
 
 #[cfg(test)]
@@ -505,6 +1378,7 @@ mod weightless {
             Particle::from(Rock::new()),
             Particle::from(Tap::new()),
             Particle::from(Drain::new()),
+            Particle::from(Glass::new()),
         ]
     }
 
@@ -886,6 +1760,33 @@ mod powder {
             assert_eq!(vec![Cell::empty().with_cycle(1)], *g.get_cells());
         }
     }
+
+    #[test]
+    fn test_drifting_powder_prefers_diagonals_over_falling_straight_down() {
+        /*
+         * ---s--- -> ------- | -------
+         * -------    ------- | -------
+         * -------    ------- | -------
+         * -------    ---s--- | ------s
+         */
+        fn landing_column(drift: u8) -> usize {
+            let mut g = Grid::new(7, 4)
+                .with_rand_vertical_velocity_probability(|_| 0)
+                .with_rand_horizontal_velocity_probability(|_| 1)
+                .with_rand_drift_probability(|_| 128);
+            g.spawn_particle((3, 0), Particle::from(Sand::new()).with_drift(drift));
+            for _ in 0..3 {
+                g.update_grid();
+            }
+            (0..7)
+                .find(|&x| g.get_cell(g.to_index((x, 3))).particle.is_some())
+                .unwrap()
+        }
+
+        assert_eq!(3, landing_column(u8::MIN));
+        assert_eq!(3, landing_column(64));
+        assert_eq!(6, landing_column(200));
+    }
 }
 
 #[cfg(test)]
@@ -910,6 +1811,70 @@ mod liquid {
         vec![Particle::from(Sand::new()), Particle::from(Salt::new())]
     }
 
+    #[test]
+    fn test_liquid_spread_mode_changes_how_a_puddle_spreads() {
+        /*
+         * diagonal     orthogonal
+         * ---    ---   ---    ---
+         * -w- -> ---   -w- -> w--
+         * -r-    wr-   -r-    -r-
+         */
+        let spread = |mode: SpreadMode| {
+            let mut g = Grid::from_rle("3-/-w-/-r-")
+                .unwrap()
+                .with_liquid_spread(mode)
+                .with_rand_horizontal_velocity_probability(|_| -1);
+            g.update_grid();
+            g.to_rle()
+        };
+
+        assert_eq!("3-/3-/wr-", spread(SpreadMode::default()));
+        assert_eq!("3-/3-/wr-", spread(SpreadMode::Diagonal));
+        assert_eq!("3-/w2-/-r-", spread(SpreadMode::Orthogonal));
+    }
+
+    #[test]
+    fn test_surface_jitter_keeps_a_still_pool_awake() {
+        /*
+         * ----
+         * wwww
+         * rrrr
+         */
+        let pool = |jitter: f32| {
+            let mut g = Grid::from_rle("4-/4w/4r")
+                .unwrap()
+                .with_surface_jitter(jitter)
+                .with_rand_jitter_probability(|_| 0.);
+            g.run_until_settled(50);
+            (g.is_settled(), g.to_rle())
+        };
+
+        assert_eq!((true, "4-/4w/4r".to_string()), pool(0.));
+        assert_eq!((false, "4-/4w/4r".to_string()), pool(0.5));
+    }
+
+    #[test]
+    fn test_max_viscosity_keeps_a_particle_from_flowing() {
+        /*
+         * -w- -> -w-
+         */
+        assert_eq!(u8::MAX, Particle::from(Sand::new()).viscosity());
+        assert_eq!(0, Particle::from(Water::new()).viscosity());
+        assert_eq!(
+            10,
+            Particle::from(Water::new()).with_viscosity(10).viscosity()
+        );
+
+        let mut g = Grid::new(3, 1);
+        g.spawn_particle((1, 0), Particle::from(Water::new()).with_viscosity(u8::MAX));
+        assert!(!Particle::flow(&mut g, (1, 0)));
+        assert_eq!("-w-", g.to_rle());
+
+        let mut g = Grid::new(3, 1);
+        g.spawn_particle((1, 0), Particle::from(Water::new()));
+        assert!(Particle::flow(&mut g, (1, 0)));
+    }
+
     #[test]
     fn test_liquid_particle_falls_down_to_last_row_stays_there() {
         /*
@@ -1916,4 +2881,146 @@ mod liquid {
             }
         }
     }
+
+    #[test]
+    fn test_lone_liquid_particle_with_cohesion_spreads_less_than_without() {
+        /*
+         * ---w--- -> ----w-- | ---w---
+         * rrrrrrr    rrrrrrr | rrrrrrr
+         */
+        fn spread_width(g: &mut Grid) -> usize {
+            for x in 0..7 {
+                g.spawn_particle((x, 1), Particle::from(Rock::new()));
+            }
+            g.spawn_particle((3, 0), Particle::from(Water::new()).with_velocity((0, 0)));
+            for _ in 0..3 {
+                g.update_grid();
+            }
+            let x = (0..7)
+                .find(|&x| g.get_cell(g.to_index((x, 0))).particle.is_some())
+                .unwrap();
+            x.abs_diff(3)
+        }
+
+        let without =
+            spread_width(&mut Grid::new(7, 2).with_rand_horizontal_velocity_probability(|_| 1));
+        let with = spread_width(
+            &mut Grid::new(7, 2)
+                .with_rand_horizontal_velocity_probability(|_| 1)
+                .with_cohesion(1.),
+        );
+
+        assert!(with < without);
+        assert_eq!(0, with);
+    }
+
+    #[test]
+    fn test_sand_with_high_repose_piles_steeper_than_with_low_repose() {
+        /*
+         * --s-- -> --s-- | -----
+         * --s--    --s-- | -----
+         * --s--    --s-- | --s--
+         * --s--    --s-- | -sss-
+         */
+        fn pile_height(repose: u8) -> usize {
+            let mut g = Grid::new(5, 4)
+                .with_rand_horizontal_velocity_probability(|_| 1)
+                .with_rand_repose_probability(|_| 128);
+            for y in 0..4 {
+                g.spawn_particle((2, y), Particle::from(Sand::new()).with_repose(repose));
+            }
+            for _ in 0..50 {
+                g.update_grid();
+            }
+            (0..4)
+                .filter(|&y| g.get_cell(g.to_index((2, y))).particle.is_some())
+                .count()
+        }
+
+        let high = pile_height(200);
+        let low = pile_height(64);
+
+        assert!(high > low);
+        assert_eq!(4, high);
+        assert_eq!(pile_height(u8::MIN), low);
+        assert_eq!(2, low);
+    }
+
+    #[test]
+    fn test_sand_with_a_higher_repose_piles_steeper_than_salt() {
+        /*
+         * --s-- | --S-- -> --s-- | -----
+         * --s-- | --S--    --s-- | -----
+         * --s-- | --S--    --s-- | --S--
+         * --s-- | --S--    --s-- | -SSS-
+         */
+        fn pile_height(particle: Particle) -> usize {
+            let mut g = Grid::new(5, 4)
+                .with_rand_horizontal_velocity_probability(|_| 1)
+                .with_rand_repose_probability(|_| 64);
+            for y in 0..4 {
+                g.spawn_particle((2, y), particle.clone());
+            }
+            for _ in 0..50 {
+                g.update_grid();
+            }
+            (0..4)
+                .filter(|&y| g.get_cell(g.to_index((2, y))).particle.is_some())
+                .count()
+        }
+
+        assert_eq!(4, pile_height(Particle::from(Sand::new()).with_repose(96)));
+        assert_eq!(2, pile_height(Particle::from(Salt::new()).with_repose(32)));
+    }
+}
+
+#[cfg(test)]
+mod gas {
+    use crate::component::{
+        grid::{Grid, RowUpdateDirection},
+        particles::{particle::Particle, steam::Steam},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_steam_rises_to_the_ceiling() {
+        /*
+         * -- -> -- -> -- -> W-
+         * --    --    W-    --
+         * --    W-    --    --
+         * W-    --    --    --
+         */
+        let mut g = Grid::new(2, 4)
+            .with_rand_vertical_velocity_probability(|_| 0)
+            .with_rand_horizontal_velocity_probability(|_| 1);
+        g.spawn_particle((0, 3), Particle::from(Steam::new()));
+
+        for _ in 0..3 {
+            g.update_grid();
+        }
+
+        assert_eq!("W-/2-/2-/2-", g.to_rle());
+    }
+
+    #[test]
+    fn test_steam_spreads_under_the_ceiling() {
+        /*
+         * --W-- -> W-W-W
+         * --W--    -----
+         * --W--    -----
+         */
+        let mut g = Grid::new(5, 3)
+            .with_rand_vertical_velocity_probability(|_| 0)
+            .with_rand_horizontal_velocity_probability(|_| 0)
+            .with_rand_row_update_direction(|_| RowUpdateDirection::Forward);
+        for y in 0..3 {
+            g.spawn_particle((2, y), Particle::from(Steam::new()));
+        }
+
+        for _ in 0..2 {
+            g.update_grid();
+        }
+
+        assert_eq!("W-W-W/5-/5-", g.to_rle());
+    }
 }
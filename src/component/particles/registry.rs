@@ -0,0 +1,191 @@
+use std::sync::RwLock;
+
+use bevy::color::Color;
+
+use crate::component::grid::GridAccess;
+
+pub const CUSTOM_KIND_FIRST_ID: u8 = 128;
+
+/// Glyphs the built-in kinds and the rle format already use.
+const RESERVED_GLYPHS: &str = "-/swSrdtaIiglvRTcDBCemMnpyfFhHGkKW";
+
+pub type UpdateFn = fn(&mut dyn GridAccess, (usize, usize));
+
+#[derive(Clone, Debug)]
+pub struct KindDescriptor {
+    pub name: &'static str,
+    pub glyph: char,
+    pub color: Color,
+    pub weight: u8,
+    pub viscosity: u8,
+    pub update: Option<UpdateFn>,
+}
+
+static REGISTRY: RwLock<Vec<KindDescriptor>> = RwLock::new(Vec::new());
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Custom {
+    pub id: u8,
+}
+
+impl Custom {
+    /// Registers a new kind, or `None` when its glyph can't be told apart
+    /// from a built-in kind, another custom kind or an rle run length.
+    pub fn register(descriptor: KindDescriptor) -> Option<Self> {
+        let glyph = descriptor.glyph;
+        if RESERVED_GLYPHS.contains(glyph) || glyph.is_ascii_digit() || glyph.is_whitespace() {
+            return None;
+        }
+        let mut registry = REGISTRY.write().ok()?;
+        if registry.iter().any(|d| d.glyph == glyph) {
+            return None;
+        }
+        let id = CUSTOM_KIND_FIRST_ID.checked_add(u8::try_from(registry.len()).ok()?)?;
+        registry.push(descriptor);
+        Some(Self { id })
+    }
+
+    pub fn with_id(id: u8) -> Option<Self> {
+        Self::descriptor_of(id).map(|_| Self { id })
+    }
+
+    pub fn with_glyph(glyph: char) -> Option<Self> {
+        let registry = REGISTRY.read().ok()?;
+        let index = registry.iter().position(|d| d.glyph == glyph)?;
+        Some(Self {
+            id: CUSTOM_KIND_FIRST_ID + index as u8,
+        })
+    }
+
+    pub fn descriptor(&self) -> Option<KindDescriptor> {
+        Self::descriptor_of(self.id)
+    }
+
+    fn descriptor_of(id: u8) -> Option<KindDescriptor> {
+        let index = id.checked_sub(CUSTOM_KIND_FIRST_ID)?;
+        REGISTRY.read().ok()?.get(index as usize).cloned()
+    }
+
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        if let Some(KindDescriptor {
+            update: Some(update),
+            ..
+        }) = self.descriptor()
+        {
+            update(grid, position);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use bevy::color::Color;
+    use pretty_assertions::assert_eq;
+
+    use crate::component::{
+        grid::{Grid, GridAccess},
+        macros::assert_color_srgb_eq,
+        particles::{
+            particle::{Particle, ParticleKind},
+            registry::{Custom, KindDescriptor},
+        },
+    };
+
+    fn wither(grid: &mut dyn GridAccess, position: (usize, usize)) {
+        let cell = grid.get_cell_mut(grid.to_index(position));
+        if let Some(p) = &mut cell.particle {
+            p.health = p.health.saturating_sub(100);
+        }
+        grid.activate_window(position);
+    }
+
+    #[test]
+    fn test_registered_custom_kind_is_simulated_end_to_end() {
+        /*
+         * o -> - -> -
+         * -    o    -
+         */
+        let ember = Custom::register(KindDescriptor {
+            name: "ember",
+            glyph: 'o',
+            color: Color::srgb(1., 0.4, 0.),
+            weight: 5,
+            viscosity: u8::MAX,
+            update: Some(wither),
+        })
+        .unwrap();
+        let kind = ParticleKind::from(ember.clone());
+
+        assert_eq!(Some(kind.clone()), ParticleKind::with_id(kind.id()));
+        assert_eq!("ember", Particle::from(kind.clone()).to_string());
+        assert_color_srgb_eq!(
+            Color::srgb(1., 0.4, 0.),
+            Particle::from(kind.clone()).color()
+        );
+
+        let mut g = Grid::new(1, 2).with_rand_vertical_velocity_probability(|_| 0);
+        g.spawn_particle((0, 0), Particle::from(kind.clone()));
+        g.update_grid();
+        assert_eq!("-/o", g.to_rle());
+
+        for _ in 0..4 {
+            g.update_grid();
+        }
+        assert_eq!("-/-", g.to_rle());
+        assert_eq!(
+            Some(ember),
+            Grid::from_rle("o")
+                .ok()
+                .and_then(|g| match &g.get_cell(0).particle {
+                    Some(Particle {
+                        kind: ParticleKind::Custom(c),
+                        ..
+                    }) => Some(c.clone()),
+                    _ => None,
+                })
+        );
+    }
+
+    #[test]
+    fn test_register_rejects_glyphs_that_would_be_ambiguous() {
+        let descriptor = |glyph| KindDescriptor {
+            name: "shadow",
+            glyph,
+            color: Color::BLACK,
+            weight: 1,
+            viscosity: u8::MAX,
+            update: None,
+        };
+
+        for glyph in ['s', 'K', 'w', '7', '0', '-', '/', ' '] {
+            assert_eq!(None, Custom::register(descriptor(glyph)), "{glyph:?}");
+        }
+
+        assert!(Custom::register(descriptor('x')).is_some());
+        assert_eq!(None, Custom::register(descriptor('x')));
+    }
+
+    #[test]
+    fn test_register_rejects_the_steam_glyph() {
+        let vapor = KindDescriptor {
+            name: "vapor",
+            glyph: 'W',
+            color: Color::WHITE,
+            weight: 1,
+            viscosity: u8::MAX,
+            update: None,
+        };
+
+        assert_eq!(None, Custom::register(vapor));
+        assert_eq!(
+            "steam",
+            Grid::from_rle("W")
+                .unwrap()
+                .get_cell(0)
+                .particle
+                .as_ref()
+                .unwrap()
+                .to_string()
+        );
+    }
+}
@@ -0,0 +1,158 @@
+use crate::component::grid::GridAccess;
+
+use super::{
+    particle::{ORTHOGONAL_NEIGHBORS, Particle, ParticleKind},
+    wire::WireState,
+};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Reservoir {
+    pub stored: u8,
+    pub liquid_kind_id: Option<u8>,
+}
+
+impl Default for Reservoir {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Reservoir {
+    pub fn new() -> Self {
+        Self {
+            stored: 0,
+            liquid_kind_id: None,
+        }
+    }
+
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        let mut reservoir = self.clone();
+        let released = Self::is_triggered(grid, position) && reservoir.release(grid, position);
+        if !released {
+            reservoir.fill(grid, position);
+        }
+        if reservoir != *self {
+            let cycle = grid.cycle();
+            let cell = grid.get_cell_mut(grid.to_index(position));
+            if let Some(p) = &mut cell.particle {
+                p.kind = ParticleKind::from(reservoir);
+                cell.cycle = cycle;
+            }
+            grid.activate_window(position);
+        }
+    }
+
+    fn is_triggered<T: GridAccess>(grid: &T, position: (usize, usize)) -> bool {
+        ORTHOGONAL_NEIGHBORS.into_iter().any(|offset| {
+            grid.get_neighbor_index(position, offset).is_ok_and(|i| {
+                let cell = grid.get_cell(i);
+                matches!(
+                    &cell.particle,
+                    Some(Particle {
+                        kind: ParticleKind::Wire(w),
+                        ..
+                    }) if w.is_on() || (w.state == WireState::Cooling && cell.cycle == grid.cycle())
+                )
+            })
+        })
+    }
+
+    fn fill<T: GridAccess>(&mut self, grid: &mut T, position: (usize, usize)) {
+        if self.stored == u8::MAX {
+            return;
+        }
+        let Some(index) = grid.get_neighbor_index(position, (0, -1)).ok() else {
+            return;
+        };
+        let cycle = grid.cycle();
+        if let Some(p) = &grid.get_cell(index).particle
+            && p.is_liquid()
+            && self.liquid_kind_id.is_none_or(|id| id == p.kind.id())
+        {
+            self.liquid_kind_id = Some(p.kind.id());
+            self.stored += 1;
+            grid.remove_particle(index);
+            grid.get_cell_mut(index).cycle = cycle;
+        }
+    }
+
+    fn release<T: GridAccess>(&mut self, grid: &mut T, position: (usize, usize)) -> bool {
+        if let Some(kind) = self.liquid_kind_id.and_then(ParticleKind::with_id)
+            && let Some(index) = grid.is_empty(position, (0, 1))
+        {
+            let cycle = grid.cycle();
+            let particle = Particle::from(kind)
+                .with_velocity(grid.get_particle_initial_velocity())
+                .with_seed(grid.particle_seed());
+            if !grid.place_particle(index, particle) {
+                return false;
+            }
+            grid.get_cell_mut(index).cycle = cycle;
+            self.stored -= 1;
+            if self.stored == 0 {
+                self.liquid_kind_id = None;
+            }
+            return true;
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::{Grid, GridAccess},
+        particles::{
+            particle::{Particle, ParticleKind},
+            reservoir::Reservoir,
+            rock::Rock,
+            water::Water,
+            wire::Wire,
+        },
+    };
+    use pretty_assertions::assert_eq;
+
+    fn reservoir_at(g: &Grid, position: (usize, usize)) -> Option<Reservoir> {
+        match &g.get_cell(g.to_index(position)).particle {
+            Some(Particle {
+                kind: ParticleKind::Reservoir(r),
+                ..
+            }) => Some(r.clone()),
+            _ => None,
+        }
+    }
+
+    #[test]
+    fn test_reservoir_fills_from_water_above_and_releases_below_on_trigger() {
+        /*
+         * -w- -> --- -> ---
+         * rRr    rRr    rRi
+         * r-r    r-r    rwr
+         */
+        let mut g = Grid::new(3, 3);
+        g.spawn_particle((1, 0), Particle::from(Water::new()));
+        for position in [(0, 1), (2, 1), (0, 2), (2, 2)] {
+            g.spawn_particle(position, Particle::from(Rock::new()));
+        }
+        g.spawn_particle((1, 1), Particle::from(Reservoir::new()));
+
+        g.update_grid();
+        assert_eq!("3-/rRr/r-r", g.to_rle());
+        assert_eq!(
+            Some(Reservoir {
+                stored: 1,
+                liquid_kind_id: Some(ParticleKind::from(Water::new()).id()),
+            }),
+            reservoir_at(&g, (1, 1))
+        );
+
+        g.update_grid();
+        assert_eq!("3-/rRr/r-r", g.to_rle());
+
+        g.despawn_particle((2, 1));
+        g.spawn_particle((2, 1), Particle::from(Wire::powered()));
+        g.update_grid();
+        assert_eq!("3-/rRi/rwr", g.to_rle());
+        assert_eq!(Some(Reservoir::new()), reservoir_at(&g, (1, 1)));
+    }
+}
@@ -0,0 +1,114 @@
+use crate::component::grid::GridAccess;
+
+use super::particle::{Particle, ParticleKind};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Rope;
+
+impl Default for Rope {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Rope {
+    pub fn new() -> Self {
+        Self
+    }
+
+    /// Walks up the chain of rope segments above `position` and reports
+    /// whether it ends at an anchor, i.e. a solid particle. Sand, water or
+    /// gas resting on the rope fall with it rather than hold it up.
+    pub fn is_anchored<T: GridAccess>(grid: &T, position: (usize, usize)) -> bool {
+        let mut position = position;
+        while let Ok(above) = grid.get_neighbor_position(position, (0, -1)) {
+            match &grid.get_cell(grid.to_index(above)).particle {
+                Some(Particle {
+                    kind: ParticleKind::Rope(..),
+                    ..
+                }) => position = above,
+                Some(p) => return p.is_solid(),
+                None => return false,
+            }
+        }
+        false
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::Grid,
+        particles::{particle::Particle, rock::Rock, rope::Rope, sand::Sand, water::Water},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_rope_hangs_from_an_anchor_and_falls_once_it_is_cut() {
+        /*
+         * r -> r -> -
+         * p    p    -
+         * p    p    -
+         * p    p    p
+         * -    -    p
+         * -    -    p
+         */
+        let mut g = Grid::new(1, 6);
+        g.spawn_particle((0, 0), Particle::from(Rock::new()));
+        for y in 1..4 {
+            g.spawn_particle((0, y), Particle::from(Rope::new()));
+        }
+
+        for _ in 0..5 {
+            g.update_grid();
+        }
+        assert_eq!("r/p/p/p/-/-", g.to_rle());
+
+        g.despawn_particle((0, 0));
+        for _ in 0..5 {
+            g.update_grid();
+        }
+        assert_eq!("-/-/-/p/p/p", g.to_rle());
+    }
+
+    #[test]
+    fn test_rope_without_an_anchor_falls() {
+        /*
+         * p -> -
+         * -    p
+         */
+        let mut g = Grid::new(1, 2);
+        g.spawn_particle((0, 0), Particle::from(Rope::new()));
+
+        g.update_grid();
+
+        assert_eq!("-/p", g.to_rle());
+    }
+
+    #[test]
+    fn test_rope_under_sand_or_water_is_not_anchored_and_falls() {
+        /*
+         * s -> -    w -> -
+         * p    -    p    -
+         * p    p    p    w
+         * -    p    -    p
+         * -    s    -    p
+         */
+        for (top, expected) in [
+            (Particle::from(Sand::new()), "-/-/p/p/s"),
+            (Particle::from(Water::new()), "-/-/w/p/p"),
+        ] {
+            let mut g = Grid::new(1, 5);
+            g.spawn_particle((0, 0), top);
+            for y in 1..3 {
+                g.spawn_particle((0, y), Particle::from(Rope::new()));
+            }
+
+            for _ in 0..5 {
+                g.update_grid();
+            }
+
+            assert_eq!(expected, g.to_rle());
+        }
+    }
+}
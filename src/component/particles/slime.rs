@@ -0,0 +1,71 @@
+use crate::component::grid::GridAccess;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Slime;
+
+impl Default for Slime {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Slime {
+    pub fn new() -> Self {
+        Self
+    }
+
+    pub fn clings<T: GridAccess>(grid: &T, position: (usize, usize)) -> bool {
+        [(-1, 0), (1, 0)].into_iter().any(|offset| {
+            grid.get_neighbor_index(position, offset).is_ok_and(|i| {
+                grid.get_cell(i)
+                    .particle
+                    .as_ref()
+                    .is_some_and(|p| p.is_solid())
+            })
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::component::{
+        grid::{Grid, GridAccess},
+        particles::{particle::Particle, rock::Rock, slime::Slime, water::Water},
+    };
+
+    fn pour_next_to_wall(particle: Particle) -> Grid {
+        /*
+         * r---
+         * rp--
+         * rrrr
+         */
+        let mut g = Grid::new(4, 3).with_rand_horizontal_velocity_probability(|_| 1);
+        for y in 0..3 {
+            g.spawn_particle((0, y), Particle::from(Rock::new()));
+        }
+        for x in 1..4 {
+            g.spawn_particle((x, 2), Particle::from(Rock::new()));
+        }
+        g.spawn_particle((1, 1), particle);
+        for _ in 0..5 {
+            g.update_grid();
+        }
+        g
+    }
+
+    #[test]
+    fn test_slime_stays_against_a_rock_wall() {
+        let g = pour_next_to_wall(Particle::from(Slime::new()));
+
+        assert_eq!("l", g.get_cell(g.to_index((1, 1))).to_string());
+    }
+
+    #[test]
+    fn test_water_spreads_away_from_a_rock_wall() {
+        let g = pour_next_to_wall(Particle::from(Water::new()));
+
+        assert_eq!("-", g.get_cell(g.to_index((1, 1))).to_string());
+    }
+}
@@ -0,0 +1,14 @@
+#[derive(Clone, PartialEq, Debug)]
+pub struct Steam;
+
+impl Default for Steam {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Steam {
+    pub fn new() -> Self {
+        Self
+    }
+}
@@ -0,0 +1,110 @@
+use crate::component::grid::GridAccess;
+
+use super::particle::ORTHOGONAL_NEIGHBORS;
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Sticky {
+    pub stuck: bool,
+}
+
+impl Default for Sticky {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Sticky {
+    pub fn new() -> Self {
+        Self { stuck: false }
+    }
+
+    pub fn stuck() -> Self {
+        Self { stuck: true }
+    }
+
+    /// Whether anything sits right above, below or beside `position` for a
+    /// sticky particle there to hold on to. The grid floor counts too.
+    pub fn touches_anything<T: GridAccess>(grid: &T, position: (usize, usize)) -> bool {
+        grid.get_neighbor_index(position, (0, 1)).is_err()
+            || ORTHOGONAL_NEIGHBORS.iter().any(|&offset| {
+                grid.get_neighbor_index(position, offset)
+                    .is_ok_and(|i| grid.get_cell(i).particle.is_some())
+            })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::Grid,
+        particles::{particle::Particle, rock::Rock, sticky::Sticky},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_sticky_lands_on_rock_and_stays_after_the_rock_is_removed() {
+        /*
+         * k -> - -> -
+         * -    -    -
+         * -    K    K
+         * r    r    -
+         * -    -    -
+         */
+        let mut g = Grid::new(1, 5);
+        g.spawn_particle((0, 0), Particle::from(Sticky::new()));
+        g.spawn_particle((0, 3), Particle::from(Rock::new()));
+
+        g.run_until_settled(20);
+        assert_eq!("-/-/K/r/-", g.to_rle());
+
+        g.despawn_particle((0, 3));
+        for _ in 0..5 {
+            g.update_grid();
+        }
+        assert_eq!("-/-/K/-/-", g.to_rle());
+    }
+
+    #[test]
+    fn test_sticky_slides_down_a_wall_and_sticks_once_it_lands() {
+        /*
+         * rk -> r- -> r-
+         * r-    rk    rK
+         */
+        let mut g = Grid::from_rle("rk/r-").unwrap();
+
+        g.update_grid();
+        assert_eq!("r-/rk", g.to_rle());
+
+        g.update_grid();
+        assert_eq!("r-/rK", g.to_rle());
+    }
+
+    #[test]
+    fn test_painted_sticky_blob_falls_before_sticking() {
+        /*
+         * kk -> --
+         * kk    --
+         * --    KK
+         * --    KK
+         * rr    rr
+         */
+        let mut g = Grid::from_rle("2k/2k/2-/2-/2r").unwrap();
+
+        g.run_until_settled(30);
+        assert_eq!("2-/2-/2K/2K/2r", g.to_rle());
+    }
+
+    #[test]
+    fn test_sticky_sticks_once_it_lands_on_the_grid_floor() {
+        /*
+         * k- -> --
+         * -k    --
+         * --    KK
+         */
+        let mut g = Grid::from_rle("k-/-k/2-").unwrap();
+
+        g.run_until_settled(20);
+
+        assert_eq!("2-/2-/2K", g.to_rle());
+    }
+}
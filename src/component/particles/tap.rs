@@ -2,9 +2,14 @@ use crate::component::grid::GridAccess;
 
 use super::particle::{Particle, ParticleKind};
 
+const DIRECTIONAL_SPEED: i16 = 128;
+/// How many cells a directional tap launches a grain along its direction.
+const DIRECTIONAL_REACH: i32 = 3;
+
 #[derive(Clone, PartialEq, Debug)]
 pub struct Tap {
     pub particle_kind_id: Option<u8>,
+    pub directional: bool,
 }
 
 impl Default for Tap {
@@ -17,15 +22,22 @@ impl Tap {
     pub fn new() -> Self {
         Self {
             particle_kind_id: None,
+            directional: false,
         }
     }
 
     pub fn with_particle(particle: &Particle) -> Self {
         Self {
             particle_kind_id: Some(particle.kind.id()),
+            directional: false,
         }
     }
 
+    pub fn with_directional(mut self, directional: bool) -> Self {
+        self.directional = directional;
+        self
+    }
+
     pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
         let mut particle = self.clone();
         if particle.particle_kind_id.is_none() {
@@ -42,9 +54,8 @@ impl Tap {
             }
 
             if let Some(p) = particle_to_clone {
-                let cell = grid.get_cell_mut(grid.to_index(position));
                 particle.particle_kind_id = Some(p.kind.id());
-                cell.particle = Some(Particle::from(particle.clone()));
+                grid.place_particle(grid.to_index(position), Particle::from(particle.clone()));
             }
         }
 
@@ -56,19 +67,53 @@ impl Tap {
                     if let Ok(i) = grid.get_neighbor_index(position, (x, y))
                         && grid.get_cell_mut(i).particle.is_none()
                     {
+                        if !grid.try_emit() {
+                            grid.activate_window(position);
+                            return;
+                        }
                         let cycle = grid.cycle();
+                        let (i, velocity) = match particle.directional {
+                            true => Self::launch(grid, position, (x, y)),
+                            false => (i, grid.get_particle_initial_velocity()),
+                        };
                         let particle = Particle::from(particle_kind.clone())
-                            .with_velocity(grid.get_particle_initial_velocity())
+                            .with_velocity(velocity)
                             .with_seed(grid.particle_seed());
-                        let cell = grid.get_cell_mut(i);
-                        cell.particle = Some(particle);
-                        cell.cycle = cycle;
+                        if !grid.place_particle(i, particle) {
+                            grid.activate_window(position);
+                            return;
+                        }
+                        grid.get_cell_mut(i).cycle = cycle;
+                        if self.directional {
+                            grid.activate_window(grid.to_position(i));
+                        }
                         grid.get_cell_mut(grid.to_index(position)).cycle = cycle;
                     };
                 }
             }
         }
     }
+
+    fn launch<T: GridAccess>(
+        grid: &T,
+        position: (usize, usize),
+        (x, y): (i32, i32),
+    ) -> (usize, (i16, i16)) {
+        let mut index = grid
+            .get_neighbor_index(position, (x, y))
+            .expect("emitting into a neighbor inside the grid");
+        for step in 2..=DIRECTIONAL_REACH {
+            match grid.get_neighbor_index(position, (x * step, y * step)) {
+                Ok(i) if grid.get_cell(i).particle.is_none() => index = i,
+                _ => break,
+            }
+        }
+        let velocityy = match y {
+            1 => i16::MAX,
+            _ => grid.get_particle_initial_velocity().1,
+        };
+        (index, (x as i16 * DIRECTIONAL_SPEED, velocityy))
+    }
 }
 
 #[cfg(test)]
@@ -328,6 +373,65 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_directional_tap_launches_sand_and_water_away_from_it() {
+        /*
+         * ------- -> s--s--s
+         * -------    -------
+         * -------    -------
+         * ---t---    s--t--s
+         * -------    -------
+         * -------    -------
+         * -------    s--s--s
+         */
+        for particle in [Particle::from(Sand::new()), Particle::from(Water::new())] {
+            let mut g = Grid::new(7, 7);
+
+            g.spawn_particle(
+                (3, 3),
+                Particle::from(Tap::with_particle(&particle).with_directional(true)),
+            );
+
+            g.update_grid();
+
+            for (x, y) in [
+                (0, 0),
+                (3, 0),
+                (6, 0),
+                (0, 3),
+                (6, 3),
+                (0, 6),
+                (3, 6),
+                (6, 6),
+            ] {
+                assert_eq!(
+                    Some(&particle.kind),
+                    g.get_cell(g.to_index((x, y)))
+                        .particle
+                        .as_ref()
+                        .map(|p| &p.kind),
+                    "{} at {:?}",
+                    particle,
+                    (x, y)
+                );
+            }
+
+            g.despawn_particle((3, 3));
+            for _ in 0..10 {
+                g.update_grid();
+            }
+
+            for x in 0..7 {
+                assert_eq!(
+                    None,
+                    g.get_cell(g.to_index((x, 0))).particle,
+                    "{}",
+                    particle
+                );
+            }
+        }
+    }
+
     #[test]
     fn test_tap_clones_a_new_particle_with_a_random_seed() {
         let mut g = Grid::new(1, 2).with_rand_seed_with_cycle(|_| 33);
@@ -353,4 +457,31 @@ mod tests {
 
         assert_eq!(33, g.get_cell(1).particle.as_ref().map(|p| p.seed).unwrap());
     }
+
+    #[test]
+    fn test_taps_share_the_grid_emission_budget() {
+        /*
+         * ttt -> ttt -> ttt
+         * ---    ss-    sss
+         */
+        let sand = Particle::from(Sand::new());
+        let mut g = Grid::new(3, 2)
+            .with_emission_budget(2)
+            .with_deterministic_row_order(true);
+        for x in 0..3 {
+            g.spawn_particle((x, 0), Particle::from(Tap::with_particle(&sand)));
+        }
+        let emitted = |g: &Grid| {
+            g.get_cells()
+                .iter()
+                .filter(|c| matches!(&c.particle, Some(p) if p.kind == sand.kind))
+                .count()
+        };
+
+        g.update_grid();
+        assert_eq!(2, emitted(&g));
+
+        g.update_grid();
+        assert_eq!(3, emitted(&g));
+    }
 }
@@ -0,0 +1,141 @@
+use crate::component::grid::GridAccess;
+
+use super::particle::ParticleKind;
+
+/// Cells a carried particle can come out of next to the partner, below it
+/// first. The cell above is left out since that is the partner's own
+/// entrance and would send the particle straight back.
+const TELEPORTER_EXITS: [(i32, i32); 3] = [(0, 1), (-1, 0), (1, 0)];
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Teleporter {
+    pub channel: u8,
+}
+
+impl Default for Teleporter {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Teleporter {
+    pub fn new() -> Self {
+        Self::with_channel(0)
+    }
+
+    pub fn with_channel(channel: u8) -> Self {
+        Self { channel }
+    }
+
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        let Ok(entrance) = grid.get_neighbor_index(position, (0, -1)) else {
+            return;
+        };
+        let carried = grid
+            .get_cell(entrance)
+            .particle
+            .as_ref()
+            .is_some_and(|p| !p.is_solid() && !matches!(p.kind, ParticleKind::Teleporter(..)));
+        if !carried {
+            return;
+        }
+        if let Some(exit) = self.find_exit(grid, grid.to_index(position)) {
+            let cycle = grid.cycle();
+            if let Some(particle) = grid.remove_particle(entrance) {
+                grid.place_particle(exit, particle);
+            }
+            grid.get_cell_mut(entrance).cycle = cycle;
+            grid.get_cell_mut(exit).cycle = cycle;
+            grid.activate_window(position);
+            grid.activate_window(grid.to_position(exit));
+        }
+    }
+
+    /// The first other teleporter on the same channel is the partner; the
+    /// scan stops there instead of walking the rest of the grid.
+    fn find_exit<T: GridAccess>(&self, grid: &T, index: usize) -> Option<usize> {
+        let partner = grid.get_cells().iter().enumerate().position(|(i, c)| {
+            i != index
+                && matches!(
+                    &c.particle,
+                    Some(p) if p.kind == ParticleKind::from(self.clone())
+                )
+        })?;
+        let partner = grid.to_position(partner);
+        TELEPORTER_EXITS
+            .into_iter()
+            .find_map(|offset| grid.is_empty(partner, offset))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::Grid,
+        particles::{particle::Particle, rock::Rock, sand::Sand, teleporter::Teleporter},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_sand_falling_into_a_teleporter_appears_below_its_pair() {
+        /*
+         * -s- -> --- -> ---
+         * ---    -s-    ---
+         * -T-    -T-    -T-
+         * ---    ---    ---
+         * -T-    -T-    -T-
+         * ---    ---    -s-
+         */
+        let mut g = Grid::new(3, 6).with_rand_vertical_velocity_probability(|_| 0);
+        g.spawn_particle((1, 0), Particle::from(Sand::new()));
+        g.spawn_particle((1, 2), Particle::from(Teleporter::new()));
+        g.spawn_particle((1, 4), Particle::from(Teleporter::new()));
+
+        g.update_grid();
+        assert_eq!("3-/-s-/-T-/3-/-T-/3-", g.to_rle());
+
+        g.update_grid();
+        assert_eq!("3-/3-/-T-/3-/-T-/-s-", g.to_rle());
+    }
+
+    #[test]
+    fn test_teleporters_only_pair_within_the_same_channel() {
+        /*
+         * s -> s
+         * T    T
+         * T    T
+         * -    -
+         */
+        let mut g = Grid::new(1, 4);
+        g.spawn_particle((0, 0), Particle::from(Sand::new()));
+        g.spawn_particle((0, 1), Particle::from(Teleporter::with_channel(1)));
+        g.spawn_particle((0, 2), Particle::from(Teleporter::with_channel(2)));
+
+        g.update_grid();
+
+        assert_eq!("s/T/T/-", g.to_rle());
+    }
+
+    #[test]
+    fn test_blocked_pair_keeps_the_particle_instead_of_bouncing_it_back() {
+        /*
+         * -s- -> -s-
+         * rTr    rTr
+         * ---    ---
+         * rTr    rTr
+         * -r-    -r-
+         */
+        let mut g = Grid::new(3, 5).with_rand_vertical_velocity_probability(|_| 0);
+        g.spawn_particle((1, 0), Particle::from(Sand::new()));
+        g.spawn_particle((1, 1), Particle::from(Teleporter::new()));
+        g.spawn_particle((1, 3), Particle::from(Teleporter::new()));
+        for position in [(0, 1), (2, 1), (0, 3), (2, 3), (1, 4)] {
+            g.spawn_particle(position, Particle::from(Rock::new()));
+        }
+
+        for _ in 0..4 {
+            g.update_grid();
+            assert_eq!("-s-/rTr/3-/rTr/-r-", g.to_rle());
+        }
+    }
+}
@@ -0,0 +1,110 @@
+use crate::component::grid::GridAccess;
+
+use super::particle::{ORTHOGONAL_NEIGHBORS, Particle, ParticleKind};
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Virus {
+    lifetime: u8,
+}
+
+impl Default for Virus {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Virus {
+    pub fn new() -> Self {
+        Self::with_lifetime(20)
+    }
+
+    pub fn with_lifetime(lifetime: u8) -> Self {
+        Self { lifetime }
+    }
+
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        let cycle = grid.cycle();
+        let candidates: Vec<usize> = ORTHOGONAL_NEIGHBORS
+            .into_iter()
+            .filter_map(|offset| grid.get_neighbor_index(position, offset).ok())
+            .filter(|&i| {
+                grid.get_cell(i)
+                    .particle
+                    .as_ref()
+                    .is_some_and(|p| !p.is_solid() && !matches!(p.kind, ParticleKind::Virus(..)))
+            })
+            .collect();
+        if !candidates.is_empty() {
+            let index = candidates[grid.particle_seed() as usize % candidates.len()];
+            if let Some(seed) = grid.get_cell(index).particle.as_ref().map(|p| p.seed) {
+                grid.place_particle(index, Particle::from(Virus::new()).with_seed(seed));
+                grid.get_cell_mut(index).cycle = cycle;
+            }
+        }
+
+        let cell = grid.get_cell_mut(grid.to_index(position));
+        if let Some(p) = &mut cell.particle {
+            match self.lifetime {
+                0 => p.health = 0,
+                lifetime => p.kind = ParticleKind::from(Virus::with_lifetime(lifetime - 1)),
+            }
+            cell.cycle = cycle;
+        }
+        grid.activate_window(position);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::{Grid, GridAccess},
+        particles::{particle::Particle, rock::Rock, sand::Sand, virus::Virus},
+    };
+    use pretty_assertions::assert_eq;
+
+    #[test]
+    fn test_virus_spreads_through_sand_and_dies_out() {
+        /*
+         * sss -> vvv -> ---
+         * svs    vvv    ---
+         * sss    vvv    ---
+         */
+        let mut g = Grid::new(3, 3);
+        for y in 0..3 {
+            for x in 0..3 {
+                g.spawn_particle((x, y), Particle::from(Sand::new()));
+            }
+        }
+        g.despawn_particle((1, 1));
+        g.spawn_particle((1, 1), Particle::from(Virus::new()));
+
+        for _ in 0..10 {
+            g.update_grid();
+        }
+        assert_eq!("3v/3v/3v", g.to_rle());
+
+        for _ in 0..30 {
+            g.update_grid();
+        }
+        assert_eq!("3-/3-/3-", g.to_rle());
+    }
+
+    #[test]
+    fn test_rock_is_immune_to_virus() {
+        /*
+         * rvr -> rvr
+         */
+        let mut g = Grid::new(3, 1);
+        g.spawn_particle((0, 0), Particle::from(Rock::new()));
+        g.spawn_particle((1, 0), Particle::from(Virus::new()));
+        g.spawn_particle((2, 0), Particle::from(Rock::new()));
+
+        g.update_grid();
+
+        assert_eq!("rvr", g.to_rle());
+        assert_eq!(
+            Some(Particle::from(Virus::with_lifetime(19))),
+            g.get_cell(1).particle
+        );
+    }
+}
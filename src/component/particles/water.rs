@@ -1,9 +1,12 @@
-use super::particle::{Particle, ParticleKind};
-use crate::component::grid::GridAccess;
+use bevy::color::Color;
+
+use super::particle::{ORTHOGONAL_NEIGHBORS, Particle, ParticleKind};
+use crate::component::grid::{GridAccess, SimEvent};
 
 #[derive(Clone, PartialEq, Debug)]
 pub struct Water {
     pub solvant_capacity: u8,
+    pub tint: Option<Color>,
 }
 
 impl Default for Water {
@@ -20,35 +23,52 @@ impl Water {
     pub fn with_capacity(capacity: u8) -> Self {
         Self {
             solvant_capacity: capacity,
+            tint: None,
         }
     }
 
+    pub fn with_tint(mut self, tint: Color) -> Self {
+        self.tint = Some(tint);
+        self
+    }
+
     pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
-        dissolve_salt(grid, self.solvant_capacity, position);
+        dissolve_salt(grid, self, position);
     }
 }
 
-fn dissolve_salt<T: GridAccess>(grid: &mut T, capacity: u8, position: (usize, usize)) -> bool {
-    for offset in [(0, -1), (-1, 0), (1, 0), (0, 1)] {
+fn dissolve_salt<T: GridAccess>(grid: &mut T, water: &Water, position: (usize, usize)) -> bool {
+    let capacity = water.solvant_capacity;
+    for offset in ORTHOGONAL_NEIGHBORS {
         if let Ok(i) = grid.get_neighbor_index(position, offset)
             && let Some(p) = &grid.get_cell(i).particle
             && let ParticleKind::Salt(..) = p.kind
             && 0 < capacity
             && 0 < p.health
         {
+            if !grid.reacts() {
+                grid.activate_window(position);
+                return false;
+            }
             let cycle = grid.cycle();
             let cell = grid.get_cell_mut(i);
             if let Some(particle) = &mut cell.particle {
                 particle.health = 0;
                 cell.cycle = cycle;
             }
+            grid.push_event(SimEvent::Dissolve {
+                position: grid.to_position(i),
+            });
             let cell = grid.get_cell_mut(grid.to_index(position));
             if let Some(particle) = &cell.particle {
                 cell.particle = Some(
-                    Particle::from(Water::with_capacity(capacity - 1))
-                        .with_seed(particle.seed)
-                        .with_velocity(particle.velocity)
-                        .with_health(particle.health),
+                    Particle::from(Water {
+                        solvant_capacity: capacity - 1,
+                        ..water.clone()
+                    })
+                    .with_seed(particle.seed)
+                    .with_velocity(particle.velocity)
+                    .with_health(particle.health),
                 );
                 cell.cycle = cycle;
                 grid.activate_window(position);
@@ -62,7 +82,7 @@ fn dissolve_salt<T: GridAccess>(grid: &mut T, capacity: u8, position: (usize, us
 #[cfg(test)]
 mod tests {
     use crate::component::{
-        grid::{Cell, Grid, GridAccess},
+        grid::{Cell, Grid, GridAccess, Random, SimEvent},
         particles::{
             particle::{Particle, ParticleKind},
             rock::Rock,
@@ -72,6 +92,34 @@ mod tests {
         },
     };
     use pretty_assertions::assert_eq;
+    use std::sync::{Arc, Mutex};
+
+    #[test]
+    fn test_water_surrounded_by_salts_dissolves_the_left_one_first() {
+        /*
+         * SwS -> (S)wS
+         * rSr     rSr
+         */
+        let mut g = Grid::new(3, 2);
+        g.spawn_particle((1, 0), Particle::from(Water::new()));
+        for position in [(0, 0), (2, 0), (1, 1)] {
+            g.spawn_particle(position, Particle::from(Salt::new()));
+        }
+        g.spawn_particle((0, 1), Particle::from(Rock::new()));
+        g.spawn_particle((2, 1), Particle::from(Rock::new()));
+
+        g.update_grid();
+
+        let health = |position| {
+            g.get_cell(g.to_index(position))
+                .particle
+                .as_ref()
+                .map(|p| p.health)
+        };
+        assert_eq!(Some(0), health((0, 0)));
+        assert_eq!(Some(255), health((2, 0)));
+        assert_eq!(Some(255), health((1, 1)));
+    }
 
     #[test]
     fn test_dissolving_particle_counts_as_being_simulated() {
@@ -98,6 +146,27 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_water_dissolving_salt_fires_a_dissolve_event() {
+        /*
+         * w -> w
+         * S    (S)
+         */
+        let fired = Arc::new(Mutex::new(vec![]));
+        let mut g = Grid::new(1, 2);
+        g.spawn_particle((0, 0), Particle::from(Water::new()));
+        g.spawn_particle((0, 1), Particle::from(Salt::new()));
+        let events = fired.clone();
+        g.on_event(Box::new(move |e| events.lock().unwrap().push(e)));
+
+        g.update_grid();
+
+        assert_eq!(
+            vec![SimEvent::Dissolve { position: (0, 1) }],
+            *fired.lock().unwrap()
+        );
+    }
+
     #[test]
     fn test_water_dissolve_neighboring_salts() {
         /*
@@ -145,6 +214,24 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_lower_reaction_rate_slows_down_dissolving() {
+        let dissolved_in_one_cycle = |reaction_rate: f32| {
+            let mut g = Grid::from_rle("wSwSwSwSwSwSwSwS/16r")
+                .unwrap()
+                .with_random(Random::new().with_rng_seed(7))
+                .with_reaction_rate(reaction_rate);
+            g.update_grid();
+            g.drain_events()
+                .iter()
+                .filter(|e| matches!(e, SimEvent::Dissolve { .. }))
+                .count()
+        };
+
+        assert_eq!(8, dissolved_in_one_cycle(1.));
+        assert_eq!(5, dissolved_in_one_cycle(0.5));
+    }
+
     #[test]
     fn test_water_can_only_dissolve_three_salt_particles() {
         let mut g = Grid::new(1, 5).with_rand_vertical_velocity_probability(|_| 0);
@@ -0,0 +1,137 @@
+use crate::component::grid::GridAccess;
+
+use super::particle::{ORTHOGONAL_NEIGHBORS, ParticleKind};
+
+#[derive(Clone, PartialEq, Debug)]
+pub enum WireState {
+    Off,
+    On,
+    Cooling,
+}
+
+#[derive(Clone, PartialEq, Debug)]
+pub struct Wire {
+    pub state: WireState,
+}
+
+impl Default for Wire {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl Wire {
+    pub fn new() -> Self {
+        Self {
+            state: WireState::Off,
+        }
+    }
+
+    pub fn powered() -> Self {
+        Self {
+            state: WireState::On,
+        }
+    }
+
+    pub fn is_on(&self) -> bool {
+        self.state == WireState::On
+    }
+
+    pub fn update<T: GridAccess>(&self, grid: &mut T, position: (usize, usize)) {
+        let next_state = match self.state {
+            WireState::Off => return,
+            WireState::On => {
+                for offset in ORTHOGONAL_NEIGHBORS {
+                    if let Ok(index) = grid.get_neighbor_index(position, offset) {
+                        Self::set_state(grid, index, WireState::On);
+                    }
+                }
+                WireState::Cooling
+            }
+            WireState::Cooling => WireState::Off,
+        };
+        Self::set_state(grid, grid.to_index(position), next_state);
+        grid.activate_window(position);
+    }
+
+    fn set_state<T: GridAccess>(grid: &mut T, index: usize, state: WireState) {
+        let cycle = grid.cycle();
+        let cell = grid.get_cell_mut(index);
+        if let Some(p) = &mut cell.particle
+            && let ParticleKind::Wire(wire) = &mut p.kind
+            && (state != WireState::On || (wire.state == WireState::Off && cell.cycle != cycle))
+        {
+            wire.state = state;
+            cell.cycle = cycle;
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::component::{
+        grid::{Grid, GridAccess},
+        particles::{
+            particle::{Particle, ParticleKind},
+            rock::Rock,
+            wire::{Wire, WireState},
+        },
+    };
+    use pretty_assertions::assert_eq;
+
+    fn wire_states(g: &Grid) -> Vec<Option<WireState>> {
+        g.get_cells()
+            .iter()
+            .map(|c| match &c.particle {
+                Some(Particle {
+                    kind: ParticleKind::Wire(w),
+                    ..
+                }) => Some(w.state.clone()),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_wire_pulse_travels_down_a_wire_chain_one_cell_per_cycle() {
+        /*
+         * I--- -> cI-- -> -cI- -> --cI -> ---c -> ----
+         */
+        use WireState::{Cooling, Off, On};
+        let mut g = Grid::new(4, 1);
+        g.spawn_particle((0, 0), Particle::from(Wire::powered()));
+        for x in 1..4 {
+            g.spawn_particle((x, 0), Particle::from(Wire::new()));
+        }
+
+        let expected = [
+            [Cooling, On, Off, Off],
+            [Off, Cooling, On, Off],
+            [Off, Off, Cooling, On],
+            [Off, Off, Off, Cooling],
+            [Off, Off, Off, Off],
+        ];
+        for states in expected {
+            g.update_grid();
+            assert_eq!(
+                states.into_iter().map(Some).collect::<Vec<_>>(),
+                wire_states(&g)
+            );
+        }
+    }
+
+    #[test]
+    fn test_wire_only_powers_neighboring_wires() {
+        /*
+         * rIr -> rcr
+         */
+        let mut g = Grid::new(3, 1);
+        g.spawn_particle((0, 0), Particle::from(Rock::new()));
+        g.spawn_particle((1, 0), Particle::from(Wire::powered()));
+        g.spawn_particle((2, 0), Particle::from(Rock::new()));
+
+        g.update_grid();
+
+        assert_eq!(vec![None, Some(WireState::Cooling), None], wire_states(&g));
+    }
+}
@@ -0,0 +1,152 @@
+use std::{
+    sync::{
+        Arc, Mutex,
+        mpsc::{self, RecvTimeoutError, Sender},
+    },
+    thread::{self, JoinHandle},
+    time::{Duration, Instant},
+};
+
+use bevy::ecs::resource::Resource;
+
+use super::grid::{Cell, Grid, GridAccess};
+
+pub type SimCommand = Box<dyn FnOnce(&mut Grid) + Send>;
+
+struct Snapshot {
+    cells: Vec<Cell>,
+    cycle: u32,
+    fresh: bool,
+}
+
+#[derive(Resource)]
+pub struct SimThread {
+    sender: Option<Sender<SimCommand>>,
+    snapshot: Arc<Mutex<Snapshot>>,
+    handle: Option<JoinHandle<()>>,
+}
+
+impl SimThread {
+    pub fn spawn(mut grid: Grid, period: Duration) -> Self {
+        let (sender, receiver) = mpsc::channel::<SimCommand>();
+        let snapshot = Arc::new(Mutex::new(Snapshot {
+            cells: grid.get_cells().clone(),
+            cycle: grid.cycle(),
+            fresh: false,
+        }));
+        let shared = snapshot.clone();
+        let handle = thread::spawn(move || {
+            loop {
+                let deadline = Instant::now() + period;
+                grid.update_grid();
+                if let Ok(mut s) = shared.lock() {
+                    s.cells.clone_from(grid.get_cells());
+                    s.cycle = grid.cycle();
+                    s.fresh = true;
+                }
+                loop {
+                    match receiver.recv_timeout(deadline.saturating_duration_since(Instant::now()))
+                    {
+                        Ok(command) => command(&mut grid),
+                        Err(RecvTimeoutError::Timeout) => break,
+                        Err(RecvTimeoutError::Disconnected) => return,
+                    }
+                }
+            }
+        });
+        Self {
+            sender: Some(sender),
+            snapshot,
+            handle: Some(handle),
+        }
+    }
+
+    pub fn send(&self, command: impl FnOnce(&mut Grid) + Send + 'static) {
+        if let Some(sender) = &self.sender {
+            let _ = sender.send(Box::new(command));
+        }
+    }
+
+    pub fn cycle(&self) -> u32 {
+        match self.snapshot.lock() {
+            Ok(s) => s.cycle,
+            Err(_) => 0,
+        }
+    }
+
+    pub fn read_snapshot(&self, grid: &mut Grid) -> bool {
+        match self.snapshot.lock() {
+            Ok(mut s) if s.fresh => {
+                s.fresh = false;
+                grid.restore_cells(&s.cells, s.cycle).is_ok()
+            }
+            _ => false,
+        }
+    }
+}
+
+impl Drop for SimThread {
+    fn drop(&mut self) {
+        self.sender = None;
+        if let Some(handle) = self.handle.take() {
+            let _ = handle.join();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use pretty_assertions::assert_eq;
+
+    use crate::component::particles::{particle::ParticleKind, sand::Sand};
+
+    use super::*;
+
+    fn wait_for_cycle(sim: &SimThread, cycle: u32) {
+        while sim.cycle() < cycle {
+            thread::yield_now();
+        }
+    }
+
+    #[test]
+    fn test_sim_thread_updates_the_grid_in_the_background() {
+        /*
+         * s  -> -
+         * -     s
+         */
+        let mut g = Grid::new(1, 2);
+        g.spawn_brush((0, 0), 1, Some(&ParticleKind::from(Sand::new())));
+        let sim = SimThread::spawn(g, Duration::ZERO);
+
+        wait_for_cycle(&sim, 2);
+        let mut mirror = Grid::new(1, 2);
+        assert!(sim.read_snapshot(&mut mirror));
+
+        assert_eq!(None, mirror.get_cell(0).particle);
+        assert!(mirror.get_cell(1).particle.is_some());
+    }
+
+    #[test]
+    fn test_sim_thread_applies_forwarded_edits() {
+        let sim = SimThread::spawn(Grid::new(1, 2), Duration::ZERO);
+
+        sim.send(|g| g.spawn_brush((0, 1), 1, Some(&ParticleKind::from(Sand::new()))));
+        let mut mirror = Grid::new(1, 2);
+        while mirror.get_cell(1).particle.is_none() {
+            sim.read_snapshot(&mut mirror);
+        }
+
+        assert_eq!(None, mirror.get_cell(0).particle);
+    }
+
+    #[test]
+    fn test_sim_thread_snapshot_is_read_once() {
+        let sim = SimThread::spawn(Grid::new(1, 1), Duration::from_secs(60));
+
+        wait_for_cycle(&sim, 1);
+        let mut mirror = Grid::new(1, 1);
+
+        assert!(sim.read_snapshot(&mut mirror));
+        assert!(!sim.read_snapshot(&mut mirror));
+    }
+}